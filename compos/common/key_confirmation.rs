@@ -0,0 +1,118 @@
+/*
+ * Copyright (C) 2024 The Android Open Source Project
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+
+//! Key confirmation for the RPC channel between the host and the CompOS VM.
+//!
+//! Once a channel is established, each end sends an HMAC-SHA256 tag computed with the shared
+//! session key over the channel transcript, and checks the tag received from its peer. Each role
+//! uses a distinct label so that a tag can't be reflected back to its sender.
+
+use anyhow::{bail, Result};
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+
+/// Length in bytes of a key confirmation tag.
+pub const TAG_LENGTH: usize = 32;
+
+/// The end of the channel computing or checking a tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    /// The host side, which connects to the VM.
+    Client,
+    /// The CompOS VM side, which accepts the connection.
+    Server,
+}
+
+impl Role {
+    fn label(self) -> &'static [u8] {
+        match self {
+            Role::Client => b"compos key confirmation: client",
+            Role::Server => b"compos key confirmation: server",
+        }
+    }
+}
+
+/// Computes the tag that `role` sends to its peer to confirm that it holds `key`.
+pub fn confirmation_tag(key: &[u8], role: Role, transcript: &[u8]) -> Result<[u8; TAG_LENGTH]> {
+    let mut data = Vec::with_capacity(role.label().len() + transcript.len());
+    data.extend_from_slice(role.label());
+    data.extend_from_slice(transcript);
+    hmac_sha256(key, &data)
+}
+
+/// Checks that `tag` was sent by a peer acting as `role` and holding `key`.
+pub fn verify_confirmation_tag(
+    key: &[u8],
+    role: Role,
+    transcript: &[u8],
+    tag: &[u8],
+) -> Result<()> {
+    let expected = confirmation_tag(key, role, transcript)?;
+    // memcmp::eq panics on slices of different lengths, and the length isn't secret.
+    if tag.len() != expected.len() || !memcmp::eq(&expected, tag) {
+        bail!("Key confirmation failed for {role:?}");
+    }
+    Ok(())
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<[u8; TAG_LENGTH]> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+    let mut tag = [0; TAG_LENGTH];
+    let len = signer.sign(&mut tag)?;
+    if len != TAG_LENGTH {
+        bail!("Unexpected HMAC-SHA256 length {len}");
+    }
+    Ok(tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &[u8] = b"compos session key";
+    const TRANSCRIPT: &[u8] = b"transcript";
+
+    #[test]
+    fn confirmation_succeeds_with_same_key() -> Result<()> {
+        let client_tag = confirmation_tag(KEY, Role::Client, TRANSCRIPT)?;
+        let server_tag = confirmation_tag(KEY, Role::Server, TRANSCRIPT)?;
+
+        verify_confirmation_tag(KEY, Role::Client, TRANSCRIPT, &client_tag)?;
+        verify_confirmation_tag(KEY, Role::Server, TRANSCRIPT, &server_tag)?;
+        Ok(())
+    }
+
+    #[test]
+    fn confirmation_fails_with_mismatched_key() -> Result<()> {
+        let tag = confirmation_tag(b"another key", Role::Client, TRANSCRIPT)?;
+
+        assert!(verify_confirmation_tag(KEY, Role::Client, TRANSCRIPT, &tag).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn confirmation_fails_with_reflected_tag() -> Result<()> {
+        let tag = confirmation_tag(KEY, Role::Client, TRANSCRIPT)?;
+
+        assert!(verify_confirmation_tag(KEY, Role::Server, TRANSCRIPT, &tag).is_err());
+        assert!(verify_confirmation_tag(KEY, Role::Client, TRANSCRIPT, &tag[..16]).is_err());
+        Ok(())
+    }
+}
@@ -18,6 +18,7 @@
 
 pub mod binder;
 pub mod compos_client;
+pub mod key_confirmation;
 pub mod odrefresh;
 pub mod timeouts;
 
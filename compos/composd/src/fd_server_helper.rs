@@ -16,7 +16,7 @@
 
 //! A helper library to start a fd_server.
 
-use anyhow::{Context, Result};
+use anyhow::{bail, Context, Result};
 use log::{debug, warn};
 use minijail::Minijail;
 use nix::fcntl::OFlag;
@@ -28,6 +28,9 @@ use std::path::Path;
 
 const FD_SERVER_BIN: &str = "/apex/com.android.virt/bin/fd_server";
 
+/// Maximum number of FDs a single `fd_server` may serve.
+const MAX_SERVED_FDS: usize = 64;
+
 /// Config for starting a `FdServer`
 #[derive(Default)]
 pub struct FdServerConfig {
@@ -44,12 +47,27 @@ pub struct FdServerConfig {
 impl FdServerConfig {
     /// Creates a `FdServer` based on the current config.
     pub fn into_fd_server(self) -> Result<FdServer> {
+        self.validate()?;
         let (ready_read_fd, ready_write_fd) = create_pipe()?;
         let fd_server_jail = self.do_spawn_fd_server(ready_write_fd)?;
         wait_for_fd_server_ready(ready_read_fd)?;
         Ok(FdServer { jailed_process: fd_server_jail })
     }
 
+    fn validate(&self) -> Result<()> {
+        let count = self.ro_file_fds.len()
+            + self.rw_file_fds.len()
+            + self.ro_dir_fds.len()
+            + self.rw_dir_fds.len();
+        if count == 0 {
+            bail!("No FD to serve");
+        }
+        if count > MAX_SERVED_FDS {
+            bail!("Too many FDs to serve: {count} (max {MAX_SERVED_FDS})");
+        }
+        Ok(())
+    }
+
     fn do_spawn_fd_server(self, ready_file: File) -> Result<Minijail> {
         let mut inheritable_fds = Vec::new();
         let mut args = vec![FD_SERVER_BIN.to_string()];
@@ -119,3 +137,34 @@ fn wait_for_fd_server_ready(mut ready_fd: File) -> Result<()> {
     debug!("fd_server is ready");
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fds(count: usize) -> Result<Vec<OwnedFd>> {
+        (0..count).map(|_| Ok(File::open("/dev/null")?.into())).collect()
+    }
+
+    #[test]
+    fn config_without_fds_is_rejected() {
+        assert!(FdServerConfig::default().validate().is_err());
+    }
+
+    #[test]
+    fn config_with_too_many_fds_is_rejected() -> Result<()> {
+        let config = FdServerConfig {
+            ro_file_fds: fds(MAX_SERVED_FDS / 2)?,
+            rw_dir_fds: fds(MAX_SERVED_FDS / 2 + 1)?,
+            ..Default::default()
+        };
+        assert!(config.validate().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn config_within_fd_budget_is_accepted() -> Result<()> {
+        let config = FdServerConfig { rw_dir_fds: fds(MAX_SERVED_FDS)?, ..Default::default() };
+        config.validate()
+    }
+}
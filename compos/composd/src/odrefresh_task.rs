@@ -41,8 +41,13 @@ use std::os::fd::AsFd;
 use std::os::unix::fs::OpenOptionsExt;
 use std::os::unix::io::{AsRawFd, OwnedFd};
 use std::path::Path;
+use std::sync::mpsc::{self, RecvTimeoutError};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often to log that odrefresh is still running in the VM.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(60);
 
 #[derive(Clone)]
 pub struct OdrefreshTask {
@@ -216,12 +221,41 @@ fn run_in_vm(
         zygoteArch: zygote_arch,
         systemServerCompilerFilter: system_server_compiler_filter,
     };
-    let exit_code = service.odrefresh(&args)?;
+    let exit_code = with_progress_reports(
+        PROGRESS_INTERVAL,
+        || service.odrefresh(&args),
+        |elapsed| info!("odrefresh still running in the VM after {}s", elapsed.as_secs()),
+    )?;
 
     drop(fd_server_raii);
     ExitCode::from_i32(exit_code.into())
 }
 
+/// Runs `work` on the current thread, calling `report` with the elapsed time every `interval`
+/// until it completes.
+///
+/// The VM doesn't report the progress of the compilation, so this at least shows that a long
+/// compilation is still alive.
+fn with_progress_reports<T>(
+    interval: Duration,
+    work: impl FnOnce() -> T,
+    mut report: impl FnMut(Duration) + Send,
+) -> T {
+    let start = Instant::now();
+    let (done_sender, done_receiver) = mpsc::channel::<()>();
+    thread::scope(|scope| {
+        scope.spawn(move || loop {
+            match done_receiver.recv_timeout(interval) {
+                Err(RecvTimeoutError::Timeout) => report(start.elapsed()),
+                Ok(()) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        });
+        let result = work();
+        drop(done_sender);
+        result
+    })
+}
+
 /// Enable fs-verity to output artifacts according to compos.info in the pending directory. Any
 /// error before the completion will just abort, leaving the previous files enabled.
 fn enable_fsverity_to_all() -> Result<()> {
@@ -257,3 +291,36 @@ fn open_dir(path: &Path) -> Result<OwnedFd> {
             .with_context(|| format!("Failed to open {:?} directory as path fd", path))?,
     ))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn progress_is_reported_in_order_until_completion() {
+        let mut reports = Vec::new();
+
+        let result = with_progress_reports(
+            Duration::from_millis(10),
+            || {
+                thread::sleep(Duration::from_millis(55));
+                42
+            },
+            |elapsed| reports.push(elapsed),
+        );
+
+        assert_eq!(result, 42);
+        assert!(!reports.is_empty());
+        assert!(reports.windows(2).all(|w| w[0] < w[1]), "Unordered reports: {reports:?}");
+        assert!(reports.iter().all(|elapsed| *elapsed >= Duration::from_millis(10)));
+    }
+
+    #[test]
+    fn no_progress_is_reported_for_quick_work() {
+        let mut reports = Vec::new();
+
+        with_progress_reports(Duration::from_secs(60), || (), |elapsed| reports.push(elapsed));
+
+        assert!(reports.is_empty());
+    }
+}
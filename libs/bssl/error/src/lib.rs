@@ -43,6 +43,15 @@ pub enum Error {
 
     /// Unimplemented operation.
     Unimplemented,
+
+    /// The signature doesn't have the size expected for the key.
+    InvalidSignatureLength,
+
+    /// Failed to decode a CBOR item.
+    CborDecodingFailed,
+
+    /// The private key doesn't have the size expected for the curve.
+    InvalidPrivateKeyLength,
 }
 
 impl fmt::Display for Error {
@@ -57,6 +66,13 @@ impl fmt::Display for Error {
                 write!(f, "An error occurred when interacting with the coset crate")
             }
             Self::Unimplemented => write!(f, "Unimplemented operation"),
+            Self::InvalidSignatureLength => {
+                write!(f, "The signature doesn't have the size expected for the key")
+            }
+            Self::CborDecodingFailed => write!(f, "Failed to decode the CBOR item"),
+            Self::InvalidPrivateKeyLength => {
+                write!(f, "The private key doesn't have the size expected for the curve")
+            }
         }
     }
 }
@@ -76,18 +92,34 @@ pub enum ApiName {
     BN_new,
     BN_bin2bn,
     BN_bn2bin_padded,
+    CBB_add_bytes,
+    CBB_add_u8,
+    CBB_add_u16,
+    CBB_add_u32,
+    CBB_add_u64,
     CBB_flush,
     CBB_len,
+    CBS_get_bytes,
+    CBS_get_u8,
+    CBS_get_u16,
+    CBS_get_u32,
+    CBS_get_u64,
     EC_GROUP_new_by_curve_name,
     EC_KEY_check_key,
+    EC_KEY_derive_from_secret,
     EC_KEY_generate_key,
     EC_KEY_get0_group,
+    EC_KEY_get0_private_key,
     EC_KEY_get0_public_key,
     EC_KEY_marshal_private_key,
     EC_KEY_parse_private_key,
     EC_KEY_new_by_curve_name,
+    EC_KEY_set_private_key,
+    EC_KEY_set_public_key,
     EC_KEY_set_public_key_affine_coordinates,
     EC_POINT_get_affine_coordinates,
+    EC_POINT_mul,
+    EC_POINT_new,
     ECDSA_SIG_from_bytes,
     ECDSA_SIG_new,
     ECDSA_SIG_set0,
@@ -99,16 +131,28 @@ pub enum ApiName {
     EVP_AEAD_CTX_open,
     EVP_AEAD_CTX_seal,
     EVP_Digest,
+    EVP_DigestFinal_ex,
+    EVP_DigestInit_ex,
+    EVP_DigestUpdate,
+    EVP_MD_CTX_copy_ex,
     EVP_MD_CTX_new,
+    EVP_PKEY_get1_EC_KEY,
     EVP_PKEY_new,
     EVP_PKEY_new_raw_public_key,
     EVP_PKEY_set1_EC_KEY,
     EVP_marshal_public_key,
+    EVP_parse_public_key,
     EVP_DigestVerify,
     EVP_DigestVerifyInit,
     HKDF,
     HMAC,
+    HMAC_CTX_new,
+    HMAC_Final,
+    HMAC_Init_ex,
+    HMAC_Update,
     i2d_ECDSA_SIG,
     RAND_bytes,
+    RSA_public_key_from_bytes,
+    RSA_verify,
     SHA256,
 }
@@ -14,7 +14,12 @@
 
 //! Helpers for using BoringSSL CBB (crypto byte builder) objects.
 
-use bssl_sys::{CBB_init_fixed, CBB};
+use crate::util::check_int_result;
+use bssl_avf_error::{ApiName, Result};
+use bssl_sys::{
+    CBB_add_bytes, CBB_add_u16, CBB_add_u32, CBB_add_u64, CBB_add_u8, CBB_flush, CBB_init_fixed,
+    CBB_len, CBB,
+};
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
 
@@ -38,6 +43,103 @@ impl<'a> CbbFixed<'a> {
         let cbb = unsafe { cbb.assume_init() };
         Self { cbb, _buffer: PhantomData }
     }
+
+    /// Flushes the CBB and returns the number of bytes written to the buffer so far.
+    pub fn written_len(&mut self) -> Result<usize> {
+        // SAFETY: The CBB pointer is a valid pointer initialized with `CBB_init_fixed()`.
+        check_int_result(unsafe { CBB_flush(&mut self.cbb) }, ApiName::CBB_flush)?;
+        // SAFETY: The CBB has been flushed, thus it has no active children.
+        Ok(unsafe { CBB_len(&self.cbb) })
+    }
+
+    /// Appends the CBOR encoding of the unsigned integer `value`.
+    pub fn add_cbor_uint(&mut self, value: u64) -> Result<()> {
+        self.add_cbor_header(CborMajorType::UnsignedInt, value)
+    }
+
+    /// Appends the CBOR encoding of the integer `value`, as an unsigned or negative integer.
+    pub fn add_cbor_int(&mut self, value: i64) -> Result<()> {
+        match u64::try_from(value) {
+            Ok(value) => self.add_cbor_header(CborMajorType::UnsignedInt, value),
+            // A negative integer -1 - n is encoded as n, which is the bitwise NOT of the value.
+            Err(_) => self.add_cbor_header(CborMajorType::NegativeInt, !value as u64),
+        }
+    }
+
+    /// Appends the CBOR encoding of the byte string `bytes`.
+    pub fn add_cbor_bstr(&mut self, bytes: &[u8]) -> Result<()> {
+        self.add_cbor_header(CborMajorType::ByteString, bytes.len() as u64)?;
+        // SAFETY: The function only reads `bytes` within its bounds and writes to the buffer
+        // managed by the valid `CBB` object.
+        let ret = unsafe { CBB_add_bytes(&mut self.cbb, bytes.as_ptr(), bytes.len()) };
+        check_int_result(ret, ApiName::CBB_add_bytes)
+    }
+
+    /// Appends the header of a CBOR array of `len` items, which must be appended next.
+    pub fn add_cbor_array_header(&mut self, len: u64) -> Result<()> {
+        self.add_cbor_header(CborMajorType::Array, len)
+    }
+
+    /// Appends the header of a CBOR map of `len` key-value pairs, which must be appended next.
+    pub fn add_cbor_map_header(&mut self, len: u64) -> Result<()> {
+        self.add_cbor_header(CborMajorType::Map, len)
+    }
+
+    /// Appends a CBOR item header with the shortest encoding of `value` as its argument.
+    ///
+    /// See RFC 8949 Section 3.
+    fn add_cbor_header(&mut self, major_type: CborMajorType, value: u64) -> Result<()> {
+        let major_type = (major_type as u8) << 5;
+        if let Ok(value @ 0..=23) = u8::try_from(value) {
+            self.add_u8(major_type | value)
+        } else if let Ok(value) = u8::try_from(value) {
+            self.add_u8(major_type | 24)?;
+            self.add_u8(value)
+        } else if let Ok(value) = u16::try_from(value) {
+            self.add_u8(major_type | 25)?;
+            self.add_u16(value)
+        } else if let Ok(value) = u32::try_from(value) {
+            self.add_u8(major_type | 26)?;
+            self.add_u32(value)
+        } else {
+            self.add_u8(major_type | 27)?;
+            self.add_u64(value)
+        }
+    }
+
+    fn add_u8(&mut self, value: u8) -> Result<()> {
+        // SAFETY: The function only writes to the buffer managed by the valid `CBB` object.
+        let ret = unsafe { CBB_add_u8(&mut self.cbb, value) };
+        check_int_result(ret, ApiName::CBB_add_u8)
+    }
+
+    fn add_u16(&mut self, value: u16) -> Result<()> {
+        // SAFETY: The function only writes to the buffer managed by the valid `CBB` object.
+        let ret = unsafe { CBB_add_u16(&mut self.cbb, value) };
+        check_int_result(ret, ApiName::CBB_add_u16)
+    }
+
+    fn add_u32(&mut self, value: u32) -> Result<()> {
+        // SAFETY: The function only writes to the buffer managed by the valid `CBB` object.
+        let ret = unsafe { CBB_add_u32(&mut self.cbb, value) };
+        check_int_result(ret, ApiName::CBB_add_u32)
+    }
+
+    fn add_u64(&mut self, value: u64) -> Result<()> {
+        // SAFETY: The function only writes to the buffer managed by the valid `CBB` object.
+        let ret = unsafe { CBB_add_u64(&mut self.cbb, value) };
+        check_int_result(ret, ApiName::CBB_add_u64)
+    }
+}
+
+/// CBOR major types, as defined in RFC 8949 Section 3.1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum CborMajorType {
+    UnsignedInt = 0,
+    NegativeInt = 1,
+    ByteString = 2,
+    Array = 4,
+    Map = 5,
 }
 
 impl<'a> AsRef<CBB> for CbbFixed<'a> {
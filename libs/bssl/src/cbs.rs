@@ -14,9 +14,17 @@
 
 //! Helpers for using BoringSSL CBS (crypto byte string) objects.
 
-use bssl_sys::{CBS_init, CBS};
+use crate::cbb::CborMajorType;
+use crate::util::check_int_result;
+use bssl_avf_error::{ApiName, Error, Result};
+use bssl_sys::{
+    CBS_data, CBS_get_bytes, CBS_get_u16, CBS_get_u32, CBS_get_u64, CBS_get_u8, CBS_init, CBS_len,
+    CBS,
+};
 use core::marker::PhantomData;
 use core::mem::MaybeUninit;
+use core::slice;
+use log::error;
 
 /// CRYPTO ByteString.
 ///
@@ -40,6 +48,117 @@ impl<'a> Cbs<'a> {
         let cbs = unsafe { cbs.assume_init() };
         Self { cbs, _buffer: PhantomData }
     }
+
+    /// Reads a CBOR unsigned integer.
+    pub fn get_cbor_uint(&mut self) -> Result<u64> {
+        self.get_cbor_header(CborMajorType::UnsignedInt)
+    }
+
+    /// Reads a CBOR unsigned or negative integer.
+    pub fn get_cbor_int(&mut self) -> Result<i64> {
+        let (major_type, value) = self.get_cbor_item_header()?;
+        let negative = match major_type {
+            t if t == CborMajorType::UnsignedInt as u8 => false,
+            t if t == CborMajorType::NegativeInt as u8 => true,
+            t => {
+                error!("Expected a CBOR integer but found major type {t}");
+                return Err(Error::CborDecodingFailed);
+            }
+        };
+        let value = i64::try_from(value).map_err(|_| {
+            error!("CBOR integer with argument {value} doesn't fit in i64");
+            Error::CborDecodingFailed
+        })?;
+        Ok(if negative { -1 - value } else { value })
+    }
+
+    /// Reads a CBOR byte string, returning the slice of the buffer holding its content.
+    pub fn get_cbor_bstr(&mut self) -> Result<&'a [u8]> {
+        let len = self.get_cbor_header(CborMajorType::ByteString)?;
+        let len = usize::try_from(len).map_err(|_| Error::CborDecodingFailed)?;
+        let mut bytes = MaybeUninit::uninit();
+        // SAFETY: The function only reads from the buffer of the valid `CBS` object and sets
+        // `bytes` to point to a subslice of it.
+        let ret = unsafe { CBS_get_bytes(&mut self.cbs, bytes.as_mut_ptr(), len) };
+        check_int_result(ret, ApiName::CBS_get_bytes)?;
+        // SAFETY: `bytes` has just been initialized by `CBS_get_bytes()`.
+        let bytes = unsafe { bytes.assume_init() };
+        // SAFETY: `bytes` points to `len` bytes of the buffer, which is borrowed for `'a`.
+        Ok(unsafe { slice::from_raw_parts(CBS_data(&bytes), CBS_len(&bytes)) })
+    }
+
+    /// Reads the header of a CBOR array, returning its number of items, which follow it.
+    pub fn get_cbor_array_header(&mut self) -> Result<u64> {
+        self.get_cbor_header(CborMajorType::Array)
+    }
+
+    /// Reads the header of a CBOR map, returning its number of key-value pairs, which follow it.
+    pub fn get_cbor_map_header(&mut self) -> Result<u64> {
+        self.get_cbor_header(CborMajorType::Map)
+    }
+
+    /// Reads a CBOR item header of the given major type, returning its argument.
+    fn get_cbor_header(&mut self, major_type: CborMajorType) -> Result<u64> {
+        match self.get_cbor_item_header()? {
+            (t, value) if t == major_type as u8 => Ok(value),
+            (t, _) => {
+                error!("Expected CBOR major type {major_type:?} but found {t}");
+                Err(Error::CborDecodingFailed)
+            }
+        }
+    }
+
+    /// Reads a CBOR item header, returning its major type and argument.
+    ///
+    /// Items of indefinite length are not supported.
+    fn get_cbor_item_header(&mut self) -> Result<(u8, u64)> {
+        let initial_byte = self.get_u8()?;
+        let major_type = initial_byte >> 5;
+        let value = match initial_byte & 0x1f {
+            value @ 0..=23 => value.into(),
+            24 => self.get_u8()?.into(),
+            25 => self.get_u16()?.into(),
+            26 => self.get_u32()?.into(),
+            27 => self.get_u64()?,
+            info => {
+                error!("Unsupported CBOR additional information {info}");
+                return Err(Error::CborDecodingFailed);
+            }
+        };
+        Ok((major_type, value))
+    }
+
+    fn get_u8(&mut self) -> Result<u8> {
+        let mut value = 0;
+        // SAFETY: The function only reads from the buffer of the valid `CBS` object.
+        let ret = unsafe { CBS_get_u8(&mut self.cbs, &mut value) };
+        check_int_result(ret, ApiName::CBS_get_u8)?;
+        Ok(value)
+    }
+
+    fn get_u16(&mut self) -> Result<u16> {
+        let mut value = 0;
+        // SAFETY: The function only reads from the buffer of the valid `CBS` object.
+        let ret = unsafe { CBS_get_u16(&mut self.cbs, &mut value) };
+        check_int_result(ret, ApiName::CBS_get_u16)?;
+        Ok(value)
+    }
+
+    fn get_u32(&mut self) -> Result<u32> {
+        let mut value = 0;
+        // SAFETY: The function only reads from the buffer of the valid `CBS` object.
+        let ret = unsafe { CBS_get_u32(&mut self.cbs, &mut value) };
+        check_int_result(ret, ApiName::CBS_get_u32)?;
+        Ok(value)
+    }
+
+    fn get_u64(&mut self) -> Result<u64> {
+        let mut value = 0;
+        // SAFETY: The function only reads from the buffer of the valid `CBS` object.
+        let ret = unsafe { CBS_get_u64(&mut self.cbs, &mut value) };
+        check_int_result(ret, ApiName::CBS_get_u64)?;
+        Ok(value)
+    }
 }
 
 impl<'a> AsRef<CBS> for Cbs<'a> {
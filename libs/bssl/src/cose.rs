@@ -0,0 +1,48 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Verification of COSE_Sign1 structures.
+
+use crate::digest::Digester;
+use crate::ec_key::EcKey;
+use alloc::vec::Vec;
+use bssl_avf_error::{Error, Result};
+use coset::{iana, Algorithm, CborSerializable, CoseSign1};
+use log::error;
+
+/// Verifies the ECDSA signature of the CBOR-encoded COSE_Sign1 `sign1` with `public_key`.
+///
+/// The digest algorithm is selected from the `alg` of the protected header and the signature is
+/// checked over the `Sig_structure` (see RFC 9052 section 4.4), with an empty external AAD.
+///
+/// Returns the payload of the COSE_Sign1 if the verification succeeds.
+pub fn verify_cose_sign1(public_key: &EcKey, sign1: &[u8]) -> Result<Vec<u8>> {
+    let sign1 = CoseSign1::from_slice(sign1)?;
+    let digester = match &sign1.protected.header.alg {
+        Some(Algorithm::Assigned(iana::Algorithm::ES256)) => Digester::sha256(),
+        Some(Algorithm::Assigned(iana::Algorithm::ES384)) => Digester::sha384(),
+        alg => {
+            error!("Unsupported COSE_Sign1 algorithm: {alg:?}");
+            return Err(Error::Unimplemented);
+        }
+    };
+    let aad = &[];
+    sign1.verify_signature(aad, |signature, message| {
+        public_key.ecdsa_verify_cose(signature, &digester.digest(message)?)
+    })?;
+    sign1.payload.ok_or_else(|| {
+        error!("The COSE_Sign1 has no payload");
+        Error::CosetError
+    })
+}
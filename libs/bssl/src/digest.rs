@@ -19,7 +19,8 @@ use alloc::vec;
 use alloc::vec::Vec;
 use bssl_avf_error::{ApiName, Error, Result};
 use bssl_sys::{
-    EVP_Digest, EVP_MD_CTX_free, EVP_MD_CTX_new, EVP_MD_size, EVP_sha256, EVP_sha384, EVP_sha512,
+    EVP_Digest, EVP_DigestFinal_ex, EVP_DigestInit_ex, EVP_DigestUpdate, EVP_MD_CTX_copy_ex,
+    EVP_MD_CTX_free, EVP_MD_CTX_new, EVP_MD_size, EVP_sha256, EVP_sha384, EVP_sha512,
     EVP_MAX_MD_SIZE, EVP_MD, EVP_MD_CTX,
 };
 use core::ptr::{self, NonNull};
@@ -29,7 +30,11 @@ const MAX_DIGEST_SIZE: usize = EVP_MAX_MD_SIZE as usize;
 
 /// Message digester wrapping `EVP_MD`.
 #[derive(Clone, Debug)]
-pub struct Digester(pub(crate) &'static EVP_MD);
+pub struct Digester {
+    pub(crate) md: &'static EVP_MD,
+    size: usize,
+    name: &'static str,
+}
 
 impl Digester {
     /// Returns a `Digester` implementing `SHA-256` algorithm.
@@ -37,9 +42,7 @@ impl Digester {
         // SAFETY: This function does not access any Rust variables and simply returns
         // a pointer to the static variable in BoringSSL.
         let p = unsafe { EVP_sha256() };
-        // SAFETY: The returned pointer should always be valid and points to a static
-        // `EVP_MD`.
-        Self(unsafe { p.as_ref().unwrap() })
+        Self::new(p, "SHA-256")
     }
 
     /// Returns a `Digester` implementing `SHA-384` algorithm.
@@ -47,9 +50,7 @@ impl Digester {
         // SAFETY: This function does not access any Rust variables and simply returns
         // a pointer to the static variable in BoringSSL.
         let p = unsafe { EVP_sha384() };
-        // SAFETY: The returned pointer should always be valid and points to a static
-        // `EVP_MD`.
-        Self(unsafe { p.as_ref().unwrap() })
+        Self::new(p, "SHA-384")
     }
 
     /// Returns a `Digester` implementing `SHA-512` algorithm.
@@ -57,15 +58,26 @@ impl Digester {
         // SAFETY: This function does not access any Rust variables and simply returns
         // a pointer to the static variable in BoringSSL.
         let p = unsafe { EVP_sha512() };
-        // SAFETY: The returned pointer should always be valid and points to a static
-        // `EVP_MD`.
-        Self(unsafe { p.as_ref().unwrap() })
+        Self::new(p, "SHA-512")
+    }
+
+    fn new(p: *const EVP_MD, name: &'static str) -> Self {
+        // SAFETY: The pointer was returned by one of the EVP_* hash functions above, so it should
+        // always be valid and point to a static `EVP_MD`.
+        let md = unsafe { p.as_ref().unwrap() };
+        // SAFETY: `md` points to a static `EVP_MD` from BoringSSL digest.h.
+        let size = unsafe { EVP_MD_size(md) };
+        Self { md, size, name }
     }
 
     /// Returns the digest size in bytes.
     pub fn size(&self) -> usize {
-        // SAFETY: The inner pointer is fetched from EVP_* hash functions in BoringSSL digest.h
-        unsafe { EVP_MD_size(self.0) }
+        self.size
+    }
+
+    /// Returns the name of the digest algorithm, e.g. "SHA-256".
+    pub fn algorithm_name(&self) -> &'static str {
+        self.name
     }
 
     /// Computes the digest of the provided `data`.
@@ -85,7 +97,7 @@ impl Digester {
                     data.len(),
                     out.as_mut_ptr(),
                     &mut out_size,
-                    self.0,
+                    self.md,
                     engine,
                 )
             };
@@ -103,9 +115,21 @@ impl Digester {
 }
 
 /// Message digester context wrapping `EVP_MD_CTX`.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct DigesterContext(NonNull<EVP_MD_CTX>);
 
+impl Clone for DigesterContext {
+    /// Clones the context into a freshly allocated `EVP_MD_CTX`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if BoringSSL fails to allocate or copy the context; use `try_clone()` to handle
+    /// the failure instead.
+    fn clone(&self) -> Self {
+        self.try_clone().expect("Failed to clone the EVP_MD_CTX")
+    }
+}
+
 impl Drop for DigesterContext {
     fn drop(&mut self) {
         // SAFETY: This function frees any resources owned by `EVP_MD_CTX` and resets it to a
@@ -124,6 +148,54 @@ impl DigesterContext {
         NonNull::new(ctx).map(Self).ok_or_else(|| to_call_failed_error(ApiName::EVP_MD_CTX_new))
     }
 
+    /// Returns a new `DigesterContext` owning a copy of this context's state, so that the two
+    /// can be updated and finalized independently.
+    pub fn try_clone(&self) -> Result<Self> {
+        let mut ctx = Self::new()?;
+        // SAFETY: Both contexts were allocated by BoringSSL and are distinct. The function only
+        // reads from `self` and any state previously held by `ctx` is released.
+        let ret = unsafe { EVP_MD_CTX_copy_ex(ctx.as_mut_ptr(), self.0.as_ptr()) };
+        check_int_result(ret, ApiName::EVP_MD_CTX_copy_ex)?;
+        Ok(ctx)
+    }
+
+    /// Sets up the context to compute a digest with the given `digester`, discarding any
+    /// previous state.
+    pub fn init(&mut self, digester: Digester) -> Result<()> {
+        let engine = ptr::null_mut(); // Use the default engine.
+
+        // SAFETY: The context was allocated by BoringSSL and the digester is a valid pointer to a
+        // static `EVP_MD`.
+        let ret = unsafe { EVP_DigestInit_ex(self.as_mut_ptr(), digester.md, engine) };
+        check_int_result(ret, ApiName::EVP_DigestInit_ex)
+    }
+
+    /// Feeds `data` into the digest computation set up by `init()`.
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        // SAFETY: This function only reads `data` within its bounds and the context is valid.
+        let ret =
+            unsafe { EVP_DigestUpdate(self.as_mut_ptr(), data.as_ptr() as *const _, data.len()) };
+        check_int_result(ret, ApiName::EVP_DigestUpdate)
+    }
+
+    /// Returns the digest of all the data fed through `update()` since `init()`.
+    ///
+    /// The context must be initialised again before computing another digest.
+    pub fn finalize(&mut self) -> Result<Vec<u8>> {
+        let mut out = vec![0u8; MAX_DIGEST_SIZE];
+        let mut out_size = 0;
+        // SAFETY: `out` has `MAX_DIGEST_SIZE` bytes of space for write as required in the
+        // BoringSSL spec and the context is valid.
+        let ret = unsafe { EVP_DigestFinal_ex(self.as_mut_ptr(), out.as_mut_ptr(), &mut out_size) };
+        check_int_result(ret, ApiName::EVP_DigestFinal_ex)?;
+        let out_size = usize::try_from(out_size).map_err(|e| {
+            error!("Failed to convert digest size to usize: {:?}", e);
+            Error::InternalError
+        })?;
+        out.truncate(out_size);
+        Ok(out)
+    }
+
     pub(crate) fn as_mut_ptr(&mut self) -> *mut EVP_MD_CTX {
         self.0.as_ptr()
     }
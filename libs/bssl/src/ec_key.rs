@@ -17,6 +17,7 @@
 
 use crate::cbb::CbbFixed;
 use crate::cbs::Cbs;
+use crate::evp::PKey;
 use crate::util::{check_int_result, to_call_failed_error};
 use alloc::vec;
 use alloc::vec::Vec;
@@ -25,11 +26,12 @@ use bssl_sys::{
     i2d_ECDSA_SIG, BN_bin2bn, BN_bn2bin_padded, BN_clear_free, BN_new, CBB_flush, CBB_len,
     ECDSA_SIG_free, ECDSA_SIG_from_bytes, ECDSA_SIG_get0_r, ECDSA_SIG_get0_s, ECDSA_SIG_new,
     ECDSA_SIG_set0, ECDSA_sign, ECDSA_size, ECDSA_verify, EC_GROUP_get_curve_name,
-    EC_GROUP_new_by_curve_name, EC_KEY_check_key, EC_KEY_free, EC_KEY_generate_key,
-    EC_KEY_get0_group, EC_KEY_get0_public_key, EC_KEY_marshal_private_key,
-    EC_KEY_new_by_curve_name, EC_KEY_parse_private_key, EC_KEY_set_public_key_affine_coordinates,
-    EC_POINT_get_affine_coordinates, NID_X9_62_prime256v1, NID_secp384r1, BIGNUM, ECDSA_SIG,
-    EC_GROUP, EC_KEY, EC_POINT,
+    EC_GROUP_new_by_curve_name, EC_KEY_check_key, EC_KEY_derive_from_secret, EC_KEY_free,
+    EC_KEY_generate_key, EC_KEY_get0_group, EC_KEY_get0_private_key, EC_KEY_get0_public_key,
+    EC_KEY_marshal_private_key, EC_KEY_new_by_curve_name, EC_KEY_parse_private_key,
+    EC_KEY_set_private_key, EC_KEY_set_public_key, EC_KEY_set_public_key_affine_coordinates,
+    EC_POINT_free, EC_POINT_get_affine_coordinates, EC_POINT_mul, EC_POINT_new,
+    NID_X9_62_prime256v1, NID_secp384r1, BIGNUM, ECDSA_SIG, EC_GROUP, EC_KEY, EC_POINT,
 };
 use cbor_util::{get_label_value, get_label_value_as_bytes};
 use ciborium::Value;
@@ -170,7 +172,17 @@ impl EcKey {
     /// current `EcKey`.
     ///
     /// Returns Ok(()) if the verification succeeds, otherwise an error will be returned.
+    /// The signature must consist of two coordinates of the key's curve size.
     pub fn ecdsa_verify_cose(&self, signature: &[u8], digest: &[u8]) -> Result<()> {
+        let coord_bytes = self.ec_group()?.affine_coordinate_size()?;
+        if signature.len() != 2 * coord_bytes {
+            error!(
+                "The size of the COSE signature '{}' does not match the expected size '{}'",
+                signature.len(),
+                2 * coord_bytes
+            );
+            return Err(Error::InvalidSignatureLength);
+        }
         let signature = ec_cose_signature_to_der(signature)?;
         self.ecdsa_verify_der(&signature, digest)
     }
@@ -236,6 +248,25 @@ impl EcKey {
         check_int_result(ret, ApiName::EC_KEY_generate_key)
     }
 
+    /// Deterministically derives a private key from the given `seed` with HKDF-SHA256, calculates
+    /// the corresponding public key and stores both in the `EC_KEY`, replacing the current key.
+    ///
+    /// The derived scalar is guaranteed to be in the range [1, n - 1], where n is the order of
+    /// the curve of the current key.
+    pub fn generate_with_seed(&mut self, seed: &[u8]) -> Result<()> {
+        let ec_group = self.ec_group()?;
+        // SAFETY: The function only reads the `EC_GROUP` and the `seed` within its bounds, and
+        // the returned pointer is checked below.
+        let ec_key =
+            unsafe { EC_KEY_derive_from_secret(ec_group.as_ref(), seed.as_ptr(), seed.len()) };
+        let ec_key = NonNull::new(ec_key)
+            .map(Self)
+            .ok_or_else(|| to_call_failed_error(ApiName::EC_KEY_derive_from_secret))?;
+        ec_key.check_key()?;
+        *self = ec_key;
+        Ok(())
+    }
+
     /// Returns the `CoseKey` for the public key.
     pub fn cose_public_key(&self) -> Result<CoseKey> {
         let (x, y) = self.public_key_coordinates()?;
@@ -244,6 +275,13 @@ impl EcKey {
         Ok(key)
     }
 
+    /// Returns the big-endian x and y affine coordinates of the public key, each padded with
+    /// leading zeros to the coordinate size of the key's curve.
+    pub fn public_coords(&self) -> Result<(ZVec, ZVec)> {
+        let (x, y) = self.public_key_coordinates()?;
+        Ok((x.into(), y.into()))
+    }
+
     /// Returns the x and y coordinates of the public key.
     fn public_key_coordinates(&self) -> Result<(Vec<u8>, Vec<u8>)> {
         let ec_group = self.ec_group()?;
@@ -300,6 +338,9 @@ impl EcKey {
     /// Constructs an `EcKey` instance from the provided DER-encoded ECPrivateKey slice.
     ///
     /// Currently, only the EC P-256 curve is supported.
+    ///
+    /// The key is rejected if its private scalar isn't in the range [1, n - 1], where n is the
+    /// order of the curve, or if its public point isn't a valid point of the curve.
     pub fn from_ec_private_key(der_encoded_ec_private_key: &[u8]) -> Result<Self> {
         // SAFETY: This function only returns a pointer to a static object, and the
         // return is checked below.
@@ -321,6 +362,67 @@ impl EcKey {
         Ok(ec_key)
     }
 
+    /// Constructs an `EcKey` instance on the given `curve` from its big-endian private `scalar`,
+    /// and computes the matching public key.
+    ///
+    /// Currently, only the EC P-256 and P-384 curves are supported. The scalar must be padded
+    /// with leading zeros to the size of the curve, as returned by `private_scalar()`, and is
+    /// rejected if it isn't in the range [1, n - 1], where n is the order of the curve.
+    pub fn from_private_scalar(curve: iana::EllipticCurve, scalar: &[u8]) -> Result<Self> {
+        let ec_key = match curve {
+            c if c == P256_CURVE => EcKey::new_p256()?,
+            c if c == P384_CURVE => EcKey::new_p384()?,
+            c => {
+                error!("Only EC P-256 and P-384 curves are supported. Curve: {c:?}");
+                return Err(Error::Unimplemented);
+            }
+        };
+        let group = ec_key.ec_group()?;
+        let expected_len = group.affine_coordinate_size()?;
+        if scalar.len() != expected_len {
+            error!(
+                "The size of the private scalar '{}' does not match the expected size '{}'",
+                scalar.len(),
+                expected_len
+            );
+            return Err(Error::InvalidPrivateKeyLength);
+        }
+        let private_key = BigNum::from_slice(scalar)?;
+        // SAFETY: All the parameters are checked non-null and initialized. The function copies
+        // the scalar and checks that it is in range.
+        let ret = unsafe { EC_KEY_set_private_key(ec_key.0.as_ptr(), private_key.as_ref()) };
+        check_int_result(ret, ApiName::EC_KEY_set_private_key)?;
+
+        let public_key = EcPoint::new(&group)?;
+        // SAFETY: All the parameters are checked non-null and initialized, except for the
+        // optional point, its scalar and `ctx`, which are null.
+        let ret = unsafe {
+            EC_POINT_mul(
+                group.as_ref(),
+                public_key.0.as_ptr(),
+                private_key.as_ref(),
+                ptr::null(),
+                ptr::null(),
+                ptr::null_mut(),
+            )
+        };
+        check_int_result(ret, ApiName::EC_POINT_mul)?;
+        // SAFETY: All the parameters are checked non-null and initialized. The function copies
+        // the point into the `EC_KEY`.
+        let ret = unsafe { EC_KEY_set_public_key(ec_key.0.as_ptr(), public_key.0.as_ptr()) };
+        check_int_result(ret, ApiName::EC_KEY_set_public_key)?;
+        ec_key.check_key()?;
+        Ok(ec_key)
+    }
+
+    /// Constructs an `EcKey` instance from the provided DER-encoded SubjectPublicKeyInfo slice,
+    /// as found in X.509 certificates. Keys of any type other than EC are rejected.
+    pub fn from_subject_public_key_info(der_encoded_spki: &[u8]) -> Result<Self> {
+        let ec_key = PKey::from_subject_public_key_info(der_encoded_spki)?.ec_key()?;
+        ec_key.check_key()?;
+        Ok(ec_key)
+    }
+
     /// Returns the DER-encoded ECPrivateKey structure described in RFC 5915 Section 3:
     ///
     /// https://datatracker.ietf.org/doc/html/rfc5915#section-3
@@ -343,6 +445,26 @@ impl EcKey {
         let len = unsafe { CBB_len(cbb.as_ref()) };
         Ok(buf.get(0..len).ok_or_else(|| to_call_failed_error(ApiName::CBB_len))?.to_vec().into())
     }
+
+    /// Returns the private key as a big-endian scalar, padded with leading zeros to the size of
+    /// the key's curve. The key must hold a private key.
+    pub fn private_scalar(&self) -> Result<ZVec> {
+        let scalar =
+            // SAFETY: This function only reads the `EC_KEY` pointer. The returned pointer, if
+            // non-null, is owned by the `EC_KEY`.
+            unsafe { EC_KEY_get0_private_key(self.0.as_ptr()) };
+        if scalar.is_null() {
+            return Err(to_call_failed_error(ApiName::EC_KEY_get0_private_key));
+        }
+        // For the supported curves, the scalar and the affine coordinates have the same size.
+        let len = self.ec_group()?.affine_coordinate_size()?;
+        let mut buf = Zeroizing::new(vec![0u8; len]);
+        // SAFETY: The function writes at most `buf.len()` bytes to `buf` and only reads the
+        // `BIGNUM`, which is checked non-null above.
+        let ret = unsafe { BN_bn2bin_padded(buf.as_mut_ptr(), buf.len(), scalar) };
+        check_int_result(ret, ApiName::BN_bn2bin_padded)?;
+        Ok(mem::take(&mut *buf).into())
+    }
 }
 
 /// Convert a COSE format (R | S) ECDSA signature to a DER-encoded form.
@@ -468,6 +590,24 @@ impl Drop for EcSignature {
     }
 }
 
+/// Wrapper of an `EC_POINT` object.
+struct EcPoint(NonNull<EC_POINT>);
+
+impl EcPoint {
+    fn new(group: &EcGroup<'_>) -> Result<Self> {
+        // SAFETY: The `EC_GROUP` is valid and the returned pointer is checked below.
+        let point = unsafe { EC_POINT_new(group.as_ref()) };
+        NonNull::new(point).map(Self).ok_or_else(|| to_call_failed_error(ApiName::EC_POINT_new))
+    }
+}
+
+impl Drop for EcPoint {
+    fn drop(&mut self) {
+        // SAFETY: The pointer was allocated by `EC_POINT_new`.
+        unsafe { EC_POINT_free(self.0.as_ptr()) }
+    }
+}
+
 /// Wrapper of an `EC_GROUP` reference.
 struct EcGroup<'a>(&'a EC_GROUP);
 
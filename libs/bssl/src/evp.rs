@@ -15,14 +15,16 @@
 //! Wrappers of the EVP functions in BoringSSL evp.h.
 
 use crate::cbb::CbbFixed;
+use crate::cbs::Cbs;
 use crate::digest::{Digester, DigesterContext};
 use crate::ec_key::EcKey;
 use crate::util::{check_int_result, to_call_failed_error};
 use alloc::vec::Vec;
 use bssl_avf_error::{ApiName, Error, Result};
 use bssl_sys::{
-    CBB_flush, CBB_len, EVP_DigestVerify, EVP_DigestVerifyInit, EVP_PKEY_free, EVP_PKEY_new,
-    EVP_PKEY_new_raw_public_key, EVP_PKEY_set1_EC_KEY, EVP_marshal_public_key, EVP_PKEY,
+    CBB_flush, CBB_len, CBS_len, EVP_DigestVerify, EVP_DigestVerifyInit, EVP_PKEY_free,
+    EVP_PKEY_get1_EC_KEY, EVP_PKEY_id, EVP_PKEY_new, EVP_PKEY_new_raw_public_key,
+    EVP_PKEY_set1_EC_KEY, EVP_marshal_public_key, EVP_parse_public_key, EVP_PKEY, EVP_PKEY_EC,
     EVP_PKEY_ED25519, EVP_PKEY_X25519,
 };
 use cbor_util::{get_label_value, get_label_value_as_bytes};
@@ -97,6 +99,40 @@ impl PKey {
         Ok(buf.get(0..len).ok_or_else(|| to_call_failed_error(ApiName::CBB_len))?.to_vec())
     }
 
+    /// Parses a DER-encoded SubjectPublicKeyInfo structure as specified in RFC 5280 s4.1.2.7,
+    /// rejecting any trailing data.
+    pub fn from_subject_public_key_info(der_encoded_spki: &[u8]) -> Result<Self> {
+        let mut cbs = Cbs::new(der_encoded_spki);
+        // SAFETY: The function only reads bytes from the buffer managed by the valid `CBS`
+        // object, and the returned `EVP_PKEY` is checked below.
+        let pkey = unsafe { EVP_parse_public_key(cbs.as_mut()) };
+        let pkey = NonNull::new(pkey)
+            .map(|pkey| Self { pkey, _inner_ec_key: None })
+            .ok_or_else(|| to_call_failed_error(ApiName::EVP_parse_public_key))?;
+        // SAFETY: The `CBS` is valid and only read by the function.
+        if unsafe { CBS_len(cbs.as_ref()) } != 0 {
+            error!("Unexpected trailing data after the SubjectPublicKeyInfo");
+            return Err(to_call_failed_error(ApiName::EVP_parse_public_key));
+        }
+        Ok(pkey)
+    }
+
+    /// Returns the EC key held by this `PKey`, rejecting keys of any other type.
+    pub(crate) fn ec_key(&self) -> Result<EcKey> {
+        // SAFETY: The function only reads the type of the valid `EVP_PKEY`.
+        let key_type = unsafe { EVP_PKEY_id(self.pkey.as_ptr()) };
+        if key_type != EVP_PKEY_EC {
+            error!("Only EC keys are supported. Key type in the EVP_PKEY: {key_type}");
+            return Err(Error::Unimplemented);
+        }
+        // SAFETY: The `EVP_PKEY` is valid and holds an EC key. The function takes a new
+        // reference to that key, which is released when the returned `EcKey` is dropped.
+        let ec_key = unsafe { EVP_PKEY_get1_EC_KEY(self.pkey.as_ptr()) };
+        NonNull::new(ec_key)
+            .map(EcKey)
+            .ok_or_else(|| to_call_failed_error(ApiName::EVP_PKEY_get1_EC_KEY))
+    }
+
     /// This function takes a raw public key data slice and creates a `PKey` instance wrapping
     /// a freshly allocated `EVP_PKEY` object from it.
     ///
@@ -184,7 +220,7 @@ impl PKey {
                 EVP_DigestVerifyInit(
                     digester_context.as_mut_ptr(),
                     pkey_context,
-                    digester.map_or(ptr::null(), |d| d.0),
+                    digester.map_or(ptr::null(), |d| d.md),
                     engine,
                     self.pkey.as_ptr(),
                 )
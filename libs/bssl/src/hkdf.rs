@@ -36,7 +36,7 @@ pub fn hkdf<const N: usize>(
         HKDF(
             key.as_mut_ptr(),
             key.len(),
-            digester.0,
+            digester.md,
             secret.as_ptr(),
             secret.len(),
             salt.as_ptr(),
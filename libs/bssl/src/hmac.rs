@@ -15,10 +15,19 @@
 //! Wrappers of the HMAC functions in BoringSSL hmac.h.
 
 use crate::digest::Digester;
+use crate::ec_key::ZVec;
 use crate::sha::SHA256_DIGEST_LENGTH;
-use crate::util::to_call_failed_error;
-use bssl_avf_error::{ApiName, Result};
-use bssl_sys::HMAC;
+use crate::util::{check_int_result, to_call_failed_error};
+use alloc::vec;
+use bssl_avf_error::{ApiName, Error, Result};
+use bssl_sys::{
+    HMAC_CTX_free, HMAC_CTX_new, HMAC_Final, HMAC_Init_ex, HMAC_Update, EVP_MAX_MD_SIZE, HMAC,
+    HMAC_CTX,
+};
+use core::ptr::{self, NonNull};
+use log::error;
+
+const MAX_DIGEST_SIZE: usize = EVP_MAX_MD_SIZE as usize;
 
 /// Computes the HMAC using SHA-256 for the given `data` with the given `key`.
 pub fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<[u8; SHA256_DIGEST_LENGTH]> {
@@ -41,7 +50,7 @@ fn hmac<const HASH_LEN: usize>(
     // SAFETY: Only reads from/writes to the provided slices and the digester was non-null.
     let ret = unsafe {
         HMAC(
-            digester.0,
+            digester.md,
             key.as_ptr() as *const _,
             key.len(),
             data.as_ptr(),
@@ -56,3 +65,58 @@ fn hmac<const HASH_LEN: usize>(
         Err(to_call_failed_error(ApiName::HMAC))
     }
 }
+
+/// HMAC context wrapping `HMAC_CTX`, to compute the HMAC of data fed in several chunks.
+pub struct HmacContext(NonNull<HMAC_CTX>);
+
+impl Drop for HmacContext {
+    fn drop(&mut self) {
+        // SAFETY: This function frees any resources owned by `HMAC_CTX`, including the key, and
+        // then frees the context.
+        // It is safe because `HMAC_CTX` has been allocated by BoringSSL and isn't used after this.
+        unsafe { HMAC_CTX_free(self.0.as_ptr()) }
+    }
+}
+
+impl HmacContext {
+    /// Creates a new `HmacContext` set up to compute the HMAC with the given `digester` and `key`.
+    pub fn new(digester: Digester, key: &[u8]) -> Result<Self> {
+        // SAFETY: The returned pointer is checked below.
+        let ctx = unsafe { HMAC_CTX_new() };
+        let ctx = NonNull::new(ctx).ok_or_else(|| to_call_failed_error(ApiName::HMAC_CTX_new))?;
+        // Wrap the context first so that it gets freed if the initialisation fails.
+        let ctx = Self(ctx);
+        let engine = ptr::null_mut(); // Use the default engine.
+
+        // SAFETY: The context was allocated by BoringSSL, the function only reads `key` within its
+        // bounds and the digester is a valid pointer to a static `EVP_MD`.
+        let ret = unsafe {
+            HMAC_Init_ex(ctx.0.as_ptr(), key.as_ptr() as *const _, key.len(), digester.md, engine)
+        };
+        check_int_result(ret, ApiName::HMAC_Init_ex)?;
+        Ok(ctx)
+    }
+
+    /// Feeds `data` into the HMAC computation.
+    pub fn update(&mut self, data: &[u8]) -> Result<()> {
+        // SAFETY: This function only reads `data` within its bounds and the context is valid.
+        let ret = unsafe { HMAC_Update(self.0.as_ptr(), data.as_ptr(), data.len()) };
+        check_int_result(ret, ApiName::HMAC_Update)
+    }
+
+    /// Returns the HMAC of all the data fed through `update()`.
+    pub fn finalize(self) -> Result<ZVec> {
+        let mut out = vec![0u8; MAX_DIGEST_SIZE];
+        let mut out_len = 0;
+        // SAFETY: `out` has `MAX_DIGEST_SIZE` bytes of space for write as required in the
+        // BoringSSL spec and the context is valid.
+        let ret = unsafe { HMAC_Final(self.0.as_ptr(), out.as_mut_ptr(), &mut out_len) };
+        check_int_result(ret, ApiName::HMAC_Final)?;
+        let out_len = usize::try_from(out_len).map_err(|e| {
+            error!("Failed to convert HMAC size to usize: {:?}", e);
+            Error::InternalError
+        })?;
+        out.truncate(out_len);
+        Ok(out.into())
+    }
+}
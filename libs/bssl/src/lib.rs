@@ -22,6 +22,7 @@ extern crate alloc;
 mod aead;
 mod cbb;
 mod cbs;
+mod cose;
 mod curve25519;
 mod digest;
 mod ec_key;
@@ -30,6 +31,7 @@ mod evp;
 mod hkdf;
 mod hmac;
 mod rand;
+mod rsa;
 mod sha;
 mod util;
 
@@ -38,11 +40,13 @@ pub use bssl_avf_error::{ApiName, CipherError, EcError, EcdsaError, Error, Reaso
 pub use aead::{Aead, AeadContext, AES_GCM_NONCE_LENGTH};
 pub use cbb::CbbFixed;
 pub use cbs::Cbs;
+pub use cose::verify_cose_sign1;
 pub use curve25519::ed25519_verify;
-pub use digest::Digester;
+pub use digest::{Digester, DigesterContext};
 pub use ec_key::{EcKey, ZVec};
 pub use evp::{PKey, PKeyType};
 pub use hkdf::hkdf;
-pub use hmac::hmac_sha256;
+pub use hmac::{hmac_sha256, HmacContext};
 pub use rand::rand_bytes;
+pub use rsa::rsa_verify_pkcs1;
 pub use sha::sha256;
@@ -0,0 +1,82 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Wrappers of the RSA related functions in BoringSSL rsa.h.
+
+use crate::util::{check_int_result, to_call_failed_error};
+use bssl_avf_error::{ApiName, Error, Result};
+use bssl_sys::{
+    NID_sha256, NID_sha384, NID_sha512, RSA_free, RSA_public_key_from_bytes, RSA_verify, RSA,
+    SHA256_DIGEST_LENGTH, SHA384_DIGEST_LENGTH, SHA512_DIGEST_LENGTH,
+};
+use core::ptr::NonNull;
+
+const SHA256_DIGEST_LEN: usize = SHA256_DIGEST_LENGTH as usize;
+const SHA384_DIGEST_LEN: usize = SHA384_DIGEST_LENGTH as usize;
+const SHA512_DIGEST_LEN: usize = SHA512_DIGEST_LENGTH as usize;
+
+/// Verifies the RSASSA-PKCS1-v1_5 `signature` of the `digest` with the given DER-encoded
+/// RSAPublicKey, as described in RFC 8017 Appendix A.1.1:
+///
+/// https://datatracker.ietf.org/doc/html/rfc8017#appendix-A.1.1
+///
+/// The hash function is deduced from the size of `digest`, which must be a SHA-256, SHA-384 or
+/// SHA-512 digest.
+pub fn rsa_verify_pkcs1(public_key: &[u8], digest: &[u8], signature: &[u8]) -> Result<()> {
+    let hash_nid = match digest.len() {
+        SHA256_DIGEST_LEN => NID_sha256,
+        SHA384_DIGEST_LEN => NID_sha384,
+        SHA512_DIGEST_LEN => NID_sha512,
+        _ => return Err(Error::Unimplemented),
+    };
+    let rsa = RsaKey::from_public_key(public_key)?;
+    // SAFETY: This function only reads the given data within its bounds.
+    // The `RSA` passed to this function has been initialized and checked non-null.
+    let ret = unsafe {
+        RSA_verify(
+            hash_nid,
+            digest.as_ptr(),
+            digest.len(),
+            signature.as_ptr(),
+            signature.len(),
+            rsa.0.as_ptr(),
+        )
+    };
+    check_int_result(ret, ApiName::RSA_verify)
+}
+
+/// Wrapper of an `RSA` object holding a public key.
+struct RsaKey(NonNull<RSA>);
+
+impl Drop for RsaKey {
+    fn drop(&mut self) {
+        // SAFETY: It is safe because the key has been allocated by BoringSSL and isn't
+        // used after this.
+        unsafe { RSA_free(self.0.as_ptr()) }
+    }
+}
+
+impl RsaKey {
+    /// Parses the given DER-encoded RSAPublicKey.
+    fn from_public_key(der_encoded_public_key: &[u8]) -> Result<Self> {
+        // SAFETY: The function only reads bytes from the buffer within its bounds and returns
+        // either null or a newly allocated `RSA`.
+        let rsa = unsafe {
+            RSA_public_key_from_bytes(der_encoded_public_key.as_ptr(), der_encoded_public_key.len())
+        };
+        NonNull::new(rsa)
+            .map(Self)
+            .ok_or_else(|| to_call_failed_error(ApiName::RSA_public_key_from_bytes))
+    }
+}
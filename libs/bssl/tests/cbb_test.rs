@@ -0,0 +1,71 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests the CBOR encoding helpers of `CbbFixed`.
+
+use bssl_avf::{ApiName, CbbFixed, Error, Result};
+
+#[test]
+fn cbor_array_of_maps_is_encoded() -> Result<()> {
+    let mut buffer = [0u8; 64];
+    let mut cbb = CbbFixed::new(&mut buffer);
+
+    // [{1: h'0102'}, {-1: 24, 1000: -500}]
+    cbb.add_cbor_array_header(2)?;
+    cbb.add_cbor_map_header(1)?;
+    cbb.add_cbor_int(1)?;
+    cbb.add_cbor_bstr(&[0x01, 0x02])?;
+    cbb.add_cbor_map_header(2)?;
+    cbb.add_cbor_int(-1)?;
+    cbb.add_cbor_uint(24)?;
+    cbb.add_cbor_int(1000)?;
+    cbb.add_cbor_int(-500)?;
+    let len = cbb.written_len()?;
+
+    let expected = [
+        0x82, 0xa1, 0x01, 0x42, 0x01, 0x02, 0xa2, 0x20, 0x18, 0x18, 0x19, 0x03, 0xe8, 0x39, 0x01,
+        0xf3,
+    ];
+    assert_eq!(expected, buffer[..len]);
+    Ok(())
+}
+
+#[test]
+fn cbor_integers_use_shortest_encoding() -> Result<()> {
+    let mut buffer = [0u8; 64];
+    let mut cbb = CbbFixed::new(&mut buffer);
+
+    cbb.add_cbor_uint(23)?;
+    cbb.add_cbor_uint(u64::from(u32::MAX) + 1)?;
+    cbb.add_cbor_int(i64::MIN)?;
+    let len = cbb.written_len()?;
+
+    let expected = [
+        0x17, // 23
+        0x1b, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, // 2^32
+        0x3b, 0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, // -2^63
+    ];
+    assert_eq!(expected, buffer[..len]);
+    Ok(())
+}
+
+#[test]
+fn cbor_encoding_fails_when_buffer_is_full() {
+    let mut buffer = [0u8; 2];
+    let mut cbb = CbbFixed::new(&mut buffer);
+
+    let err = cbb.add_cbor_bstr(&[0x01, 0x02]).unwrap_err();
+
+    assert!(matches!(err, Error::CallFailed(ApiName::CBB_add_bytes, _)));
+}
@@ -0,0 +1,67 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests the CBOR decoding helpers of `Cbs`.
+
+use bssl_avf::{ApiName, Cbs, Error, Result};
+
+#[test]
+fn cbor_array_of_maps_is_decoded() -> Result<()> {
+    // [{1: h'0102'}, {-1: 24, 1000: -500}]
+    let data = [
+        0x82, 0xa1, 0x01, 0x42, 0x01, 0x02, 0xa2, 0x20, 0x18, 0x18, 0x19, 0x03, 0xe8, 0x39, 0x01,
+        0xf3,
+    ];
+    let mut cbs = Cbs::new(&data);
+
+    assert_eq!(2, cbs.get_cbor_array_header()?);
+    assert_eq!(1, cbs.get_cbor_map_header()?);
+    assert_eq!(1, cbs.get_cbor_int()?);
+    let bstr = cbs.get_cbor_bstr()?;
+    assert_eq!([0x01_u8, 0x02], bstr);
+    assert!(core::ptr::eq(bstr, &data[4..6]), "The byte string isn't borrowed from the input");
+    assert_eq!(2, cbs.get_cbor_map_header()?);
+    assert_eq!(-1, cbs.get_cbor_int()?);
+    assert_eq!(24, cbs.get_cbor_uint()?);
+    assert_eq!(1000, cbs.get_cbor_int()?);
+    assert_eq!(-500, cbs.get_cbor_int()?);
+    Ok(())
+}
+
+#[test]
+fn truncated_cbor_is_rejected() {
+    // h'010203' missing its last byte.
+    let data = [0x43, 0x01, 0x02];
+    let mut cbs = Cbs::new(&data);
+
+    let err = cbs.get_cbor_bstr().unwrap_err();
+
+    assert!(matches!(err, Error::CallFailed(ApiName::CBS_get_bytes, _)));
+
+    // 2^32 missing its last byte.
+    let data = [0x1b, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00];
+    let mut cbs = Cbs::new(&data);
+
+    let err = cbs.get_cbor_uint().unwrap_err();
+
+    assert!(matches!(err, Error::CallFailed(ApiName::CBS_get_u64, _)));
+}
+
+#[test]
+fn cbor_of_unexpected_type_is_rejected() {
+    let data = [0x20]; // -1
+    let mut cbs = Cbs::new(&data);
+
+    assert_eq!(Err(Error::CborDecodingFailed), cbs.get_cbor_uint());
+}
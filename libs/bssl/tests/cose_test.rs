@@ -0,0 +1,53 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Tests the verification of COSE_Sign1 structures.
+
+use bssl_avf::{sha256, verify_cose_sign1, ApiName, EcKey, EcdsaError, Error, Result};
+use coset::{iana, CborSerializable, CoseSign1, CoseSign1Builder, HeaderBuilder};
+
+const PAYLOAD: &[u8] = b"test payload";
+
+#[test]
+fn cose_sign1_verification_returns_the_payload() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    let sign1 = es256_cose_sign1(&ec_key, PAYLOAD)?;
+
+    assert_eq!(PAYLOAD, verify_cose_sign1(&ec_key, &sign1.to_vec()?)?);
+    Ok(())
+}
+
+#[test]
+fn tampered_cose_sign1_verification_fails() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    let mut sign1 = es256_cose_sign1(&ec_key, PAYLOAD)?;
+    sign1.payload = Some(b"tampered payload".to_vec());
+
+    let err = verify_cose_sign1(&ec_key, &sign1.to_vec()?).unwrap_err();
+    let expected_err = Error::CallFailed(ApiName::ECDSA_verify, EcdsaError::BadSignature.into());
+    assert_eq!(expected_err, err);
+    Ok(())
+}
+
+fn es256_cose_sign1(ec_key: &EcKey, payload: &[u8]) -> Result<CoseSign1> {
+    let protected = HeaderBuilder::new().algorithm(iana::Algorithm::ES256).build();
+    let sign1 = CoseSign1Builder::new()
+        .protected(protected)
+        .payload(payload.to_vec())
+        .try_create_signature(&[], |message| ec_key.ecdsa_sign_cose(&sha256(message)?))?
+        .build();
+    Ok(sign1)
+}
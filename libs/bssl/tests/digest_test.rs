@@ -0,0 +1,58 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bssl_avf::{sha256, Digester, DigesterContext, Result};
+
+const PREFIX: &[u8] = b"Common prefix, ";
+const SUFFIX1: &[u8] = b"first continuation";
+const SUFFIX2: &[u8] = b"second continuation";
+
+#[test]
+fn cloned_contexts_are_updated_independently() -> Result<()> {
+    let mut context1 = DigesterContext::new()?;
+    context1.init(Digester::sha256())?;
+    context1.update(PREFIX)?;
+
+    let mut context2 = context1.try_clone()?;
+    let mut context3 = context1.clone();
+    context1.update(SUFFIX1)?;
+    context2.update(SUFFIX2)?;
+
+    assert_eq!(context1.finalize()?, sha256(&[PREFIX, SUFFIX1].concat())?);
+    assert_eq!(context2.finalize()?, sha256(&[PREFIX, SUFFIX2].concat())?);
+    drop(context1);
+    drop(context2);
+
+    // The remaining clone is unaffected by the other contexts being updated and freed.
+    assert_eq!(context3.finalize()?, sha256(PREFIX)?);
+    Ok(())
+}
+
+#[test]
+fn digester_size_matches_digest_length() -> Result<()> {
+    for (digester, expected_size) in
+        [(Digester::sha256(), 32), (Digester::sha384(), 48), (Digester::sha512(), 64)]
+    {
+        assert_eq!(digester.size(), expected_size);
+        assert_eq!(digester.digest(PREFIX)?.len(), digester.size());
+    }
+    Ok(())
+}
+
+#[test]
+fn digester_algorithm_names() {
+    assert_eq!(Digester::sha256().algorithm_name(), "SHA-256");
+    assert_eq!(Digester::sha384().algorithm_name(), "SHA-384");
+    assert_eq!(Digester::sha512().algorithm_name(), "SHA-512");
+}
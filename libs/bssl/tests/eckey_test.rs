@@ -12,8 +12,8 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use bssl_avf::{sha256, ApiName, Digester, EcKey, EcdsaError, Error, PKey, Result};
-use coset::CborSerializable;
+use bssl_avf::{sha256, ApiName, Digester, EcKey, EcdsaError, Error, PKey, PKeyType, Result};
+use coset::{iana, iana::EnumI64, CborSerializable, CoseKeyBuilder, Label};
 use spki::{
     der::{AnyRef, Decode, Encode},
     AlgorithmIdentifier, ObjectIdentifier, SubjectPublicKeyInfoRef,
@@ -27,6 +27,29 @@ const ALGO_PARAM_P256_OID: ObjectIdentifier = ObjectIdentifier::new_unwrap("1.2.
 
 const MESSAGE1: &[u8] = b"test message 1";
 const MESSAGE2: &[u8] = b"test message 2";
+const SEED1: &[u8] = b"test seed 1";
+const SEED2: &[u8] = b"test seed 2";
+
+/// ES256 test vector from RFC 6979 Section A.2.5, with the message "sample".
+const RFC6979_P256_MESSAGE: &[u8] = b"sample";
+const RFC6979_P256_PRIVATE_KEY: [u8; 32] = [
+    0xc9, 0xaf, 0xa9, 0xd8, 0x45, 0xba, 0x75, 0x16, 0x6b, 0x5c, 0x21, 0x57, 0x67, 0xb1, 0xd6, 0x93,
+    0x4e, 0x50, 0xc3, 0xdb, 0x36, 0xe8, 0x9b, 0x12, 0x7b, 0x8a, 0x62, 0x2b, 0x12, 0x0f, 0x67, 0x21,
+];
+const RFC6979_P256_PUBLIC_KEY_X: [u8; 32] = [
+    0x60, 0xfe, 0xd4, 0xba, 0x25, 0x5a, 0x9d, 0x31, 0xc9, 0x61, 0xeb, 0x74, 0xc6, 0x35, 0x6d, 0x68,
+    0xc0, 0x49, 0xb8, 0x92, 0x3b, 0x61, 0xfa, 0x6c, 0xe6, 0x69, 0x62, 0x2e, 0x60, 0xf2, 0x9f, 0xb6,
+];
+const RFC6979_P256_PUBLIC_KEY_Y: [u8; 32] = [
+    0x79, 0x03, 0xfe, 0x10, 0x08, 0xb8, 0xbc, 0x99, 0xa4, 0x1a, 0xe9, 0xe9, 0x56, 0x28, 0xbc, 0x64,
+    0xf2, 0xf1, 0xb2, 0x0c, 0x2d, 0x7e, 0x9f, 0x51, 0x77, 0xa3, 0xc2, 0x94, 0xd4, 0x46, 0x22, 0x99,
+];
+const RFC6979_P256_COSE_SIGNATURE: [u8; 64] = [
+    0xef, 0xd4, 0x8b, 0x2a, 0xac, 0xb6, 0xa8, 0xfd, 0x11, 0x40, 0xdd, 0x9c, 0xd4, 0x5e, 0x81, 0xd6,
+    0x9d, 0x2c, 0x87, 0x7b, 0x56, 0xaa, 0xf9, 0x91, 0xc3, 0x4d, 0x0e, 0xa8, 0x4e, 0xaf, 0x37, 0x16,
+    0xf7, 0xcb, 0x1c, 0x94, 0x2d, 0x65, 0x7c, 0x41, 0xd4, 0x36, 0xc7, 0xa1, 0xb6, 0xe2, 0x9f, 0x65,
+    0xf3, 0xe9, 0x00, 0xdb, 0xb9, 0xaf, 0xf4, 0x06, 0x4d, 0xc4, 0xab, 0x2f, 0x84, 0x3a, 0xcd, 0xa8,
+];
 
 #[test]
 fn ec_private_key_serialization() -> Result<()> {
@@ -39,6 +62,64 @@ fn ec_private_key_serialization() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn ec_private_key_with_out_of_range_scalar_is_rejected() {
+    // The order of the P-256 curve.
+    const P256_ORDER: [u8; 32] = [
+        0xff, 0xff, 0xff, 0xff, 0x00, 0x00, 0x00, 0x00, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+        0xff, 0xbc, 0xe6, 0xfa, 0xad, 0xa7, 0x17, 0x9e, 0x84, 0xf3, 0xb9, 0xca, 0xc2, 0xfc, 0x63,
+        0x25, 0x51,
+    ];
+
+    assert!(EcKey::from_ec_private_key(&ec_private_key_with_scalar(&P256_ORDER)).is_err());
+}
+
+#[test]
+fn ec_private_key_with_public_point_at_infinity_is_rejected() {
+    // A zero scalar yields the point at infinity as public key.
+    assert!(EcKey::from_ec_private_key(&ec_private_key_with_scalar(&[0; 32])).is_err());
+}
+
+#[test]
+fn private_scalar_round_trips_to_same_public_key() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    let scalar = ec_key.private_scalar()?;
+    let scalar: &[u8; 32] = scalar.as_slice().try_into().unwrap();
+
+    let imported_ec_key = EcKey::from_ec_private_key(&ec_private_key_with_scalar(scalar))?;
+
+    assert_eq!(ec_key.cose_public_key()?, imported_ec_key.cose_public_key()?);
+    Ok(())
+}
+
+#[test]
+fn private_scalar_is_padded_to_curve_size() -> Result<()> {
+    let mut ec_key = EcKey::new_p384()?;
+    ec_key.generate_key()?;
+
+    assert_eq!(48, ec_key.private_scalar()?.as_slice().len());
+    Ok(())
+}
+
+#[test]
+fn private_scalar_of_public_key_fails() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    let public_key = EcKey::from_cose_public_key(&ec_key.cose_public_key()?)?;
+
+    let err = public_key.private_scalar().err().unwrap();
+    assert!(matches!(err, Error::CallFailed(ApiName::EC_KEY_get0_private_key, _)));
+    Ok(())
+}
+
+/// Returns a DER-encoded P-256 ECPrivateKey with the given scalar and no optional fields.
+fn ec_private_key_with_scalar(scalar: &[u8; 32]) -> Vec<u8> {
+    let mut der = vec![0x30, 0x25, 0x02, 0x01, 0x01, 0x04, 0x20];
+    der.extend_from_slice(scalar);
+    der
+}
+
 #[test]
 fn subject_public_key_info_serialization() -> Result<()> {
     let mut ec_key = EcKey::new_p256()?;
@@ -57,6 +138,42 @@ fn subject_public_key_info_serialization() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn ec_key_from_subject_public_key_info() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    let cose_public_key = ec_key.cose_public_key()?;
+    let pkey: PKey = ec_key.try_into()?;
+    let subject_public_key_info = pkey.subject_public_key_info()?;
+
+    let deserialized_ec_key = EcKey::from_subject_public_key_info(&subject_public_key_info)?;
+
+    assert_eq!(cose_public_key, deserialized_ec_key.cose_public_key()?);
+    Ok(())
+}
+
+#[test]
+fn non_ec_subject_public_key_info_is_rejected() -> Result<()> {
+    let pkey = PKey::new_raw_public_key(&[0x01; 32], PKeyType::ED25519)?;
+    let subject_public_key_info = pkey.subject_public_key_info()?;
+
+    let err = EcKey::from_subject_public_key_info(&subject_public_key_info).unwrap_err();
+    assert_eq!(Error::Unimplemented, err);
+    Ok(())
+}
+
+#[test]
+fn subject_public_key_info_with_trailing_data_is_rejected() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    let pkey: PKey = ec_key.try_into()?;
+    let mut subject_public_key_info = pkey.subject_public_key_info()?;
+    subject_public_key_info.push(0);
+
+    assert!(EcKey::from_subject_public_key_info(&subject_public_key_info).is_err());
+    Ok(())
+}
+
 #[test]
 fn p256_cose_public_key_serialization() -> Result<()> {
     let mut ec_key = EcKey::new_p256()?;
@@ -79,6 +196,46 @@ fn check_cose_public_key_serialization(ec_key: &mut EcKey) -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn cose_public_key_with_invalid_encoding_is_rejected() {
+    let err = EcKey::from_cose_public_key_slice(&[0xa1, 0x01]).unwrap_err();
+    assert_eq!(Error::CoseKeyDecodingFailed, err);
+}
+
+#[test]
+fn cose_public_key_without_y_coordinate_is_rejected() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    let mut cose_key = ec_key.cose_public_key()?;
+    let y_label = Label::Int(iana::Ec2KeyParameter::Y.to_i64());
+    cose_key.params.retain(|(label, _)| label != &y_label);
+
+    let err = EcKey::from_cose_public_key_slice(&cose_key.to_vec().unwrap()).unwrap_err();
+    assert_eq!(Error::CosetError, err);
+    Ok(())
+}
+
+#[test]
+fn cose_public_key_with_unsupported_key_type_is_rejected() {
+    let cose_key = CoseKeyBuilder::new_okp_key().build();
+
+    let err = EcKey::from_cose_public_key_slice(&cose_key.to_vec().unwrap()).unwrap_err();
+    assert_eq!(Error::Unimplemented, err);
+}
+
+#[test]
+fn cose_public_key_with_wrong_coordinate_size_is_rejected() {
+    let cose_key = CoseKeyBuilder::new_ec2_pub_key(
+        iana::EllipticCurve::P_256,
+        RFC6979_P256_PUBLIC_KEY_X[1..].to_vec(),
+        RFC6979_P256_PUBLIC_KEY_Y.to_vec(),
+    )
+    .build();
+
+    let err = EcKey::from_cose_public_key_slice(&cose_key.to_vec().unwrap()).unwrap_err();
+    assert_eq!(Error::CoseKeyDecodingFailed, err);
+}
+
 #[test]
 fn ecdsa_p256_signing_and_verification_succeed() -> Result<()> {
     let mut ec_key = EcKey::new_p256()?;
@@ -155,6 +312,125 @@ fn ecdsa_cose_signing_and_verification_succeed() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn p256_cose_signature_converts_to_verifiable_der_signature() -> Result<()> {
+    let mut ec_key = EcKey::new_p256()?;
+    ec_key.generate_key()?;
+    check_cose_signature_converts_to_der(ec_key, Digester::sha256(), 32)
+}
+
+#[test]
+fn p384_cose_signature_converts_to_verifiable_der_signature() -> Result<()> {
+    let mut ec_key = EcKey::new_p384()?;
+    ec_key.generate_key()?;
+    check_cose_signature_converts_to_der(ec_key, Digester::sha384(), 48)
+}
+
+fn check_cose_signature_converts_to_der(
+    ec_key: EcKey,
+    digester: Digester,
+    coord_len: usize,
+) -> Result<()> {
+    let digest = digester.digest(MESSAGE1)?;
+
+    let signature = ec_key.ecdsa_sign_cose(&digest)?;
+    assert_eq!(signature.len(), 2 * coord_len);
+
+    let der_signature = cose_signature_to_der(&signature);
+    ec_key.ecdsa_verify_der(&der_signature, &digest)?;
+    let pkey: PKey = ec_key.try_into()?;
+    pkey.verify(&der_signature, MESSAGE1, Some(digester))
+}
+
+/// Encodes a COSE (R | S) ECDSA signature as an `ECDSA-Sig-Value` (see RFC 3279 Section 2.2.3).
+fn cose_signature_to_der(signature: &[u8]) -> Vec<u8> {
+    let (r, s) = signature.split_at(signature.len() / 2);
+    let mut integers = der_unsigned_integer(r);
+    integers.extend(der_unsigned_integer(s));
+
+    let mut der = vec![0x30, integers.len().try_into().unwrap()];
+    der.extend(integers);
+    der
+}
+
+fn der_unsigned_integer(bytes: &[u8]) -> Vec<u8> {
+    let first_non_zero = bytes.iter().position(|b| *b != 0).unwrap_or(bytes.len() - 1);
+    let bytes = &bytes[first_non_zero..];
+    // A leading zero keeps integers whose most significant bit is set positive.
+    let padding: &[u8] = if bytes[0] & 0x80 != 0 { &[0] } else { &[] };
+
+    let mut der = vec![0x02, (padding.len() + bytes.len()).try_into().unwrap()];
+    der.extend_from_slice(padding);
+    der.extend_from_slice(bytes);
+    der
+}
+
+#[test]
+fn known_es256_cose_signature_verification_succeeds() -> Result<()> {
+    let ec_key = rfc6979_p256_public_key()?;
+    let digest = sha256(RFC6979_P256_MESSAGE)?;
+
+    ec_key.ecdsa_verify_cose(&RFC6979_P256_COSE_SIGNATURE, &digest)
+}
+
+#[test]
+fn verifying_cose_signature_with_invalid_length_fails() -> Result<()> {
+    let ec_key = rfc6979_p256_public_key()?;
+    let digest = sha256(RFC6979_P256_MESSAGE)?;
+
+    let truncated_signature = &RFC6979_P256_COSE_SIGNATURE[2..];
+    let err = ec_key.ecdsa_verify_cose(truncated_signature, &digest).unwrap_err();
+    assert_eq!(Error::InvalidSignatureLength, err);
+    Ok(())
+}
+
+#[test]
+fn public_coords_of_known_point_are_returned() -> Result<()> {
+    let ec_key = rfc6979_p256_public_key()?;
+
+    let (x, y) = ec_key.public_coords()?;
+    assert_eq!(RFC6979_P256_PUBLIC_KEY_X, x.as_slice());
+    assert_eq!(RFC6979_P256_PUBLIC_KEY_Y, y.as_slice());
+    Ok(())
+}
+
+#[test]
+fn public_key_of_known_private_scalar_is_computed() -> Result<()> {
+    let ec_key = EcKey::from_private_scalar(iana::EllipticCurve::P_256, &RFC6979_P256_PRIVATE_KEY)?;
+
+    let (x, y) = ec_key.public_coords()?;
+    assert_eq!(RFC6979_P256_PUBLIC_KEY_X, x.as_slice());
+    assert_eq!(RFC6979_P256_PUBLIC_KEY_Y, y.as_slice());
+    assert_eq!(RFC6979_P256_PRIVATE_KEY, ec_key.private_scalar()?.as_slice());
+    Ok(())
+}
+
+#[test]
+fn private_scalar_with_invalid_length_is_rejected() {
+    let scalar = &RFC6979_P256_PRIVATE_KEY[1..];
+
+    let err = EcKey::from_private_scalar(iana::EllipticCurve::P_256, scalar).err().unwrap();
+    assert_eq!(Error::InvalidPrivateKeyLength, err);
+}
+
+#[test]
+fn out_of_range_private_scalar_is_rejected() {
+    for scalar in [[0; 32], [0xff; 32]] {
+        let err = EcKey::from_private_scalar(iana::EllipticCurve::P_256, &scalar).err().unwrap();
+        assert!(matches!(err, Error::CallFailed(ApiName::EC_KEY_set_private_key, _)));
+    }
+}
+
+fn rfc6979_p256_public_key() -> Result<EcKey> {
+    let cose_key = CoseKeyBuilder::new_ec2_pub_key(
+        iana::EllipticCurve::P_256,
+        RFC6979_P256_PUBLIC_KEY_X.to_vec(),
+        RFC6979_P256_PUBLIC_KEY_Y.to_vec(),
+    )
+    .build();
+    EcKey::from_cose_public_key(&cose_key)
+}
+
 #[test]
 fn verifying_ecdsa_cose_signed_with_a_different_message_fails() -> Result<()> {
     let digest = sha256(MESSAGE1)?;
@@ -181,3 +457,26 @@ fn verifying_ecdsa_cose_signed_as_der_fails() -> Result<()> {
     assert_eq!(expected_err, err);
     Ok(())
 }
+
+#[test]
+fn p256_keys_generated_with_the_same_seed_are_identical() -> Result<()> {
+    check_keys_generated_with_seed(EcKey::new_p256()?, EcKey::new_p256()?)
+}
+
+#[test]
+fn p384_keys_generated_with_the_same_seed_are_identical() -> Result<()> {
+    check_keys_generated_with_seed(EcKey::new_p384()?, EcKey::new_p384()?)
+}
+
+fn check_keys_generated_with_seed(mut ec_key1: EcKey, mut ec_key2: EcKey) -> Result<()> {
+    ec_key1.generate_with_seed(SEED1)?;
+    ec_key2.generate_with_seed(SEED1)?;
+    assert_eq!(ec_key1.cose_public_key()?, ec_key2.cose_public_key()?);
+
+    ec_key2.generate_with_seed(SEED2)?;
+    assert_ne!(ec_key1.cose_public_key()?, ec_key2.cose_public_key()?);
+
+    let digest = sha256(MESSAGE1)?;
+    let signature = ec_key1.ecdsa_sign_der(&digest)?;
+    ec_key1.ecdsa_verify_der(&signature, &digest)
+}
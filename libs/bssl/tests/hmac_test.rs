@@ -16,7 +16,7 @@
 //!
 //! [RFC 4231]: https://datatracker.ietf.org/doc/html/rfc4231
 
-use bssl_avf::{hmac_sha256, Result};
+use bssl_avf::{hmac_sha256, Digester, HmacContext, Result};
 
 #[test]
 fn rfc4231_test_case_1() -> Result<()> {
@@ -113,3 +113,16 @@ fn rfc4231_test_case_7() -> Result<()> {
     assert_eq!(HMAC_SHA256, hmac_sha256(KEY, DATA.as_bytes())?);
     Ok(())
 }
+
+#[test]
+fn chunked_hmac_matches_one_shot_hmac() -> Result<()> {
+    const KEY: &[u8] = b"Jefe";
+    const DATA: &[u8] = b"what do ya want for nothing?";
+
+    let mut ctx = HmacContext::new(Digester::sha256(), KEY)?;
+    for chunk in DATA.chunks(5) {
+        ctx.update(chunk)?;
+    }
+    assert_eq!(hmac_sha256(KEY, DATA)?, ctx.finalize()?.as_slice());
+    Ok(())
+}
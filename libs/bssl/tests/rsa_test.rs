@@ -0,0 +1,106 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use bssl_avf::{rsa_verify_pkcs1, sha256, ApiName, Error, Result};
+
+const MESSAGE: &[u8] = b"test message";
+
+/// DER-encoded RSAPublicKey of an RSA-2048 test key.
+const RSA2048_PUBLIC_KEY: [u8; 270] = [
+    0x30, 0x82, 0x01, 0x0a, 0x02, 0x82, 0x01, 0x01, 0x00, 0xb1, 0x37, 0x14, 0xad, 0x12, 0x03, 0x9e,
+    0x0b, 0x5e, 0xc9, 0x8b, 0xbb, 0xf3, 0x2f, 0x6f, 0x6b, 0x85, 0xd2, 0xa2, 0x0d, 0x4a, 0x62, 0xa3,
+    0x0d, 0xbd, 0x12, 0x24, 0x74, 0x58, 0x3e, 0x78, 0xb9, 0x6b, 0xac, 0x37, 0x57, 0x04, 0xca, 0x51,
+    0xd4, 0xc1, 0x04, 0x30, 0xa8, 0x1e, 0xd7, 0x5b, 0xa3, 0xd2, 0xb3, 0x68, 0x7b, 0xff, 0x69, 0xfc,
+    0xc1, 0x43, 0x77, 0x99, 0xf9, 0xde, 0xd7, 0x29, 0xcf, 0xa2, 0xe5, 0x5f, 0x17, 0x05, 0xaa, 0x0e,
+    0x4e, 0x31, 0x7d, 0x2c, 0xd8, 0x16, 0x43, 0x6c, 0x0c, 0x04, 0x4c, 0xc4, 0x2b, 0xf7, 0x0f, 0x30,
+    0x06, 0x6b, 0x0a, 0x9a, 0xff, 0xd6, 0xc0, 0x62, 0x25, 0xc5, 0xb8, 0xb3, 0x83, 0xf2, 0x75, 0x89,
+    0x8d, 0x41, 0x3f, 0x82, 0x41, 0xb5, 0xf3, 0x83, 0xa4, 0xb4, 0x38, 0xc8, 0xd0, 0x21, 0x24, 0x70,
+    0xe9, 0xb3, 0xda, 0x55, 0x98, 0xcc, 0x2c, 0xe3, 0xc5, 0x05, 0xc6, 0xf8, 0x1f, 0xc9, 0x40, 0x10,
+    0x08, 0x0b, 0xe0, 0x33, 0x67, 0xad, 0x2d, 0xc0, 0xd2, 0x02, 0xa5, 0x0b, 0xf8, 0xc7, 0x1a, 0xac,
+    0xc5, 0xcf, 0xca, 0x07, 0x7d, 0x85, 0x47, 0xe7, 0x92, 0x5b, 0x4d, 0x8c, 0x59, 0xe0, 0x7e, 0x45,
+    0x73, 0xd4, 0x8f, 0x8e, 0xc7, 0x86, 0xd1, 0x64, 0x30, 0xc1, 0xcb, 0x61, 0x9f, 0x83, 0x7e, 0x41,
+    0x82, 0xb2, 0x95, 0xb7, 0x0f, 0x89, 0x84, 0xad, 0x93, 0x9f, 0x7b, 0xee, 0x92, 0x76, 0x9d, 0x49,
+    0xe7, 0xcc, 0xfc, 0xb1, 0x00, 0x06, 0x2e, 0x42, 0x27, 0xab, 0xe7, 0xd4, 0x91, 0x93, 0xcc, 0x0d,
+    0x1e, 0x56, 0x45, 0xb9, 0x32, 0x58, 0x3f, 0xcc, 0x54, 0x78, 0xfc, 0xe9, 0x3f, 0x9b, 0x49, 0x81,
+    0x3b, 0xa8, 0x02, 0x99, 0x95, 0xc8, 0xf8, 0x2d, 0x6d, 0xe8, 0xc8, 0x56, 0x70, 0x88, 0x8e, 0xbc,
+    0x21, 0xa1, 0x22, 0xc1, 0xe1, 0x5a, 0x7f, 0x4e, 0x0d, 0x02, 0x03, 0x01, 0x00, 0x01,
+];
+
+/// RSASSA-PKCS1-v1_5 signature of the SHA-256 digest of `MESSAGE` with the key above.
+const RSA2048_SHA256_SIGNATURE: [u8; 256] = [
+    0x05, 0x81, 0x2a, 0xde, 0xaf, 0x4a, 0xf4, 0x23, 0x2c, 0xba, 0x74, 0x0b, 0x93, 0xe2, 0x0b, 0xf3,
+    0x30, 0x49, 0x03, 0x3f, 0x3f, 0xf2, 0x67, 0xef, 0xd5, 0x86, 0xfe, 0x17, 0x25, 0x0e, 0xca, 0x94,
+    0x28, 0xc5, 0xc4, 0xdd, 0xf7, 0x7a, 0x20, 0x09, 0x2a, 0x29, 0xa6, 0x3e, 0x62, 0xaa, 0x8d, 0xe7,
+    0x1d, 0xd0, 0x45, 0x7d, 0xef, 0x8c, 0x3d, 0x11, 0x8d, 0xd4, 0x64, 0x59, 0xb8, 0x9d, 0xf8, 0x95,
+    0x53, 0x0b, 0x82, 0xd2, 0xe1, 0x40, 0xf1, 0x3a, 0x10, 0xf6, 0xe0, 0xb9, 0xc0, 0xea, 0x89, 0x47,
+    0x84, 0x4c, 0xcd, 0x44, 0xa7, 0x77, 0x6f, 0x8a, 0x15, 0xac, 0xfb, 0x1e, 0x1d, 0x10, 0xa1, 0x12,
+    0xd0, 0xe0, 0x9c, 0x34, 0xe2, 0x2a, 0x85, 0xe3, 0xb2, 0x16, 0xde, 0xe1, 0x7d, 0xcd, 0x8c, 0xe5,
+    0xa7, 0xd9, 0xb3, 0x5c, 0x9d, 0x1b, 0xa8, 0xde, 0x38, 0x38, 0x83, 0x37, 0xdb, 0x10, 0xab, 0xbb,
+    0x9b, 0x5c, 0x4d, 0x4b, 0x81, 0x07, 0xf9, 0x0a, 0xfe, 0xab, 0x61, 0x18, 0x52, 0x46, 0x04, 0xa7,
+    0x68, 0x45, 0xc1, 0x65, 0x36, 0x64, 0x3c, 0x78, 0x5d, 0xd4, 0x16, 0xa7, 0xd7, 0x79, 0xa7, 0x05,
+    0xf9, 0x3d, 0xde, 0xbf, 0xf8, 0xfb, 0x8c, 0x4c, 0x0c, 0xa0, 0x6b, 0x68, 0xac, 0xae, 0x85, 0x23,
+    0xc4, 0x86, 0x00, 0xf2, 0x00, 0xd6, 0x9c, 0x45, 0xf2, 0x8b, 0x49, 0x80, 0x7e, 0x21, 0xc6, 0x31,
+    0xf3, 0xea, 0x48, 0xc3, 0xb1, 0x04, 0x7e, 0xdc, 0x1e, 0xa4, 0x5e, 0xf1, 0xa1, 0xad, 0x09, 0x7e,
+    0xec, 0x76, 0x06, 0x53, 0x86, 0xb2, 0xfb, 0x4e, 0x7b, 0x3b, 0xad, 0x69, 0x4f, 0x06, 0x97, 0x20,
+    0xcd, 0x31, 0x6f, 0x01, 0xb9, 0x30, 0x6b, 0x7a, 0x81, 0x78, 0xd0, 0x7b, 0x70, 0xa4, 0xe7, 0xab,
+    0xa7, 0x02, 0x55, 0x9a, 0xe6, 0x5a, 0xd0, 0xdd, 0xa6, 0x1a, 0x2c, 0x2e, 0x32, 0x87, 0x25, 0x6f,
+];
+
+#[test]
+fn rsa_verify_pkcs1_accepts_valid_signature() -> Result<()> {
+    let digest = sha256(MESSAGE)?;
+
+    rsa_verify_pkcs1(&RSA2048_PUBLIC_KEY, &digest, &RSA2048_SHA256_SIGNATURE)
+}
+
+#[test]
+fn rsa_verify_pkcs1_rejects_signature_of_other_message() -> Result<()> {
+    let digest = sha256(b"other message")?;
+
+    let err =
+        rsa_verify_pkcs1(&RSA2048_PUBLIC_KEY, &digest, &RSA2048_SHA256_SIGNATURE).unwrap_err();
+    assert!(matches!(err, Error::CallFailed(ApiName::RSA_verify, _)));
+    Ok(())
+}
+
+#[test]
+fn rsa_verify_pkcs1_rejects_corrupted_signature() -> Result<()> {
+    let digest = sha256(MESSAGE)?;
+    let mut signature = RSA2048_SHA256_SIGNATURE;
+    signature[0] ^= 1;
+
+    let err = rsa_verify_pkcs1(&RSA2048_PUBLIC_KEY, &digest, &signature).unwrap_err();
+    assert!(matches!(err, Error::CallFailed(ApiName::RSA_verify, _)));
+    Ok(())
+}
+
+#[test]
+fn rsa_verify_pkcs1_rejects_malformed_public_key() -> Result<()> {
+    let digest = sha256(MESSAGE)?;
+
+    let err =
+        rsa_verify_pkcs1(&RSA2048_PUBLIC_KEY[1..], &digest, &RSA2048_SHA256_SIGNATURE).unwrap_err();
+    assert!(matches!(err, Error::CallFailed(ApiName::RSA_public_key_from_bytes, _)));
+    Ok(())
+}
+
+#[test]
+fn rsa_verify_pkcs1_rejects_unsupported_digest_size() {
+    let digest = [0; 20];
+
+    assert_eq!(
+        rsa_verify_pkcs1(&RSA2048_PUBLIC_KEY, &digest, &RSA2048_SHA256_SIGNATURE),
+        Err(Error::Unimplemented)
+    );
+}
@@ -15,6 +15,11 @@
 //! API tests of the crate `bssl_avf`.
 
 mod aead_test;
+mod cbb_test;
+mod cbs_test;
+mod cose_test;
+mod digest_test;
 mod eckey_test;
 mod hkdf_test;
 mod hmac_test;
+mod rsa_test;
@@ -0,0 +1,112 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! In-memory device tree builder, to write unit tests without DTS fixtures.
+
+use crate::{Fdt, FdtError, Result};
+use core::ffi::CStr;
+use cstr::cstr;
+use std::ffi::CString;
+
+/// Builds a device tree in a heap-allocated buffer, one node or property at a time.
+pub struct FdtBuilder {
+    buffer: Vec<u8>,
+}
+
+impl FdtBuilder {
+    const DEFAULT_SIZE: usize = 4096;
+
+    /// Creates a builder holding an empty tree, with room for small test trees.
+    pub fn new() -> Result<Self> {
+        Self::with_size(Self::DEFAULT_SIZE)
+    }
+
+    /// Creates a builder holding an empty tree in a buffer of `size` bytes.
+    pub fn with_size(size: usize) -> Result<Self> {
+        let mut buffer = vec![0; size];
+        Fdt::create_empty_tree(&mut buffer)?;
+        Ok(Self { buffer })
+    }
+
+    /// Adds the node at absolute `path`, whose parent must already exist.
+    pub fn add_node(mut self, path: &CStr) -> Result<Self> {
+        let path = path.to_bytes_with_nul();
+        let separator = path.iter().rposition(|c| *c == b'/').ok_or(FdtError::BadPath)?;
+        let name = CStr::from_bytes_with_nul(&path[separator + 1..]).unwrap();
+        let parent = match separator {
+            0 => CString::from(cstr!("/")),
+            _ => CString::new(&path[..separator]).map_err(|_| FdtError::BadPath)?,
+        };
+
+        self.fdt_mut().node_mut(&parent)?.ok_or(FdtError::NotFound)?.add_subnode(name)?;
+        Ok(self)
+    }
+
+    /// Sets the property `name` of the existing node at `path` to `value`.
+    pub fn add_property(mut self, path: &CStr, name: &CStr, value: &[u8]) -> Result<Self> {
+        self.fdt_mut().node_mut(path)?.ok_or(FdtError::NotFound)?.setprop(name, value)?;
+        Ok(self)
+    }
+
+    /// Packs the tree and returns it, shrunk to its packed size.
+    pub fn build(mut self) -> Result<Box<Fdt>> {
+        self.fdt_mut().pack()?;
+        let size = self.fdt_mut().as_slice().len();
+        self.buffer.truncate(size);
+        let buffer = Box::into_raw(self.buffer.into_boxed_slice());
+        // SAFETY: Fdt is a transparent wrapper around [u8], so the pointer keeps its layout and
+        // metadata, and the buffer holds a valid device tree (see fdt_mut()).
+        Ok(unsafe { Box::from_raw(buffer as *mut Fdt) })
+    }
+
+    fn fdt_mut(&mut self) -> &mut Fdt {
+        // SAFETY: The buffer was initialized by Fdt::create_empty_tree() and has only been
+        // modified through Fdt since.
+        unsafe { Fdt::unchecked_from_mut_slice(&mut self.buffer) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::libfdt::Libfdt;
+
+    #[test]
+    fn two_level_tree_reads_back() {
+        let fdt = FdtBuilder::new()
+            .unwrap()
+            .add_node(cstr!("/parent"))
+            .unwrap()
+            .add_node(cstr!("/parent/child"))
+            .unwrap()
+            .add_property(cstr!("/parent/child"), cstr!("value"), &[1, 2, 3])
+            .unwrap()
+            .build()
+            .unwrap();
+
+        let parent = fdt.path_offset_namelen(b"/parent").unwrap().unwrap();
+        let child = fdt.path_offset_namelen(b"/parent/child").unwrap().unwrap();
+        assert_eq!(fdt.parent_offset(child), Ok(parent));
+        assert_eq!(fdt.get_name(child), Ok(b"child".as_slice()));
+        assert_eq!(fdt.getprop_namelen(child, b"value"), Ok(Some([1, 2, 3].as_slice())));
+        assert_eq!(fdt.getprop_namelen(parent, b"value"), Ok(None));
+    }
+
+    #[test]
+    fn add_node_requires_parent() {
+        let builder = FdtBuilder::new().unwrap();
+
+        assert_eq!(builder.add_node(cstr!("/missing/child")).err(), Some(FdtError::NotFound));
+    }
+}
@@ -0,0 +1,66 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Encoding of integers as big-endian device tree cells.
+
+use crate::{FdtError, Result};
+
+const CELL_SIZE: usize = core::mem::size_of::<u32>();
+const MAX_CELLS: usize = 2;
+
+/// Big-endian encoding of an integer over one or two cells, as found in property values.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Cells {
+    bytes: [u8; MAX_CELLS * CELL_SIZE],
+    len: usize,
+}
+
+impl AsRef<[u8]> for Cells {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes[..self.len]
+    }
+}
+
+/// Encodes `val` over `num_cells` big-endian cells.
+///
+/// Fails with `FdtError::BadNCells` if `num_cells` isn't 1 or 2 and with `FdtError::BadValue` if
+/// `val` doesn't fit in `num_cells` cells.
+pub fn cells_from_u64(val: u64, num_cells: usize) -> Result<Cells> {
+    if !(1..=MAX_CELLS).contains(&num_cells) {
+        return Err(FdtError::BadNCells);
+    }
+    let len = num_cells * CELL_SIZE;
+    let bytes = val.to_be_bytes();
+    let (truncated, kept) = bytes.split_at(bytes.len() - len);
+    if truncated.iter().any(|&b| b != 0) {
+        return Err(FdtError::BadValue);
+    }
+
+    let mut cells = Cells { len, ..Default::default() };
+    cells.bytes[..len].copy_from_slice(kept);
+    Ok(cells)
+}
+
+/// Decodes an integer from one or two big-endian cells.
+///
+/// Fails with `FdtError::BadNCells` if `cells` isn't exactly 1 or 2 cells long.
+pub fn u64_from_cells(cells: &[u8]) -> Result<u64> {
+    if let Ok(cell) = cells.try_into() {
+        Ok(u32::from_be_bytes(cell).into())
+    } else if let Ok(cells) = cells.try_into() {
+        Ok(u64::from_be_bytes(cells))
+    } else {
+        Err(FdtError::BadNCells)
+    }
+}
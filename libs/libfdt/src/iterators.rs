@@ -18,6 +18,7 @@ use crate::Fdt;
 use crate::FdtError;
 use crate::FdtNode;
 use crate::FdtProperty;
+use crate::Phandle;
 use crate::{AddrCells, SizeCells};
 use core::ffi::CStr;
 use core::marker::PhantomData;
@@ -74,6 +75,56 @@ impl<'a> Iterator for CellIterator<'a> {
     }
 }
 
+/// Iterator over the `<phandle specifier...>` entries of a DT property, such as
+/// `interrupts-extended`, where the number of specifier cells of each entry is given by a property
+/// of the node referenced by its phandle.
+#[derive(Debug)]
+pub struct PhandleListIterator<'a> {
+    fdt: &'a Fdt,
+    value: &'a [u8],
+    cells_name: &'a CStr,
+}
+
+impl<'a> PhandleListIterator<'a> {
+    pub(crate) fn new(fdt: &'a Fdt, value: &'a [u8], cells_name: &'a CStr) -> Self {
+        Self { fdt, value, cells_name }
+    }
+
+    fn next_entry(&mut self) -> Result<(Phandle, CellIterator<'a>), FdtError> {
+        const CELL_SIZE: usize = size_of::<u32>();
+
+        let phandle = self.value.get(..CELL_SIZE).ok_or(FdtError::BadValue)?;
+        let phandle = Phandle::try_from(u32::from_be_bytes(phandle.try_into().unwrap()))?;
+        let target = self.fdt.node_with_phandle(phandle)?.ok_or(FdtError::NotFound)?;
+        let ncells = target.getprop_u32(self.cells_name)?.ok_or(FdtError::BadNCells)?;
+        let end = usize::try_from(ncells)
+            .ok()
+            .and_then(|n| n.checked_mul(CELL_SIZE))
+            .and_then(|len| len.checked_add(CELL_SIZE))
+            .ok_or(FdtError::BadNCells)?;
+        let specifier = self.value.get(CELL_SIZE..end).ok_or(FdtError::BadValue)?;
+        self.value = &self.value[end..];
+
+        Ok((phandle, CellIterator::new(specifier)))
+    }
+}
+
+impl<'a> Iterator for PhandleListIterator<'a> {
+    type Item = Result<(Phandle, CellIterator<'a>), FdtError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.value.is_empty() {
+            return None;
+        }
+        let res = self.next_entry();
+        if res.is_err() {
+            self.value = &[]; // Stop at the first malformed entry.
+        }
+
+        Some(res)
+    }
+}
+
 /// Iterator over a 'reg' property of a DT node.
 #[derive(Debug)]
 pub struct RegIterator<'a> {
@@ -334,6 +385,34 @@ impl<'a> Iterator for DescendantsIterator<'a> {
     }
 }
 
+/// Iterator over the strings of a <stringlist> property.
+#[derive(Debug)]
+pub struct StringListIterator<'a> {
+    value: &'a [u8],
+}
+
+impl<'a> StringListIterator<'a> {
+    pub(crate) fn new(value: &'a [u8]) -> Result<Self, FdtError> {
+        if value.last().map_or(true, |c| *c == 0) {
+            Ok(Self { value })
+        } else {
+            Err(FdtError::BadValue)
+        }
+    }
+}
+
+impl<'a> Iterator for StringListIterator<'a> {
+    type Item = &'a CStr;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let len = self.value.iter().position(|c| *c == 0)? + 1;
+        let (s, rest) = self.value.split_at(len);
+        self.value = rest;
+
+        CStr::from_bytes_with_nul(s).ok()
+    }
+}
+
 /// Iterator over properties
 #[derive(Debug)]
 pub struct PropertyIterator<'a> {
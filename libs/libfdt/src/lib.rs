@@ -15,21 +15,29 @@
 //! Wrapper around libfdt library. Provides parsing/generating functionality
 //! to a bare-metal environment.
 
-#![no_std]
+#![cfg_attr(not(any(test, feature = "test_utils")), no_std)]
 
+#[cfg(any(test, feature = "test_utils"))]
+mod builder;
+mod cells;
 mod iterators;
 mod libfdt;
 mod result;
 mod safe_types;
 
+#[cfg(any(test, feature = "test_utils"))]
+pub use builder::FdtBuilder;
+pub use cells::{cells_from_u64, u64_from_cells, Cells};
 pub use iterators::{
     AddressRange, CellIterator, CompatibleIterator, DescendantsIterator, MemRegIterator,
-    PropertyIterator, RangesIterator, Reg, RegIterator, SubnodeIterator,
+    PhandleListIterator, PropertyIterator, RangesIterator, Reg, RegIterator, StringListIterator,
+    SubnodeIterator,
 };
 pub use result::{FdtError, Result};
 pub use safe_types::{FdtHeader, NodeOffset, Phandle, PropOffset, StringOffset};
 
 use core::ffi::{c_void, CStr};
+use core::mem::size_of;
 use core::ops::Range;
 use cstr::cstr;
 use libfdt::get_slice_at_ptr;
@@ -173,12 +181,9 @@ impl<'a> FdtNode<'a> {
     /// Returns the standard reg <prop-encoded-array> property.
     pub fn reg(&self) -> Result<Option<RegIterator<'a>>> {
         if let Some(cells) = self.getprop_cells(cstr!("reg"))? {
-            let parent = self.parent()?;
+            let (addr_cells, size_cells) = self.fdt.address_size_cells(self)?;
 
-            let addr_cells = parent.address_cells()?;
-            let size_cells = parent.size_cells()?;
-
-            Ok(Some(RegIterator::new(cells, addr_cells, size_cells)))
+            Ok(Some(RegIterator::new(cells, addr_cells.try_into()?, size_cells.try_into()?)))
         } else {
             Ok(None)
         }
@@ -187,9 +192,9 @@ impl<'a> FdtNode<'a> {
     /// Returns the standard ranges property.
     pub fn ranges<A, P, S>(&self) -> Result<Option<RangesIterator<'a, A, P, S>>> {
         if let Some(cells) = self.getprop_cells(cstr!("ranges"))? {
-            let parent = self.parent()?;
             let addr_cells = self.address_cells()?;
-            let parent_addr_cells = parent.address_cells()?;
+            let (parent_addr_cells, _) = self.fdt.address_size_cells(self)?;
+            let parent_addr_cells = parent_addr_cells.try_into()?;
             let size_cells = self.size_cells()?;
             Ok(Some(RangesIterator::<A, P, S>::new(
                 cells,
@@ -217,6 +222,24 @@ impl<'a> FdtNode<'a> {
         }
     }
 
+    /// Returns the value of a given <string> property, as UTF-8.
+    ///
+    /// Returns `FdtError::BadValue` if the value isn't NUL-terminated or isn't valid UTF-8.
+    pub fn getprop_utf8_str(&self, name: &CStr) -> Result<Option<&'a str>> {
+        let Some(bytes) = self.getprop(name)? else {
+            return Ok(None);
+        };
+        let s = CStr::from_bytes_with_nul(bytes).map_err(|_| FdtError::BadValue)?;
+        Ok(Some(s.to_str().map_err(|_| FdtError::BadValue)?))
+    }
+
+    /// Returns an iterator over the strings of a given <stringlist> property.
+    ///
+    /// Returns `FdtError::BadValue` if the value isn't NUL-terminated.
+    pub fn getprop_str_list(&self, name: &CStr) -> Result<Option<StringListIterator<'a>>> {
+        self.getprop(name)?.map(StringListIterator::new).transpose()
+    }
+
     /// Returns the value of a given property as an array of cells.
     pub fn getprop_cells(&self, name: &CStr) -> Result<Option<CellIterator<'a>>> {
         if let Some(cells) = self.getprop(name)? {
@@ -226,6 +249,33 @@ impl<'a> FdtNode<'a> {
         }
     }
 
+    /// Returns an iterator over the `(phandle, specifier)` entries of a given property, such as
+    /// `interrupts-extended`, where the number of cells of each specifier is read from the
+    /// `cells_name` property (e.g. `#interrupt-cells`) of the node that the phandle references.
+    pub fn getprop_phandle_list(
+        &self,
+        name: &CStr,
+        cells_name: &'a CStr,
+    ) -> Result<Option<PhandleListIterator<'a>>> {
+        let value = self.getprop(name)?;
+
+        Ok(value.map(|value| PhandleListIterator::new(self.fdt, value, cells_name)))
+    }
+
+    /// Returns the value of a given property as an array of <u32> cells.
+    ///
+    /// Unlike `getprop_cells`, fails with `FdtError::BadValue` if the value isn't made of whole
+    /// cells instead of ignoring the trailing bytes.
+    pub fn getprop_u32_array(&self, name: &CStr) -> Result<Option<CellIterator<'a>>> {
+        let Some(bytes) = self.getprop(name)? else {
+            return Ok(None);
+        };
+        if bytes.len() % size_of::<u32>() != 0 {
+            return Err(FdtError::BadValue);
+        }
+        Ok(Some(CellIterator::new(bytes)))
+    }
+
     /// Returns the value of a given <u32> property.
     pub fn getprop_u32(&self, name: &CStr) -> Result<Option<u32>> {
         if let Some(bytes) = self.getprop(name)? {
@@ -249,6 +299,11 @@ impl<'a> FdtNode<'a> {
         self.fdt.getprop_namelen(self.offset, name.to_bytes())
     }
 
+    /// Returns the length of the value of a given property, without borrowing the value.
+    pub fn getprop_len(&self, name: &CStr) -> Result<Option<usize>> {
+        self.fdt.getprop_len(self.offset, name)
+    }
+
     /// Returns reference to the containing device tree.
     pub fn fdt(&self) -> &Fdt {
         self.fdt
@@ -329,6 +384,22 @@ impl<'a> FdtNode<'a> {
         }
     }
 
+    /// Returns the node referenced by the phandle held in the given property.
+    ///
+    /// Returns `FdtError::NotFound` if no node has that phandle and `FdtError::BadPhandle` if the
+    /// property references this node, to avoid loops in callers following the references.
+    pub fn getprop_phandle_target(&self, name: &CStr) -> Result<Option<Self>> {
+        let Some(phandle) = self.getprop_u32(name)? else {
+            return Ok(None);
+        };
+        let offset = self.fdt.node_offset_by_phandle(phandle.try_into()?)?;
+        let target = Self { fdt: self.fdt, offset: offset.ok_or(FdtError::NotFound)? };
+        if target == *self {
+            return Err(FdtError::BadPhandle);
+        }
+        Ok(Some(target))
+    }
+
     /// Returns the subnode of the given name. The name doesn't need to be nul-terminated.
     pub fn subnode(&self, name: &CStr) -> Result<Option<Self>> {
         let name = name.to_bytes();
@@ -433,6 +504,8 @@ impl<'a> FdtNodeMut<'a> {
     }
 
     /// Adds new subnodes to the given node.
+    ///
+    /// Returns `FdtError::Exists` if the node already has a subnode with one of the names.
     pub fn add_subnodes(self, names: &[&CStr]) -> Result<()> {
         for name in names {
             self.fdt.add_subnode_namelen(self.offset, name.to_bytes())?;
@@ -441,6 +514,8 @@ impl<'a> FdtNodeMut<'a> {
     }
 
     /// Adds a new subnode to the given node and return it as a FdtNodeMut on success.
+    ///
+    /// Returns `FdtError::Exists` if the node already has a subnode with that name.
     pub fn add_subnode(self, name: &CStr) -> Result<Self> {
         let name = name.to_bytes();
         let offset = self.fdt.add_subnode_namelen(self.offset, name)?;
@@ -450,6 +525,8 @@ impl<'a> FdtNodeMut<'a> {
 
     /// Adds a new subnode to the given node with name and namelen, and returns it as a FdtNodeMut
     /// on success.
+    ///
+    /// Returns `FdtError::Exists` if the node already has a subnode with that name.
     pub fn add_subnode_with_namelen(self, name: &CStr, namelen: usize) -> Result<Self> {
         let name = &name.to_bytes()[..namelen];
         let offset = self.fdt.add_subnode_namelen(self.offset, name)?;
@@ -671,6 +748,13 @@ impl Fdt {
         LibfdtMut::pack(self)
     }
 
+    /// Packs the DT and checks that it is still valid, e.g. before handing it over to another
+    /// stage after mutating it.
+    pub fn finalize(&mut self) -> Result<()> {
+        self.pack()?;
+        libfdt::check_full(self.as_fdt_slice())
+    }
+
     /// Applies a DT overlay on the base DT.
     ///
     /// # Safety
@@ -742,6 +826,52 @@ impl Fdt {
         CompatibleIterator::new(self, compatible)
     }
 
+    /// Returns the (#address-cells, #size-cells) pair used by [`FdtNode::reg`] to parse the reg
+    /// property of `node`, and by [`FdtNode::ranges`] for its parent addresses.
+    ///
+    /// Both values are read from the parent of `node` only, as they aren't inherited from further
+    /// ancestors, and default to 2 and 1 respectively, as in libfdt. Returns `FdtError::NotFound`
+    /// for the root node, which has no parent.
+    pub fn address_size_cells(&self, node: &FdtNode) -> Result<(usize, usize)> {
+        let parent = node.parent()?;
+        Ok((self.address_cells(parent.offset)?, self.size_cells(parent.offset)?))
+    }
+
+    /// Returns the (#address-cells, #size-cells) pair with which the reg property of `node` is
+    /// parsed, as given by [`Fdt::address_size_cells`], after checking that both are 1 or 2.
+    ///
+    /// Returns `FdtError::BadNCells` otherwise, so that callers parsing addresses and sizes into
+    /// `u64` don't silently truncate wider values.
+    pub fn checked_cells(&self, node: &FdtNode) -> Result<(usize, usize)> {
+        let (address_cells, size_cells) = self.address_size_cells(node)?;
+        if !(1..=2).contains(&address_cells) || !(1..=2).contains(&size_cells) {
+            return Err(FdtError::BadNCells);
+        }
+        Ok((address_cells, size_cells))
+    }
+
+    /// Collects the distinct `compatible` strings of all the nodes of the tree into `out`, in the
+    /// order they are first found, and returns the filled part of `out`.
+    ///
+    /// Returns `FdtError::NoSpace` if the tree has more distinct strings than `out` can hold.
+    pub fn all_compatibles<'a, 'b>(&'a self, out: &'b mut [&'a CStr]) -> Result<&'b [&'a CStr]> {
+        let root = self.root();
+        let mut len = 0;
+        for node in [root].into_iter().chain(root.descendants().map(|(node, _)| node)) {
+            let Some(compatibles) = node.getprop_str_list(cstr!("compatible"))? else {
+                continue;
+            };
+            for compatible in compatibles {
+                if out[..len].contains(&compatible) {
+                    continue;
+                }
+                *out.get_mut(len).ok_or(FdtError::NoSpace)? = compatible;
+                len += 1;
+            }
+        }
+        Ok(&out[..len])
+    }
+
     /// Returns max phandle in the tree.
     pub fn max_phandle(&self) -> Result<Phandle> {
         self.find_max_phandle()
@@ -773,6 +903,74 @@ impl Fdt {
         Ok(offset.map(|offset| FdtNodeMut { fdt: self, offset }))
     }
 
+    /// Returns a mutable tree node by its full path, first adding it and any missing ancestor.
+    ///
+    /// Returns `FdtError::BadPath` if `path` isn't absolute.
+    pub fn get_or_add_node(&mut self, path: &CStr) -> Result<FdtNodeMut> {
+        let path = path.to_bytes().strip_prefix(b"/").ok_or(FdtError::BadPath)?;
+        let mut offset = NodeOffset::ROOT;
+        for name in path.split(|c| *c == b'/').filter(|name| !name.is_empty()) {
+            offset = match self.subnode_offset_namelen(offset, name)? {
+                Some(subnode) => subnode,
+                None => self.add_subnode_namelen(offset, name)?,
+            };
+        }
+
+        Ok(FdtNodeMut { fdt: self, offset })
+    }
+
+    /// Deletes the node at `path`, along with its subnodes, by setting it with FDT_NOP.
+    ///
+    /// If the node doesn't exist, returns `Ok(())` when `ignore_missing` is set and
+    /// `Err(FdtError::NotFound)` otherwise.
+    pub fn delete_node_by_path(&mut self, path: &CStr, ignore_missing: bool) -> Result<()> {
+        match self.path_offset_namelen(path.to_bytes())? {
+            Some(offset) => self.nop_node(offset),
+            None if ignore_missing => Ok(()),
+            None => Err(FdtError::NotFound),
+        }
+    }
+
+    /// Sets a property of the node at `path`, creating or replacing it.
+    ///
+    /// If the DT is full, it is first grown to cover the whole of its backing buffer and the
+    /// update is retried once. Returns `Err(FdtError::NoSpace)` if the property doesn't fit in the
+    /// backing buffer either.
+    pub fn set_property_grow(&mut self, path: &CStr, name: &CStr, value: &[u8]) -> Result<()> {
+        let offset = self.path_offset_namelen(path.to_bytes())?.ok_or(FdtError::NotFound)?;
+        match self.setprop(offset, name, value) {
+            Err(FdtError::NoSpace) if self.totalsize() < self.buffer.len() => {
+                // Node offsets are relative to the structure block so remain valid after this.
+                self.unpack()?;
+                self.setprop(offset, name, value)
+            }
+            result => result,
+        }
+    }
+
+    /// Copies the property `name` of the node at `src_path` to the node at `dst_path`, creating
+    /// or replacing it there.
+    ///
+    /// Nodes are given by path as making room for the new property may move the source node.
+    pub fn copy_property(&mut self, src_path: &CStr, dst_path: &CStr, name: &CStr) -> Result<()> {
+        let src = self.path_offset_namelen(src_path.to_bytes())?.ok_or(FdtError::NotFound)?;
+        let len = self.getprop_namelen(src, name.to_bytes())?.ok_or(FdtError::NotFound)?.len();
+        let dst = self.path_offset_namelen(dst_path.to_bytes())?.ok_or(FdtError::NotFound)?;
+        if src == dst {
+            return Ok(());
+        }
+
+        let dst_addr = self.setprop_placeholder(dst, name, len)?.as_ptr() as usize;
+        // The placeholder may have moved the source value, so only look it up now.
+        let src = self.path_offset_namelen(src_path.to_bytes())?.ok_or(FdtError::Internal)?;
+        let value = self.getprop_namelen(src, name.to_bytes())?.ok_or(FdtError::Internal)?;
+        let base = self.as_fdt_slice().as_ptr() as usize;
+        let src_start = value.as_ptr() as usize - base;
+        let dst_start = dst_addr - base;
+        self.as_fdt_slice_mut().copy_within(src_start..(src_start + len), dst_start);
+        Ok(())
+    }
+
     fn next_node_skip_subnodes(
         &self,
         node: NodeOffset,
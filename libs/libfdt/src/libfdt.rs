@@ -239,6 +239,19 @@ pub(crate) unsafe trait Libfdt {
         }
     }
 
+    /// Safe wrapper around `fdt_getprop()` (C function), only returning the length of the value.
+    fn getprop_len(&self, node: NodeOffset, name: &CStr) -> Result<Option<usize>> {
+        let fdt = self.as_fdt_slice().as_ptr().cast();
+        let node = node.into();
+        let name = name.as_ptr();
+        let mut len = 0;
+        // SAFETY: Accesses (read-only) are constrained to the DT totalsize (validated by ctor) and
+        // the returned pointer to the value is dropped.
+        let _ = unsafe { libfdt_bindgen::fdt_getprop(fdt, node, name, &mut len) };
+
+        FdtRawResult::from(len).try_into()
+    }
+
     /// Safe wrapper around `fdt_get_property_by_offset()` (C function).
     fn get_property_by_offset(&self, offset: PropOffset) -> Result<&libfdt_bindgen::fdt_property> {
         let mut len = 0;
@@ -298,16 +311,23 @@ pub(crate) unsafe trait Libfdt {
         phandle.try_into()
     }
 
-    /// Safe wrapper around `fdt_string()` (C function).
+    /// Safe wrapper around `fdt_get_string()` (C function).
+    ///
+    /// Unlike `fdt_string()`, the string is only looked up within the strings block and its NUL
+    /// terminator must be found there.
     fn string(&self, offset: StringOffset) -> Result<&CStr> {
         let fdt = self.as_fdt_slice().as_ptr().cast();
         let offset = offset.into();
-        // SAFETY: Accesses (read-only) are constrained to the DT totalsize.
-        let ptr = unsafe { libfdt_bindgen::fdt_string(fdt, offset) };
+        let mut len = 0;
+        // SAFETY: Accesses (read-only) are constrained to the strings block of the DT, within its
+        // totalsize. On success, the function returns a null terminated string of length `len`
+        // and otherwise returned values are dropped.
+        let ptr = unsafe { libfdt_bindgen::fdt_get_string(fdt, offset, &mut len) };
+        let len = usize::try_from(FdtRawResult::from(len))?.checked_add(1).unwrap();
         let bytes =
-            get_slice_from_ptr(self.as_fdt_slice(), ptr.cast()).ok_or(FdtError::Internal)?;
+            get_slice_at_ptr(self.as_fdt_slice(), ptr.cast(), len).ok_or(FdtError::Internal)?;
 
-        CStr::from_bytes_until_nul(bytes).map_err(|_| FdtError::Internal)
+        CStr::from_bytes_with_nul(bytes).map_err(|_| FdtError::Internal)
     }
 
     /// Safe wrapper around `fdt_open_into()` (C function).
@@ -525,10 +545,6 @@ fn get_mut_slice_at_ptr(s: &mut [u8], p: *mut u8, len: usize) -> Option<&mut [u8
     s.get_mut(offset..offset.checked_add(len)?)
 }
 
-fn get_slice_from_ptr(s: &[u8], p: *const u8) -> Option<&[u8]> {
-    s.get(get_slice_ptr_offset(s, p)?..)
-}
-
 fn get_slice_ptr_offset(s: &[u8], p: *const u8) -> Option<usize> {
     s.as_ptr_range().contains(&p).then(|| {
         // SAFETY: Both pointers are in bounds, derive from the same object, and size_of::<T>()=1.
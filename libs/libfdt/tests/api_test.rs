@@ -18,7 +18,7 @@
 
 use core::ffi::CStr;
 use cstr::cstr;
-use libfdt::{Fdt, FdtError, FdtNodeMut, Phandle};
+use libfdt::{cells_from_u64, u64_from_cells, Fdt, FdtBuilder, FdtError, FdtNodeMut, Phandle};
 use std::collections::HashSet;
 use std::ffi::CString;
 use std::fs;
@@ -125,6 +125,43 @@ fn node_properties() {
     assert_eq!(subnode_properties, expected);
 }
 
+#[test]
+fn node_getprop_len() {
+    let data = fs::read(TEST_TREE_WITH_NO_MEMORY_NODE_PATH).unwrap();
+    let fdt = Fdt::from_slice(&data).unwrap();
+    let root = fdt.root();
+
+    for prop in root.properties().unwrap() {
+        let name = prop.name().unwrap();
+        let len = prop.value().unwrap().len();
+        assert_eq!(root.getprop_len(name), Ok(Some(len)), "{name:?}");
+    }
+    assert_eq!(root.getprop_len(cstr!("nonexistent")), Ok(None));
+}
+
+#[test]
+fn node_property_name_missing_terminator() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().setprop_empty(cstr!("name")).unwrap();
+    fdt.pack().unwrap();
+    let len = fdt.as_slice().len();
+    let mut data = data[..len].to_vec();
+
+    // The strings block comes last, so its final byte is the terminator of the property name.
+    let off_dt_strings = u32::from_be_bytes(data[12..16].try_into().unwrap()) as usize;
+    let size_dt_strings = u32::from_be_bytes(data[32..36].try_into().unwrap()) as usize;
+    assert_eq!(off_dt_strings + size_dt_strings, len);
+    data[len - 1] = b'x';
+
+    assert!(Fdt::from_slice(&data).is_err());
+    // SAFETY: Only the strings block was corrupted, which string reads are expected to catch.
+    let fdt = unsafe { Fdt::unchecked_from_slice(&data) };
+    let root = fdt.root();
+    let names: Vec<_> = root.properties().unwrap().map(|prop| prop.name()).collect();
+    assert_eq!(names, [Err(FdtError::Truncated)]);
+}
+
 #[test]
 fn node_supernode_at_depth() {
     let data = fs::read(TEST_TREE_WITH_NO_MEMORY_NODE_PATH).unwrap();
@@ -373,6 +410,95 @@ fn node_descendants() {
     );
 }
 
+#[test]
+fn delete_node_by_path() {
+    let mut data = fs::read(TEST_TREE_PHANDLE_PATH).unwrap();
+    let fdt = Fdt::from_mut_slice(&mut data).unwrap();
+
+    let name = cstr!("/node_z/node_zz");
+    assert!(fdt.node(name).unwrap().is_some());
+
+    fdt.delete_node_by_path(name, false).unwrap();
+
+    assert_eq!(fdt.node(name), Ok(None));
+    assert!(fdt.node(cstr!("/node_z")).unwrap().is_some());
+}
+
+#[test]
+fn delete_node_by_path_missing_node() {
+    let mut data = fs::read(TEST_TREE_PHANDLE_PATH).unwrap();
+    let fdt = Fdt::from_mut_slice(&mut data).unwrap();
+
+    let name = cstr!("/node_z/node_missing");
+
+    assert_eq!(fdt.delete_node_by_path(name, true), Ok(()));
+    assert_eq!(fdt.delete_node_by_path(name, false), Err(FdtError::NotFound));
+}
+
+#[test]
+fn set_property_grow() {
+    let mut data = fs::read(TEST_TREE_PHANDLE_PATH).unwrap();
+    data.resize(data.len() + 256, 0);
+    let fdt = Fdt::from_mut_slice(&mut data).unwrap();
+    fdt.pack().unwrap();
+    let path = cstr!("/node_a");
+    let name = cstr!("grown");
+    let value = [0x55; 128];
+
+    let mut node = fdt.node_mut(path).unwrap().unwrap();
+    assert_eq!(node.setprop(name, &value), Err(FdtError::NoSpace));
+
+    assert_eq!(fdt.set_property_grow(path, name, &value), Ok(()));
+    let node = fdt.node(path).unwrap().unwrap();
+    assert_eq!(node.getprop(name), Ok(Some(&value[..])));
+}
+
+#[test]
+fn set_property_grow_beyond_buffer() {
+    let mut data = fs::read(TEST_TREE_PHANDLE_PATH).unwrap();
+    data.resize(data.len() + 256, 0);
+    let fdt = Fdt::from_mut_slice(&mut data).unwrap();
+    fdt.pack().unwrap();
+
+    let value = [0x55; 512];
+    assert_eq!(
+        fdt.set_property_grow(cstr!("/node_a"), cstr!("big"), &value),
+        Err(FdtError::NoSpace)
+    );
+    assert_eq!(
+        fdt.set_property_grow(cstr!("/node_missing"), cstr!("small"), &[]),
+        Err(FdtError::NotFound)
+    );
+}
+
+#[test]
+fn finalize_mutated_tree() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnode(cstr!("a")).unwrap().setprop(cstr!("p"), b"value\0").unwrap();
+
+    assert_eq!(fdt.finalize(), Ok(()));
+    assert!(fdt.as_slice().len() < 1000);
+    let node = fdt.node(cstr!("/a")).unwrap().unwrap();
+    assert_eq!(node.getprop(cstr!("p")), Ok(Some(&b"value\0"[..])));
+}
+
+#[test]
+fn finalize_rejects_corrupted_tree() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnode(cstr!("a")).unwrap();
+
+    // Replace the FDT_BEGIN_NODE token of the root node, at the start of the structure block.
+    let off_dt_struct = u32::from_be_bytes(data[8..12].try_into().unwrap());
+    let off_dt_struct = usize::try_from(off_dt_struct).unwrap();
+    data[off_dt_struct..(off_dt_struct + 4)].copy_from_slice(&0xdead_beef_u32.to_be_bytes());
+    // SAFETY: Only the structure block was corrupted, which finalize() is expected to catch.
+    let fdt = unsafe { Fdt::unchecked_from_mut_slice(&mut data) };
+
+    assert_eq!(fdt.finalize(), Err(FdtError::BadStructure));
+}
+
 #[test]
 fn node_mut_delete_and_next_subnode() {
     let mut data = fs::read(TEST_TREE_PHANDLE_PATH).unwrap();
@@ -483,6 +609,278 @@ fn node_mut_add_subnodes() {
     assert_eq!(expected, names);
 }
 
+#[test]
+fn node_mut_add_existing_subnode() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnode(cstr!("a")).unwrap();
+
+    assert_eq!(fdt.root_mut().add_subnode(cstr!("a")).err(), Some(FdtError::Exists));
+    assert_eq!(
+        fdt.root_mut().add_subnode_with_namelen(cstr!("ab"), 1).err(),
+        Some(FdtError::Exists)
+    );
+    assert_eq!(fdt.root_mut().add_subnodes(&[cstr!("b"), cstr!("a")]), Err(FdtError::Exists));
+    assert_eq!(fdt.root().subnodes().unwrap().count(), 2);
+}
+
+#[test]
+fn get_or_add_node_creates_missing_ancestors() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnode(cstr!("a")).unwrap();
+
+    let mut node = fdt.get_or_add_node(cstr!("/a/b/c")).unwrap();
+    node.setprop(cstr!("p"), b"value\0").unwrap();
+    assert!(fdt.node(cstr!("/a/b")).unwrap().is_some());
+
+    let node = fdt.get_or_add_node(cstr!("/a/b/c")).unwrap();
+    assert_eq!(node.as_node().getprop(cstr!("p")), Ok(Some(&b"value\0"[..])));
+    assert_eq!(fdt.node(cstr!("/a")).unwrap().unwrap().subnodes().unwrap().count(), 1);
+    assert_eq!(fdt.get_or_add_node(cstr!("a/b")).err(), Some(FdtError::BadPath));
+}
+
+#[test]
+fn getprop_phandle_target() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnodes(&[cstr!("a"), cstr!("b")]).unwrap();
+    let mut a = fdt.node_mut(cstr!("/a")).unwrap().unwrap();
+    a.setprop(cstr!("phandle"), &1_u32.to_be_bytes()).unwrap();
+    a.setprop(cstr!("self"), &1_u32.to_be_bytes()).unwrap();
+    let mut b = fdt.node_mut(cstr!("/b")).unwrap().unwrap();
+    b.setprop(cstr!("target"), &1_u32.to_be_bytes()).unwrap();
+    b.setprop(cstr!("dangling"), &2_u32.to_be_bytes()).unwrap();
+
+    let a = fdt.node(cstr!("/a")).unwrap().unwrap();
+    let b = fdt.node(cstr!("/b")).unwrap().unwrap();
+    assert_eq!(b.getprop_phandle_target(cstr!("target")), Ok(Some(a)));
+    assert_eq!(b.getprop_phandle_target(cstr!("dangling")), Err(FdtError::NotFound));
+    assert_eq!(b.getprop_phandle_target(cstr!("missing")), Ok(None));
+    assert_eq!(a.getprop_phandle_target(cstr!("self")), Err(FdtError::BadPhandle));
+}
+
+#[test]
+fn getprop_phandle_list() {
+    fn cells(values: &[u32]) -> Vec<u8> {
+        values.iter().flat_map(|v| v.to_be_bytes()).collect()
+    }
+
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnodes(&[cstr!("intc1"), cstr!("intc2"), cstr!("dev")]).unwrap();
+    let mut intc1 = fdt.node_mut(cstr!("/intc1")).unwrap().unwrap();
+    intc1.setprop(cstr!("phandle"), &cells(&[1])).unwrap();
+    intc1.setprop(cstr!("#interrupt-cells"), &cells(&[3])).unwrap();
+    let mut intc2 = fdt.node_mut(cstr!("/intc2")).unwrap().unwrap();
+    intc2.setprop(cstr!("phandle"), &cells(&[2])).unwrap();
+    intc2.setprop(cstr!("#interrupt-cells"), &cells(&[1])).unwrap();
+    let mut dev = fdt.node_mut(cstr!("/dev")).unwrap().unwrap();
+    dev.setprop(cstr!("interrupts-extended"), &cells(&[1, 0, 4, 8, 2, 9])).unwrap();
+    dev.setprop(cstr!("truncated"), &cells(&[1, 0, 4])).unwrap();
+
+    let dev = fdt.node(cstr!("/dev")).unwrap().unwrap();
+    let entries: Vec<_> = dev
+        .getprop_phandle_list(cstr!("interrupts-extended"), cstr!("#interrupt-cells"))
+        .unwrap()
+        .unwrap()
+        .map(|entry| {
+            let (phandle, specifier) = entry.unwrap();
+            (u32::from(phandle), specifier.collect::<Vec<_>>())
+        })
+        .collect();
+    assert_eq!(entries, [(1, vec![0, 4, 8]), (2, vec![9])]);
+
+    let mut truncated =
+        dev.getprop_phandle_list(cstr!("truncated"), cstr!("#interrupt-cells")).unwrap().unwrap();
+    assert_eq!(truncated.next().map(|entry| entry.err()), Some(Some(FdtError::BadValue)));
+    assert!(truncated.next().is_none());
+}
+
+#[test]
+fn compatible_nodes_iterates_over_all_matching_nodes() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+
+    let compatible = cstr!("test,device");
+    for name in [cstr!("a"), cstr!("b"), cstr!("c")] {
+        let mut node = fdt.root_mut().add_subnode(name).unwrap();
+        let value: &[u8] = if name == cstr!("b") { b"test,other\0" } else { b"test,device\0" };
+        node.setprop(cstr!("compatible"), value).unwrap();
+    }
+
+    let names: Vec<_> =
+        fdt.compatible_nodes(compatible).unwrap().map(|node| node.name().unwrap()).collect();
+
+    assert_eq!(names, vec![cstr!("a"), cstr!("c")]);
+}
+
+#[test]
+fn node_getprop_utf8_str() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    let mut root = fdt.root_mut();
+    root.setprop(cstr!("valid"), b"console=hvc0\0").unwrap();
+    root.setprop(cstr!("not-terminated"), b"console=hvc0").unwrap();
+    root.setprop(cstr!("invalid-utf8"), b"console=\xff\0").unwrap();
+
+    let root = fdt.root();
+    assert_eq!(root.getprop_utf8_str(cstr!("valid")), Ok(Some("console=hvc0")));
+    assert_eq!(root.getprop_utf8_str(cstr!("not-terminated")), Err(FdtError::BadValue));
+    assert_eq!(root.getprop_utf8_str(cstr!("invalid-utf8")), Err(FdtError::BadValue));
+    assert_eq!(root.getprop_utf8_str(cstr!("missing")), Ok(None));
+}
+
+#[test]
+fn node_getprop_u32_array() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    let mut root = fdt.root_mut();
+    root.setprop(cstr!("interrupts"), &[0, 0, 0, 1, 0, 0, 0, 0x2a, 0, 0, 0, 4]).unwrap();
+    root.setprop(cstr!("misaligned"), &[0, 0, 0, 1, 0, 0]).unwrap();
+
+    let root = fdt.root();
+    let cells = root.getprop_u32_array(cstr!("interrupts")).unwrap().unwrap();
+    assert_eq!(cells.collect::<Vec<_>>(), [1, 0x2a, 4]);
+    assert_eq!(root.getprop_u32_array(cstr!("misaligned")).unwrap_err(), FdtError::BadValue);
+    assert!(root.getprop_u32_array(cstr!("missing")).unwrap().is_none());
+}
+
+#[test]
+fn address_size_cells_are_read_from_the_parent_only() {
+    let fdt = FdtBuilder::new()
+        .unwrap()
+        .add_property(cstr!("/"), cstr!("#address-cells"), &1_u32.to_be_bytes())
+        .unwrap()
+        .add_property(cstr!("/"), cstr!("#size-cells"), &2_u32.to_be_bytes())
+        .unwrap()
+        .add_node(cstr!("/a"))
+        .unwrap()
+        .add_property(cstr!("/a"), cstr!("#size-cells"), &0_u32.to_be_bytes())
+        .unwrap()
+        .add_node(cstr!("/a/b"))
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let root = fdt.root();
+    assert_eq!(fdt.address_size_cells(&root), Err(FdtError::NotFound));
+    let node = fdt.node(cstr!("/a")).unwrap().unwrap();
+    assert_eq!(fdt.address_size_cells(&node), Ok((1, 2)));
+    let node = fdt.node(cstr!("/a/b")).unwrap().unwrap();
+    assert_eq!(fdt.address_size_cells(&node), Ok((2, 0)));
+}
+
+#[test]
+fn address_size_cells_default_to_libfdt_defaults() {
+    let fdt = FdtBuilder::new().unwrap().add_node(cstr!("/a")).unwrap().build().unwrap();
+
+    let node = fdt.node(cstr!("/a")).unwrap().unwrap();
+    assert_eq!(fdt.address_size_cells(&node), Ok((2, 1)));
+}
+
+#[test]
+fn reg_is_parsed_with_address_size_cells() {
+    let reg = [0x1000_u32, 0x2000, 0x100].map(u32::to_be_bytes).concat();
+    let fdt = FdtBuilder::new()
+        .unwrap()
+        .add_property(cstr!("/"), cstr!("#address-cells"), &1_u32.to_be_bytes())
+        .unwrap()
+        .add_node(cstr!("/a"))
+        .unwrap()
+        .add_property(cstr!("/a"), cstr!("reg"), &reg)
+        .unwrap()
+        .build()
+        .unwrap();
+
+    let node = fdt.node(cstr!("/a")).unwrap().unwrap();
+    assert_eq!(fdt.address_size_cells(&node), Ok((1, 1)));
+    let reg = node.reg().unwrap().unwrap().map(|r| (r.addr, r.size)).collect::<Vec<_>>();
+    assert_eq!(reg, [(0x1000, Some(0x2000))]);
+}
+
+#[test]
+fn checked_cells_accepts_two_address_and_size_cells() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    let mut root = fdt.root_mut();
+    root.setprop(cstr!("#address-cells"), &2_u32.to_be_bytes()).unwrap();
+    root.setprop(cstr!("#size-cells"), &2_u32.to_be_bytes()).unwrap();
+    root.add_subnode(cstr!("a")).unwrap();
+
+    let node = fdt.node(cstr!("/a")).unwrap().unwrap();
+    assert_eq!(fdt.checked_cells(&node), Ok((2, 2)));
+}
+
+#[test]
+fn checked_cells_rejects_out_of_range_cells() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    let mut root = fdt.root_mut();
+    root.setprop(cstr!("#address-cells"), &3_u32.to_be_bytes()).unwrap();
+    root.setprop(cstr!("#size-cells"), &1_u32.to_be_bytes()).unwrap();
+    let mut node = root.add_subnode(cstr!("a")).unwrap();
+    node.setprop(cstr!("#address-cells"), &1_u32.to_be_bytes()).unwrap();
+    node.setprop(cstr!("#size-cells"), &0_u32.to_be_bytes()).unwrap();
+    node.add_subnode(cstr!("b")).unwrap();
+
+    let node = fdt.node(cstr!("/a")).unwrap().unwrap();
+    assert_eq!(fdt.checked_cells(&node), Err(FdtError::BadNCells));
+    let node = fdt.node(cstr!("/a/b")).unwrap().unwrap();
+    assert_eq!(fdt.checked_cells(&node), Err(FdtError::BadNCells));
+}
+
+#[test]
+fn checked_cells_matches_the_cells_used_by_reg() {
+    let reg = [0_u32, 0x1000, 0x100].map(u32::to_be_bytes).concat();
+    let fdt = FdtBuilder::new()
+        .unwrap()
+        .add_property(cstr!("/"), cstr!("#address-cells"), &3_u32.to_be_bytes())
+        .unwrap()
+        .add_property(cstr!("/"), cstr!("#size-cells"), &3_u32.to_be_bytes())
+        .unwrap()
+        .add_node(cstr!("/a"))
+        .unwrap()
+        .add_node(cstr!("/a/b"))
+        .unwrap()
+        .add_property(cstr!("/a/b"), cstr!("reg"), &reg)
+        .unwrap()
+        .build()
+        .unwrap();
+
+    // The cells of the root don't apply to /a/b, which uses the defaults of /a.
+    let node = fdt.node(cstr!("/a/b")).unwrap().unwrap();
+    assert_eq!(fdt.checked_cells(&node), Ok((2, 1)));
+    let reg = node.reg().unwrap().unwrap().map(|r| (r.addr, r.size)).collect::<Vec<_>>();
+    assert_eq!(reg, [(0x1000, Some(0x100))]);
+}
+
+#[test]
+fn cells_round_trip_single_cell() {
+    let cells = cells_from_u64(0x1234_5678, 1).unwrap();
+
+    assert_eq!(cells.as_ref(), [0x12, 0x34, 0x56, 0x78]);
+    assert_eq!(u64_from_cells(cells.as_ref()), Ok(0x1234_5678));
+    assert_eq!(cells_from_u64(0x1_0000_0000, 1), Err(FdtError::BadValue));
+}
+
+#[test]
+fn cells_round_trip_double_cell() {
+    let val = 0x8000_0001_0000_0002;
+    let cells = cells_from_u64(val, 2).unwrap();
+
+    assert_eq!(cells.as_ref(), [0x80, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02]);
+    assert_eq!(u64_from_cells(cells.as_ref()), Ok(val));
+    assert_eq!(u64_from_cells(cells_from_u64(7, 2).unwrap().as_ref()), Ok(7));
+}
+
+#[test]
+fn cells_reject_unsupported_cell_count() {
+    assert_eq!(cells_from_u64(0, 0), Err(FdtError::BadNCells));
+    assert_eq!(cells_from_u64(0, 3), Err(FdtError::BadNCells));
+    assert_eq!(u64_from_cells(&[0; 6]), Err(FdtError::BadNCells));
+}
+
 #[test]
 #[ignore] // Borrow checker test. Compilation success is sufficient.
 fn node_subnode_lifetime() {
@@ -545,3 +943,51 @@ fn node_descendants_lifetime() {
     };
     assert_eq!(Ok(cstr!("node_a")), first_descendant_name);
 }
+
+#[test]
+fn copy_property_between_nodes() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnodes(&[cstr!("dst"), cstr!("src")]).unwrap();
+    let mut src = fdt.node_mut(cstr!("/src")).unwrap().unwrap();
+    src.setprop(cstr!("p"), b"value\0").unwrap();
+
+    fdt.copy_property(cstr!("/src"), cstr!("/dst"), cstr!("p")).unwrap();
+
+    let src = fdt.node(cstr!("/src")).unwrap().unwrap();
+    let dst = fdt.node(cstr!("/dst")).unwrap().unwrap();
+    assert_eq!(src.getprop(cstr!("p")), Ok(Some(&b"value\0"[..])));
+    assert_eq!(dst.getprop(cstr!("p")), Ok(Some(&b"value\0"[..])));
+    assert_eq!(
+        fdt.copy_property(cstr!("/src"), cstr!("/dst"), cstr!("missing")),
+        Err(FdtError::NotFound)
+    );
+}
+
+#[test]
+fn all_compatibles_are_listed_once() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().add_subnodes(&[cstr!("a"), cstr!("b"), cstr!("c")]).unwrap();
+    let mut node = fdt.node_mut(cstr!("/a")).unwrap().unwrap();
+    node.setprop(cstr!("compatible"), b"vendor,dev\0generic-dev\0").unwrap();
+    let mut node = fdt.node_mut(cstr!("/b")).unwrap().unwrap();
+    node.setprop(cstr!("compatible"), b"generic-dev\0").unwrap();
+
+    let mut compatibles = [cstr!(""); 2];
+    let compatibles = fdt.all_compatibles(&mut compatibles).unwrap();
+    assert_eq!(compatibles, [cstr!("vendor,dev"), cstr!("generic-dev")]);
+
+    let mut too_small = [cstr!(""); 1];
+    assert_eq!(fdt.all_compatibles(&mut too_small), Err(FdtError::NoSpace));
+}
+
+#[test]
+fn getprop_str_list_rejects_unterminated_value() {
+    let mut data = vec![0_u8; 1000];
+    let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+    fdt.root_mut().setprop(cstr!("compatible"), b"vendor,dev").unwrap();
+
+    let err = fdt.root().getprop_str_list(cstr!("compatible")).unwrap_err();
+    assert_eq!(err, FdtError::BadValue);
+}
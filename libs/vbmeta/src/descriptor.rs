@@ -14,14 +14,16 @@
 
 use avb_bindgen::{
     avb_descriptor_foreach, avb_descriptor_validate_and_byteswap,
-    avb_hashtree_descriptor_validate_and_byteswap, AvbDescriptor, AvbDescriptorTag,
-    AvbHashtreeDescriptor,
+    avb_hash_descriptor_validate_and_byteswap, avb_hashtree_descriptor_validate_and_byteswap,
+    AvbDescriptor, AvbDescriptorTag, AvbHashDescriptor, AvbHashtreeDescriptor,
 };
-use std::ffi::c_void;
+use openssl::hash::{DigestBytes, Hasher, MessageDigest};
+use openssl::memcmp;
+use std::ffi::{c_void, CStr};
 use std::mem::{size_of, MaybeUninit};
 use std::slice;
 
-use super::VbMetaImageParseError;
+use super::{VbMetaImageParseError, VbMetaImageVerificationError};
 
 /// The descriptors from a VBMeta image.
 pub struct Descriptors<'a> {
@@ -39,12 +41,29 @@ pub enum Descriptor<'a> {
     Unknown,
 }
 
+/// Outcome of the verification of a partition image by [`Descriptors::verify_partitions`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PartitionVerification {
+    /// The image matches the digest of the hash or hashtree descriptor of the partition.
+    Verified,
+    /// The image doesn't match the digest of the descriptor of the partition.
+    HashMismatch,
+    /// No descriptor covers the partition.
+    Missing,
+}
+
 /// A hashtree descriptor.
 pub struct HashtreeDescriptor<'a> {
     descriptor: AvbHashtreeDescriptor,
     data: &'a [u8],
 }
 
+/// A hash descriptor.
+pub struct HashDescriptor<'a> {
+    descriptor: AvbHashDescriptor,
+    data: &'a [u8],
+}
+
 impl Descriptors<'_> {
     /// Find the descriptors in a well-formed VBMeta image.
     pub(super) fn from_image(data: &[u8]) -> Result<Descriptors<'_>, VbMetaImageParseError> {
@@ -99,6 +118,67 @@ impl Descriptors<'_> {
     pub fn iter(&self) -> slice::Iter<Descriptor> {
         self.descriptors.iter()
     }
+
+    /// Get an iterator over the hash descriptors, in the order of the descriptors.
+    pub fn hash_descriptors(
+        &self,
+    ) -> impl Iterator<Item = Result<HashDescriptor, VbMetaImageParseError>> + '_ {
+        self.descriptors
+            .iter()
+            .filter(|d| matches!(d, Descriptor::Hash(_)))
+            .map(Descriptor::to_hash)
+    }
+
+    /// Get an iterator over the names of the partitions covered by the hash and hashtree
+    /// descriptors, in the order of the descriptors.
+    pub fn partition_names(
+        &self,
+    ) -> impl Iterator<Item = Result<&[u8], VbMetaImageParseError>> + '_ {
+        self.descriptors.iter().filter_map(|descriptor| match descriptor {
+            Descriptor::Hash(_) => Some(descriptor.to_hash().map(|d| d.partition_name())),
+            Descriptor::Hashtree(_) => Some(descriptor.to_hashtree().map(|d| d.partition_name())),
+            _ => None,
+        })
+    }
+
+    /// Verify the images of partitions, given as `(partition name, image)` pairs, against the
+    /// descriptors covering them.
+    ///
+    /// Returns the outcome of the verification of each partition, in the order of `images`.
+    pub fn verify_partitions<'i>(
+        &self,
+        images: &[(&'i [u8], &[u8])],
+    ) -> Result<Vec<(&'i [u8], PartitionVerification)>, VbMetaImageVerificationError> {
+        let hash_descriptors = self.hash_descriptors().collect::<Result<Vec<_>, _>>()?;
+        let hashtree_descriptors = self
+            .descriptors
+            .iter()
+            .filter(|d| matches!(d, Descriptor::Hashtree(_)))
+            .map(Descriptor::to_hashtree)
+            .collect::<Result<Vec<_>, _>>()?;
+        images
+            .iter()
+            .map(|&(name, image)| {
+                let result =
+                    if let Some(d) = hash_descriptors.iter().find(|d| d.partition_name() == name) {
+                        d.verify(image)
+                    } else if let Some(d) =
+                        hashtree_descriptors.iter().find(|d| d.partition_name() == name)
+                    {
+                        d.verify(image)
+                    } else {
+                        return Ok((name, PartitionVerification::Missing));
+                    };
+                match result {
+                    Ok(()) => Ok((name, PartitionVerification::Verified)),
+                    Err(VbMetaImageVerificationError::HashMismatch) => {
+                        Ok((name, PartitionVerification::HashMismatch))
+                    }
+                    Err(e) => Err(e),
+                }
+            })
+            .collect()
+    }
 }
 
 impl<'a> IntoIterator for Descriptors<'a> {
@@ -130,10 +210,115 @@ impl Descriptor<'_> {
         }
     }
 
+    /// Parse the descriptor as a hash descriptor.
+    pub fn to_hash(&self) -> Result<HashDescriptor, VbMetaImageParseError> {
+        match self {
+            Self::Hash(data) => {
+                // SAFETY: data contains the entire descriptor.
+                let descriptor = unsafe {
+                    let mut desc = MaybeUninit::uninit();
+                    let src = data.as_ptr() as *const _ as *const AvbHashDescriptor;
+                    if !avb_hash_descriptor_validate_and_byteswap(src, desc.as_mut_ptr()) {
+                        return Err(VbMetaImageParseError::InvalidDescriptor);
+                    }
+                    desc.assume_init()
+                };
+                Ok(HashDescriptor { descriptor, data })
+            }
+            _ => Err(VbMetaImageParseError::InvalidDescriptor),
+        }
+    }
+
     // TODO: handle other descriptor type as required
 }
 
-impl HashtreeDescriptor<'_> {
+impl<'a> HashDescriptor<'a> {
+    /// Get the name of the partition covered by the descriptor.
+    pub fn partition_name(&self) -> &'a [u8] {
+        let begin = size_of::<AvbHashDescriptor>();
+        let end = begin + self.descriptor.partition_name_len as usize;
+        &self.data[begin..end]
+    }
+
+    /// Get the size in bytes of the image covered by the descriptor.
+    pub fn image_size(&self) -> u64 {
+        self.descriptor.image_size
+    }
+
+    /// Get the name of the hash algorithm used to compute the digest, e.g. "sha256".
+    pub fn hash_algorithm(&self) -> Result<&str, VbMetaImageParseError> {
+        CStr::from_bytes_until_nul(&self.descriptor.hash_algorithm)
+            .ok()
+            .and_then(|algorithm| algorithm.to_str().ok())
+            .ok_or(VbMetaImageParseError::InvalidDescriptor)
+    }
+
+    /// Get the salt that prefixes the image data when computing its digest.
+    pub fn salt(&self) -> &[u8] {
+        let begin = size_of::<AvbHashDescriptor>() + self.descriptor.partition_name_len as usize;
+        let end = begin + self.descriptor.salt_len as usize;
+        &self.data[begin..end]
+    }
+
+    /// Get the digest of the image covered by the descriptor.
+    pub fn digest(&self) -> &[u8] {
+        let begin = size_of::<AvbHashDescriptor>()
+            + self.descriptor.partition_name_len as usize
+            + self.descriptor.salt_len as usize;
+        let end = begin + self.descriptor.digest_len as usize;
+        &self.data[begin..end]
+    }
+
+    /// Verify that the first `image_size` bytes of `image` match the digest of the descriptor.
+    ///
+    /// The data is hashed with the algorithm of the descriptor as `salt || image`.
+    pub fn verify(&self, image: &[u8]) -> Result<(), VbMetaImageVerificationError> {
+        let image_size = self.image_size() as usize;
+        let image = image.get(..image_size).ok_or(VbMetaImageVerificationError::HashMismatch)?;
+
+        let mut hasher = Hasher::new(message_digest(self.hash_algorithm()?)?)?;
+        hasher.update(self.salt())?;
+        hasher.update(image)?;
+        let digest = hasher.finish()?;
+
+        let expected = self.digest();
+        if digest.len() == expected.len() && memcmp::eq(&digest, expected) {
+            Ok(())
+        } else {
+            Err(VbMetaImageVerificationError::HashMismatch)
+        }
+    }
+}
+
+impl<'a> HashtreeDescriptor<'a> {
+    /// Get the name of the partition covered by the hashtree.
+    pub fn partition_name(&self) -> &'a [u8] {
+        let begin = size_of::<AvbHashtreeDescriptor>();
+        let end = begin + self.descriptor.partition_name_len as usize;
+        &self.data[begin..end]
+    }
+
+    /// Get the size in bytes of the image covered by the hashtree.
+    pub fn image_size(&self) -> u64 {
+        self.descriptor.image_size
+    }
+
+    /// Get the name of the hash algorithm used to compute the hashtree, e.g. "sha256".
+    pub fn hash_algorithm(&self) -> Result<&str, VbMetaImageParseError> {
+        CStr::from_bytes_until_nul(&self.descriptor.hash_algorithm)
+            .ok()
+            .and_then(|algorithm| algorithm.to_str().ok())
+            .ok_or(VbMetaImageParseError::InvalidDescriptor)
+    }
+
+    /// Get the salt that prefixes each block when computing its digest.
+    pub fn salt(&self) -> &[u8] {
+        let begin =
+            size_of::<AvbHashtreeDescriptor>() + self.descriptor.partition_name_len as usize;
+        let end = begin + self.descriptor.salt_len as usize;
+        &self.data[begin..end]
+    }
+
     /// Get the root digest of the hashtree.
     pub fn root_digest(&self) -> &[u8] {
         let begin = size_of::<AvbHashtreeDescriptor>()
@@ -143,5 +328,78 @@ impl HashtreeDescriptor<'_> {
         &self.data[begin..end]
     }
 
+    /// Verify that the first `image_size` bytes of `image` match the root digest of the hashtree.
+    ///
+    /// The hashtree is rebuilt the way avbtool generates it, so this doesn't need the hashtree
+    /// stored in the image.
+    pub fn verify(&self, image: &[u8]) -> Result<(), VbMetaImageVerificationError> {
+        let image_size = self.image_size() as usize;
+        let image = image.get(..image_size).ok_or(VbMetaImageVerificationError::HashMismatch)?;
+
+        let digest = self.compute_root_digest(image)?;
+
+        let expected = self.root_digest();
+        if digest.len() == expected.len() && memcmp::eq(&digest, expected) {
+            Ok(())
+        } else {
+            Err(VbMetaImageVerificationError::HashMismatch)
+        }
+    }
+
+    fn compute_root_digest(
+        &self,
+        image: &[u8],
+    ) -> Result<DigestBytes, VbMetaImageVerificationError> {
+        let block_size = self.descriptor.data_block_size as usize;
+        if block_size == 0 || self.descriptor.hash_block_size != self.descriptor.data_block_size {
+            // avbtool always uses the same size for data and hash blocks.
+            return Err(VbMetaImageParseError::InvalidDescriptor.into());
+        }
+        let md = message_digest(self.hash_algorithm()?)?;
+        // Each digest is padded with zeros to the next power of two.
+        let digest_padding = vec![0; md.size().next_power_of_two() - md.size()];
+        let zeros = vec![0; block_size];
+
+        // The digest of a block is computed over `salt || block`, with partial blocks padded with
+        // zeros.
+        let hash_block = |block: &[u8]| -> Result<DigestBytes, VbMetaImageVerificationError> {
+            let mut hasher = Hasher::new(md)?;
+            hasher.update(self.salt())?;
+            hasher.update(block)?;
+            hasher.update(&zeros[block.len()..])?;
+            Ok(hasher.finish()?)
+        };
+        // Each level holds the digests of the blocks of the level below, padded with zeros to a
+        // whole number of blocks.
+        let hash_level = |level: &[u8]| -> Result<Vec<u8>, VbMetaImageVerificationError> {
+            let mut digests = Vec::new();
+            for block in level.chunks(block_size) {
+                digests.extend_from_slice(&hash_block(block)?);
+                digests.extend_from_slice(&digest_padding);
+            }
+            digests.resize(digests.len().next_multiple_of(block_size), 0);
+            Ok(digests)
+        };
+
+        if image.len() <= block_size {
+            return hash_block(image);
+        }
+        let mut level = hash_level(image)?;
+        while level.len() > block_size {
+            level = hash_level(&level)?;
+        }
+        hash_block(&level)
+    }
+
     // TODO: expose other fields as required
 }
+
+fn message_digest(algorithm: &str) -> Result<MessageDigest, VbMetaImageVerificationError> {
+    match algorithm {
+        "sha256" => Ok(MessageDigest::sha256()),
+        "sha512" => Ok(MessageDigest::sha512()),
+        algorithm => {
+            Err(VbMetaImageVerificationError::UnsupportedHashAlgorithm(algorithm.to_owned()))
+        }
+    }
+}
@@ -28,7 +28,7 @@ use std::path::Path;
 use std::ptr::null_mut;
 use thiserror::Error;
 
-pub use crate::descriptor::{Descriptor, Descriptors};
+pub use crate::descriptor::{Descriptor, Descriptors, HashDescriptor, PartitionVerification};
 
 /// Errors from parsing a VBMeta image.
 #[derive(Debug, Error)]
@@ -62,6 +62,12 @@ pub enum VbMetaImageVerificationError {
     /// The VBMeta image signature did not validate.
     #[error("Signature mismatch")]
     SignatureMismatch,
+    /// The hash algorithm of a descriptor is not supported.
+    #[error("Unsupported hash algorithm: {0}")]
+    UnsupportedHashAlgorithm(String),
+    /// The digest of an image could not be computed.
+    #[error("Cannot compute the image digest")]
+    Digest(#[from] openssl::error::ErrorStack),
 }
 
 /// A VBMeta Image.
@@ -289,6 +295,210 @@ mod tests {
         signed_image_has_valid_vbmeta("SHA256_RSA8192", "data/testkey_rsa8192.pem")
     }
 
+    fn add_hash_footer(image: &Path, partition_name: &str, salt: &str) -> Result<()> {
+        add_hash_footer_with_hash_algorithm(image, partition_name, salt, "sha256")
+    }
+
+    fn add_hash_footer_with_hash_algorithm(
+        image: &Path,
+        partition_name: &str,
+        salt: &str,
+        hash_algorithm: &str,
+    ) -> Result<()> {
+        let mut cmd = Command::new("./avbtool");
+        cmd.args([
+            "add_hash_footer",
+            "--image",
+            image.to_str().unwrap(),
+            "--partition_name",
+            partition_name,
+            "--partition_size",
+            "65536",
+            "--salt",
+            salt,
+            "--hash_algorithm",
+            hash_algorithm,
+            "--algorithm",
+            "SHA256_RSA2048",
+            "--key",
+            "data/testkey_rsa2048.pem",
+        ]);
+        let status = cmd.status().context("add_hash_footer")?;
+        assert!(status.success());
+        Ok(())
+    }
+
+    fn add_hashtree_footer(image: &Path, partition_name: &str, salt: &str) -> Result<()> {
+        let mut cmd = Command::new("./avbtool");
+        cmd.args([
+            "add_hashtree_footer",
+            "--image",
+            image.to_str().unwrap(),
+            "--partition_name",
+            partition_name,
+            "--partition_size",
+            "1048576",
+            "--salt",
+            salt,
+            "--hash_algorithm",
+            "sha256",
+            "--do_not_generate_fec",
+            "--algorithm",
+            "SHA256_RSA2048",
+            "--key",
+            "data/testkey_rsa2048.pem",
+        ]);
+        let status = cmd.status().context("add_hashtree_footer")?;
+        assert!(status.success());
+        Ok(())
+    }
+
+    /// Creates a signed VBMeta image at `output` with the descriptors of `images`.
+    fn make_vbmeta_image(output: &Path, images: &[impl AsRef<Path>]) -> Result<()> {
+        let mut cmd = Command::new("./avbtool");
+        cmd.args(["make_vbmeta_image", "--algorithm", "SHA256_RSA2048"]);
+        cmd.args(["--key", "data/testkey_rsa2048.pem"]);
+        for image in images {
+            cmd.arg("--include_descriptors_from_image").arg(image.as_ref());
+        }
+        cmd.arg("--output").arg(output);
+        let status = cmd.status().context("make_vbmeta_image")?;
+        assert!(status.success());
+        Ok(())
+    }
+
+    #[test]
+    fn hash_descriptor_verifies_image() -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let test_file = test_dir.path().join("test.img");
+        fs::write(&test_file, [0xa5; 4096]).context("write image")?;
+        add_hash_footer(&test_file, "test", "")?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let descriptor = descriptors.iter().find(|d| matches!(d, Descriptor::Hash(_))).unwrap();
+        let descriptor = descriptor.to_hash()?;
+
+        let mut image = fs::read(&test_file).context("read image")?;
+        descriptor.verify(&image)?;
+
+        image[0] = !image[0];
+        assert!(matches!(
+            descriptor.verify(&image),
+            Err(VbMetaImageVerificationError::HashMismatch)
+        ));
+        assert!(matches!(
+            descriptor.verify(&image[..4095]),
+            Err(VbMetaImageVerificationError::HashMismatch)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn hash_descriptor_rejects_unsupported_hash_algorithm() -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let test_file = test_dir.path().join("test.img");
+        fs::write(&test_file, [0xa5; 4096]).context("write image")?;
+        add_hash_footer_with_hash_algorithm(&test_file, "test", "", "sha1")?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let descriptor = descriptors.iter().find(|d| matches!(d, Descriptor::Hash(_))).unwrap();
+        let descriptor = descriptor.to_hash()?;
+
+        let image = fs::read(&test_file).context("read image")?;
+        assert!(matches!(
+            descriptor.verify(&image),
+            Err(VbMetaImageVerificationError::UnsupportedHashAlgorithm(a)) if a == "sha1"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn hash_descriptor_verification_uses_salt() -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let test_file = test_dir.path().join("test.img");
+        let data = [0x5a; 4096];
+        fs::write(&test_file, data).context("write image")?;
+        add_hash_footer(&test_file, "test", "deadbeef")?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let descriptor = descriptors.iter().find(|d| matches!(d, Descriptor::Hash(_))).unwrap();
+        let descriptor = descriptor.to_hash()?;
+
+        assert_eq!(descriptor.salt(), [0xde, 0xad, 0xbe, 0xef]);
+        // The digest doesn't match the image alone, only when prefixed with the salt.
+        assert_ne!(descriptor.digest(), openssl::sha::sha256(&data));
+        descriptor.verify(&fs::read(&test_file).context("read image")?)?;
+        Ok(())
+    }
+
+    #[test]
+    fn hash_descriptor_exposes_its_fields() -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let test_file = test_dir.path().join("test.img");
+        fs::write(&test_file, [0x3c; 12288]).context("write image")?;
+        add_hash_footer(&test_file, "fields", "0123")?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let descriptor = descriptors.iter().find(|d| matches!(d, Descriptor::Hash(_))).unwrap();
+        let descriptor = descriptor.to_hash()?;
+
+        assert_eq!(descriptor.partition_name(), b"fields");
+        assert_eq!(descriptor.image_size(), 12288);
+        assert_eq!(descriptor.hash_algorithm()?, "sha256");
+        assert_eq!(descriptor.salt(), [0x01, 0x23]);
+        assert_eq!(descriptor.digest().len(), 32);
+        Ok(())
+    }
+
+    #[test]
+    fn partition_names_lists_all_covered_partitions() -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let mut images = Vec::new();
+        for partition_name in ["foo", "bar"] {
+            let image = test_dir.path().join(format!("{partition_name}.img"));
+            fs::write(&image, [0; 4096]).context("write image")?;
+            add_hash_footer(&image, partition_name, "")?;
+            images.push(image);
+        }
+        let test_file = test_dir.path().join("vbmeta.img");
+        make_vbmeta_image(&test_file, &images)?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let names = descriptors.partition_names().collect::<Result<Vec<_>, _>>()?;
+        assert_eq!(names, [b"foo".as_slice(), b"bar".as_slice()]);
+        Ok(())
+    }
+
+    #[test]
+    fn hash_descriptors_are_in_descriptor_order() -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let partitions = [("zeta", 4096), ("alpha", 8192), ("mid", 12288)];
+        let mut images = Vec::new();
+        for (partition_name, size) in partitions {
+            let image = test_dir.path().join(format!("{partition_name}.img"));
+            fs::write(&image, vec![0; size]).context("write image")?;
+            add_hash_footer(&image, partition_name, "")?;
+            images.push(image);
+        }
+        let test_file = test_dir.path().join("vbmeta.img");
+        make_vbmeta_image(&test_file, &images)?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let hash_descriptors = descriptors.hash_descriptors().collect::<Result<Vec<_>, _>>()?;
+        let found: Vec<_> =
+            hash_descriptors.iter().map(|d| (d.partition_name(), d.image_size())).collect();
+        let expected: Vec<_> =
+            partitions.iter().map(|(name, size)| (name.as_bytes(), *size as u64)).collect();
+        assert_eq!(found, expected);
+        Ok(())
+    }
+
     #[test]
     fn test_rollback_index() -> Result<()> {
         let vbmeta = VbMetaImage::verify_path("test_microdroid_vendor_image.img")?;
@@ -303,4 +513,75 @@ mod tests {
         assert_eq!(0, vbmeta.rollback_index());
         Ok(())
     }
+
+    #[test]
+    fn verify_partitions_reports_each_partition() -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let vendor_image = test_dir.path().join("vendor.img");
+        fs::write(&vendor_image, [0x96; 8192]).context("write image")?;
+        add_hash_footer(&vendor_image, "vendor", "1234")?;
+        let test_file = test_dir.path().join("vbmeta.img");
+        make_vbmeta_image(&test_file, &[&vendor_image])?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let vendor = fs::read(&vendor_image).context("read image")?;
+        let mut corrupted_vendor = vendor.clone();
+        corrupted_vendor[4096] = !corrupted_vendor[4096];
+
+        let report = descriptors.verify_partitions(&[
+            (b"vendor".as_slice(), vendor.as_slice()),
+            (b"vendor".as_slice(), corrupted_vendor.as_slice()),
+            (b"system".as_slice(), vendor.as_slice()),
+        ])?;
+        assert_eq!(
+            report,
+            [
+                (b"vendor".as_slice(), PartitionVerification::Verified),
+                (b"vendor".as_slice(), PartitionVerification::HashMismatch),
+                (b"system".as_slice(), PartitionVerification::Missing),
+            ]
+        );
+        Ok(())
+    }
+
+    fn hashtree_partition_is_verified(image_size: usize) -> Result<()> {
+        let test_dir = TempDir::new().unwrap();
+        let system_image = test_dir.path().join("system.img");
+        let data: Vec<u8> = (0..image_size).map(|i| (i % 251) as u8).collect();
+        fs::write(&system_image, &data).context("write image")?;
+        add_hashtree_footer(&system_image, "system", "5678")?;
+        let test_file = test_dir.path().join("vbmeta.img");
+        make_vbmeta_image(&test_file, &[&system_image])?;
+
+        let vbmeta = VbMetaImage::verify_path(&test_file).context("verify_path")?;
+        let descriptors = vbmeta.descriptors()?;
+        let system = fs::read(&system_image).context("read image")?;
+        let mut corrupted_system = system.clone();
+        corrupted_system[image_size - 1] = !corrupted_system[image_size - 1];
+
+        let report = descriptors.verify_partitions(&[
+            (b"system".as_slice(), system.as_slice()),
+            (b"system".as_slice(), corrupted_system.as_slice()),
+        ])?;
+        assert_eq!(
+            report,
+            [
+                (b"system".as_slice(), PartitionVerification::Verified),
+                (b"system".as_slice(), PartitionVerification::HashMismatch),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn verify_partitions_checks_single_block_hashtree() -> Result<()> {
+        hashtree_partition_is_verified(4096)
+    }
+
+    #[test]
+    fn verify_partitions_checks_multi_level_hashtree() -> Result<()> {
+        // 129 blocks need two blocks of digests, which are in turn hashed into a second level.
+        hashtree_partition_is_verified(129 * 4096)
+    }
 }
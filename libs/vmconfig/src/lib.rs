@@ -24,12 +24,13 @@ use android_system_virtualizationservice::{
     binder::ParcelFileDescriptor,
 };
 
-use anyhow::{anyhow, bail, Context, Error, Result};
+use anyhow::{anyhow, bail, ensure, Context, Error, Result};
 use semver::VersionReq;
 use serde::{Deserialize, Serialize};
 use std::convert::TryInto;
+use std::fmt;
 use std::fs::{File, OpenOptions};
-use std::io::BufReader;
+use std::io::{BufReader, Read, Seek, SeekFrom};
 use std::num::NonZeroU32;
 use std::path::{Path, PathBuf};
 
@@ -97,41 +98,134 @@ impl VmConfig {
 
     /// Convert the `VmConfig` to a [`VirtualMachineConfig`] which can be passed to the Virt
     /// Manager.
+    ///
+    /// If several problems are found, they are all reported by a [`ConfigProblems`] error.
     pub fn to_parcelable(&self) -> Result<VirtualMachineRawConfig, Error> {
-        let memory_mib = if let Some(memory_mib) = self.memory_mib {
-            memory_mib.get().try_into().context("Invalid memory_mib")?
+        let mut problems = ProblemCollector::default();
+        if let (true, Some(kernel)) = (self.protected, &self.kernel) {
+            // pvmfw only boots a kernel that it can verify with AVB, so catch an unsigned one here
+            // rather than as a failed boot.
+            if let Err(e) = check_avb_footer(kernel) {
+                problems.push(e.context("The kernel of a protected VM must be AVB-signed"));
+            }
+        }
+        let memory_mib = problems.check(if let Some(memory_mib) = self.memory_mib {
+            memory_mib.get().try_into().context("Invalid memory_mib")
         } else {
-            0
-        };
-        let cpu_topology = match self.cpu_topology.as_deref() {
-            None => CpuTopology::ONE_CPU,
-            Some("one_cpu") => CpuTopology::ONE_CPU,
-            Some("match_host") => CpuTopology::MATCH_HOST,
-            Some(cpu_topology) => bail!("Invalid cpu topology {}", cpu_topology),
-        };
+            Ok(0)
+        });
+        let cpu_topology = problems.check(match self.cpu_topology.as_deref() {
+            None => Ok(CpuTopology::ONE_CPU),
+            Some("one_cpu") => Ok(CpuTopology::ONE_CPU),
+            Some("match_host") => Ok(CpuTopology::MATCH_HOST),
+            Some(cpu_topology) => Err(anyhow!("Invalid cpu topology {}", cpu_topology)),
+        });
+        let kernel =
+            problems.check(maybe_open_parcel_file(&self.kernel, false).context("Invalid kernel"));
+        let initrd =
+            problems.check(maybe_open_parcel_file(&self.initrd, false).context("Invalid initrd"));
+        let bootloader = problems
+            .check(maybe_open_parcel_file(&self.bootloader, false).context("Invalid bootloader"));
+        let disks = self
+            .disks
+            .iter()
+            .enumerate()
+            .filter_map(|(i, disk)| {
+                problems.check(disk.to_parcelable().with_context(|| format!("Invalid disk {i}")))
+            })
+            .collect();
+        let devices = self
+            .devices
+            .iter()
+            .filter_map(|x| {
+                problems.check(
+                    x.to_str()
+                        .map(String::from)
+                        .ok_or(anyhow!("Failed to convert {x:?} to String")),
+                )
+            })
+            .collect();
+        problems.finish()?;
+
+        // Every value was successfully converted if no problem was found.
         Ok(VirtualMachineRawConfig {
-            kernel: maybe_open_parcel_file(&self.kernel, false)?,
-            initrd: maybe_open_parcel_file(&self.initrd, false)?,
+            kernel: kernel.flatten(),
+            initrd: initrd.flatten(),
             params: self.params.clone(),
-            bootloader: maybe_open_parcel_file(&self.bootloader, false)?,
-            disks: self.disks.iter().map(DiskImage::to_parcelable).collect::<Result<_, Error>>()?,
+            bootloader: bootloader.flatten(),
+            disks,
             protectedVm: self.protected,
-            memoryMib: memory_mib,
-            cpuTopology: cpu_topology,
+            memoryMib: memory_mib.unwrap_or_default(),
+            cpuTopology: cpu_topology.unwrap_or(CpuTopology::ONE_CPU),
             platformVersion: self.platform_version.to_string(),
-            devices: self
-                .devices
-                .iter()
-                .map(|x| {
-                    x.to_str().map(String::from).ok_or(anyhow!("Failed to convert {x:?} to String"))
-                })
-                .collect::<Result<_>>()?,
+            devices,
             consoleInputDevice: self.console_input_device.clone(),
             ..Default::default()
         })
     }
 }
 
+/// All the problems found by [`VmConfig::to_parcelable`] in a config, when there are several.
+#[derive(Debug)]
+pub struct ConfigProblems(pub Vec<Error>);
+
+impl fmt::Display for ConfigProblems {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Found {} problems in the VM config", self.0.len())?;
+        for problem in &self.0 {
+            write!(f, "; {problem:#}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigProblems {}
+
+/// Collects problems instead of stopping at the first one.
+#[derive(Default)]
+struct ProblemCollector(Vec<Error>);
+
+impl ProblemCollector {
+    fn push(&mut self, problem: Error) {
+        self.0.push(problem);
+    }
+
+    /// Returns the value of `result`, or records its error and returns `None`.
+    fn check<T>(&mut self, result: Result<T>) -> Option<T> {
+        result.map_err(|e| self.push(e)).ok()
+    }
+
+    /// Returns the single problem found, a [`ConfigProblems`] error if there are several, or
+    /// `Ok(())` if there is none.
+    fn finish(self) -> Result<()> {
+        let mut problems = self.0;
+        match problems.len() {
+            0 => Ok(()),
+            1 => Err(problems.remove(0)),
+            _ => Err(ConfigProblems(problems).into()),
+        }
+    }
+}
+
+/// Checks that the image at `path` ends with an AVB footer, as added by `avbtool add_hash_footer`.
+fn check_avb_footer(path: &Path) -> Result<()> {
+    const AVB_FOOTER_SIZE: u64 = 64;
+    const AVB_FOOTER_MAGIC: &[u8; 4] = b"AVBf";
+
+    let mut file = File::open(path).with_context(|| format!("Failed to open {path:?}"))?;
+    let size =
+        file.metadata().with_context(|| format!("Failed to get the size of {path:?}"))?.len();
+    let Some(footer_offset) = size.checked_sub(AVB_FOOTER_SIZE) else {
+        bail!("{path:?} is too small to have an AVB footer");
+    };
+    let mut magic = [0; 4];
+    file.seek(SeekFrom::Start(footer_offset))
+        .and_then(|_| file.read_exact(&mut magic))
+        .with_context(|| format!("Failed to read the AVB footer of {path:?}"))?;
+    ensure!(&magic == AVB_FOOTER_MAGIC, "{path:?} has no AVB footer");
+    Ok(())
+}
+
 /// Returns the debug level of the VM from its configuration.
 pub fn get_debug_level(config: &VirtualMachineConfig) -> Option<DebugLevel> {
     match config {
@@ -206,3 +300,82 @@ fn maybe_open_parcel_file(
 ) -> Result<Option<ParcelFileDescriptor>> {
     filename.as_deref().map(|filename| open_parcel_file(filename, writable)).transpose()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{self, Write};
+    use tempfile::NamedTempFile;
+
+    fn kernel_file(footer_magic: &[u8]) -> NamedTempFile {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(&[0; 4096]).unwrap();
+        file.write_all(footer_magic).unwrap();
+        file.write_all(&[0; 60]).unwrap();
+        file
+    }
+
+    fn config_with_kernel(kernel: &Path, protected: bool) -> VmConfig {
+        let json = serde_json::json!({
+            "kernel": kernel,
+            "protected": protected,
+            "platform_version": "~1.0",
+        });
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn protected_config_with_unsigned_kernel_is_rejected() {
+        let kernel = kernel_file(b"\0\0\0\0");
+        let vm_config = config_with_kernel(kernel.path(), true);
+
+        let err = vm_config.to_parcelable().unwrap_err();
+        assert!(err.to_string().contains("must be AVB-signed"), "{err:#}");
+        assert!(format!("{err:#}").contains("has no AVB footer"), "{err:#}");
+    }
+
+    #[test]
+    fn protected_config_with_signed_kernel_is_accepted() {
+        let kernel = kernel_file(b"AVBf");
+        let vm_config = config_with_kernel(kernel.path(), true);
+
+        let config = vm_config.to_parcelable().unwrap();
+        assert!(config.protectedVm);
+        assert!(config.kernel.is_some());
+    }
+
+    #[test]
+    fn unprotected_config_with_unsigned_kernel_is_accepted() {
+        let kernel = kernel_file(b"\0\0\0\0");
+        let vm_config = config_with_kernel(kernel.path(), false);
+
+        assert!(vm_config.to_parcelable().is_ok());
+    }
+
+    #[test]
+    fn protected_config_with_bootloader_is_accepted() {
+        let vm_config: VmConfig = serde_json::from_str(
+            r#"{ "bootloader": "/dev/null", "protected": true, "platform_version": "~1.0" }"#,
+        )
+        .unwrap();
+
+        let config = vm_config.to_parcelable().unwrap();
+        assert!(config.protectedVm);
+        assert!(config.bootloader.is_some());
+        assert!(config.kernel.is_none());
+    }
+
+    #[test]
+    fn to_parcelable_reports_missing_kernel_path() {
+        let vm_config: VmConfig = serde_json::from_str(
+            r#"{ "kernel": "/nonexistent/kernel", "platform_version": "~1.0" }"#,
+        )
+        .unwrap();
+
+        let err = vm_config.to_parcelable().unwrap_err();
+        let message = format!("{err:#}");
+        assert!(message.starts_with(r#"Invalid kernel: Failed to open "/nonexistent/kernel""#));
+        let cause = err.root_cause().downcast_ref::<io::Error>().unwrap();
+        assert_eq!(cause.kind(), io::ErrorKind::NotFound);
+    }
+}
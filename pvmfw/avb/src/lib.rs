@@ -21,7 +21,15 @@ extern crate alloc;
 mod error;
 mod ops;
 mod partition;
+mod public_key;
+mod rollback;
 mod verify;
 
 pub use error::PvmfwVerifyError;
-pub use verify::{verify_payload, Capability, DebugLevel, Digest, VerifiedBootData};
+pub use partition::ImagePartition;
+pub use public_key::vbmeta_public_key;
+pub use rollback::{InMemoryRollbackStore, RollbackStore};
+pub use verify::{
+    verify_images, verify_payload, verify_payload_with_rollback_store, Capability, DebugLevel,
+    Digest, VerifiedBootData, VerifiedImages,
+};
@@ -14,7 +14,8 @@
 
 //! Structs and functions relating to AVB callback operations.
 
-use crate::partition::PartitionName;
+use crate::partition::{ImagePartition, PartitionName};
+use crate::rollback::RollbackStore;
 use avb::{
     slot_verify, HashtreeErrorMode, IoError, IoResult, PublicKeyForPartitionInfo, SlotVerifyData,
     SlotVerifyFlags, SlotVerifyResult,
@@ -24,6 +25,7 @@ use core::ffi::CStr;
 pub(crate) struct Payload<'a> {
     kernel: &'a [u8],
     initrd: Option<&'a [u8]>,
+    images: &'a [(ImagePartition, &'a [u8])],
     trusted_public_key: &'a [u8],
 }
 
@@ -31,9 +33,10 @@ impl<'a> Payload<'a> {
     pub(crate) fn new(
         kernel: &'a [u8],
         initrd: Option<&'a [u8]>,
+        images: &'a [(ImagePartition, &'a [u8])],
         trusted_public_key: &'a [u8],
     ) -> Self {
-        Self { kernel, initrd, trusted_public_key }
+        Self { kernel, initrd, images, trusted_public_key }
     }
 
     fn get_partition(&self, partition_name: &CStr) -> IoResult<&[u8]> {
@@ -42,6 +45,12 @@ impl<'a> Payload<'a> {
             PartitionName::InitrdNormal | PartitionName::InitrdDebug => {
                 self.initrd.ok_or(IoError::NoSuchPartition)
             }
+            name @ (PartitionName::Dtbo | PartitionName::VendorBoot) => self
+                .images
+                .iter()
+                .find(|(partition, _)| PartitionName::from(*partition) == name)
+                .map(|(_, image)| *image)
+                .ok_or(IoError::NoSuchPartition),
         }
     }
 }
@@ -49,11 +58,12 @@ impl<'a> Payload<'a> {
 /// Pvmfw customized operations used in the verification.
 pub(crate) struct Ops<'a> {
     payload: &'a Payload<'a>,
+    rollback_store: &'a mut dyn RollbackStore,
 }
 
 impl<'a> Ops<'a> {
-    pub(crate) fn new(payload: &'a Payload<'a>) -> Self {
-        Self { payload }
+    pub(crate) fn new(payload: &'a Payload<'a>, rollback_store: &'a mut dyn RollbackStore) -> Self {
+        Self { payload, rollback_store }
     }
 
     pub(crate) fn verify_partition(
@@ -95,20 +105,12 @@ impl<'a> avb::Ops<'a> for Ops<'a> {
         Ok(self.payload.trusted_public_key == public_key)
     }
 
-    fn read_rollback_index(&mut self, _rollback_index_location: usize) -> IoResult<u64> {
-        // TODO(291213394) : Refine this comment once capability for rollback protection is defined.
-        // pvmfw does not compare stored_rollback_index with rollback_index for Antirollback
-        // protection. Hence, we set `out_rollback_index` to 0 to ensure that the rollback_index
-        // (including default: 0) is never smaller than it, thus the rollback index check will pass.
-        Ok(0)
+    fn read_rollback_index(&mut self, rollback_index_location: usize) -> IoResult<u64> {
+        self.rollback_store.read(rollback_index_location)
     }
 
-    fn write_rollback_index(
-        &mut self,
-        _rollback_index_location: usize,
-        _index: u64,
-    ) -> IoResult<()> {
-        Err(IoError::NotImplemented)
+    fn write_rollback_index(&mut self, rollback_index_location: usize, index: u64) -> IoResult<()> {
+        self.rollback_store.write(rollback_index_location, index)
     }
 
     fn read_is_device_unlocked(&mut self) -> IoResult<bool> {
@@ -24,12 +24,16 @@ pub(crate) enum PartitionName {
     Kernel,
     InitrdNormal,
     InitrdDebug,
+    Dtbo,
+    VendorBoot,
 }
 
 impl PartitionName {
     const KERNEL_PARTITION_NAME: &'static [u8] = b"boot\0";
     const INITRD_NORMAL_PARTITION_NAME: &'static [u8] = b"initrd_normal\0";
     const INITRD_DEBUG_PARTITION_NAME: &'static [u8] = b"initrd_debug\0";
+    const DTBO_PARTITION_NAME: &'static [u8] = b"dtbo\0";
+    const VENDOR_BOOT_PARTITION_NAME: &'static [u8] = b"vendor_boot\0";
 
     pub(crate) fn as_cstr(&self) -> &CStr {
         CStr::from_bytes_with_nul(self.as_bytes()).unwrap()
@@ -45,6 +49,8 @@ impl PartitionName {
             Self::Kernel => Self::KERNEL_PARTITION_NAME,
             Self::InitrdNormal => Self::INITRD_NORMAL_PARTITION_NAME,
             Self::InitrdDebug => Self::INITRD_DEBUG_PARTITION_NAME,
+            Self::Dtbo => Self::DTBO_PARTITION_NAME,
+            Self::VendorBoot => Self::VENDOR_BOOT_PARTITION_NAME,
         }
     }
 }
@@ -53,24 +59,102 @@ impl TryFrom<&CStr> for PartitionName {
     type Error = IoError;
 
     fn try_from(partition_name: &CStr) -> Result<Self, Self::Error> {
-        match partition_name.to_bytes_with_nul() {
-            Self::KERNEL_PARTITION_NAME => Ok(Self::Kernel),
-            Self::INITRD_NORMAL_PARTITION_NAME => Ok(Self::InitrdNormal),
-            Self::INITRD_DEBUG_PARTITION_NAME => Ok(Self::InitrdDebug),
-            _ => Err(IoError::NoSuchPartition),
-        }
+        partition_name.to_bytes().try_into()
     }
 }
 
 impl TryFrom<&[u8]> for PartitionName {
     type Error = IoError;
 
+    /// Parses a partition name, without NUL terminator.
+    ///
+    /// Leading and trailing ASCII whitespace is ignored and the remaining bytes must match one
+    /// of the known partition names up to ASCII case, so that e.g. `"boot"`, `"Boot"` and
+    /// `"boot "` all parse as `Kernel`. Any other name is rejected with `NoSuchPartition`.
     fn try_from(non_null_terminated_name: &[u8]) -> Result<Self, Self::Error> {
-        match non_null_terminated_name {
-            x if x == Self::Kernel.as_non_null_terminated_bytes() => Ok(Self::Kernel),
-            x if x == Self::InitrdNormal.as_non_null_terminated_bytes() => Ok(Self::InitrdNormal),
-            x if x == Self::InitrdDebug.as_non_null_terminated_bytes() => Ok(Self::InitrdDebug),
-            _ => Err(IoError::NoSuchPartition),
+        let name = trim_ascii_whitespace(non_null_terminated_name);
+        [Self::Kernel, Self::InitrdNormal, Self::InitrdDebug, Self::Dtbo, Self::VendorBoot]
+            .into_iter()
+            .find(|p| name.eq_ignore_ascii_case(p.as_non_null_terminated_bytes()))
+            .ok_or(IoError::NoSuchPartition)
+    }
+}
+
+/// Partition of an image verified along with the kernel, against the hash descriptor of the same
+/// partition in the vbmeta of the kernel.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ImagePartition {
+    /// Device tree overlays.
+    Dtbo,
+    /// Vendor ramdisk.
+    VendorBoot,
+}
+
+impl From<ImagePartition> for PartitionName {
+    fn from(partition: ImagePartition) -> Self {
+        match partition {
+            ImagePartition::Dtbo => Self::Dtbo,
+            ImagePartition::VendorBoot => Self::VendorBoot,
+        }
+    }
+}
+
+fn trim_ascii_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    let end = bytes.iter().rposition(|b| !b.is_ascii_whitespace()).map_or(start, |i| i + 1);
+    &bytes[start..end]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cstr(bytes: &[u8]) -> &CStr {
+        CStr::from_bytes_with_nul(bytes).unwrap()
+    }
+
+    #[test]
+    fn canonical_name_is_parsed() {
+        assert_eq!(PartitionName::try_from(&b"boot"[..]), Ok(PartitionName::Kernel));
+        assert_eq!(PartitionName::try_from(&b"initrd_normal"[..]), Ok(PartitionName::InitrdNormal));
+        assert_eq!(
+            PartitionName::try_from(cstr(b"initrd_debug\0")),
+            Ok(PartitionName::InitrdDebug)
+        );
+        assert_eq!(PartitionName::try_from(&b"dtbo"[..]), Ok(PartitionName::Dtbo));
+        assert_eq!(PartitionName::try_from(cstr(b"vendor_boot\0")), Ok(PartitionName::VendorBoot));
+    }
+
+    #[test]
+    fn image_partition_names_round_trip() {
+        for partition in [ImagePartition::Dtbo, ImagePartition::VendorBoot] {
+            let name = PartitionName::from(partition);
+            assert_eq!(PartitionName::try_from(name.as_cstr()), Ok(name));
+        }
+    }
+
+    #[test]
+    fn name_with_surrounding_whitespace_is_parsed() {
+        assert_eq!(PartitionName::try_from(&b"boot \n"[..]), Ok(PartitionName::Kernel));
+        assert_eq!(
+            PartitionName::try_from(cstr(b" initrd_debug\0")),
+            Ok(PartitionName::InitrdDebug)
+        );
+    }
+
+    #[test]
+    fn name_in_other_case_is_parsed() {
+        assert_eq!(PartitionName::try_from(&b"BOOT"[..]), Ok(PartitionName::Kernel));
+        assert_eq!(
+            PartitionName::try_from(cstr(b"Initrd_Normal\0")),
+            Ok(PartitionName::InitrdNormal)
+        );
+    }
+
+    #[test]
+    fn unknown_name_is_rejected() {
+        for name in [&b"system"[..], b"", b"boot_a", b"boo t", b"initrd", b"vendor"] {
+            assert_eq!(PartitionName::try_from(name), Err(IoError::NoSuchPartition));
         }
     }
 }
@@ -0,0 +1,70 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! This module extracts the public key embedded in the vbmeta of an AVB-footed image.
+
+use crate::error::PvmfwVerifyError;
+use avb::SlotVerifyError;
+use avb_bindgen::{
+    avb_footer_validate_and_byteswap, avb_vbmeta_image_verify, AvbFooter, AvbVBMetaVerifyResult,
+};
+use core::mem::{size_of, transmute};
+use core::ptr;
+
+/// Returns the public key stored in the vbmeta of `image`, located through its AVB footer.
+///
+/// libavb checks that the vbmeta is signed with that key, but not that the key is trusted, so that
+/// callers can compare it against a pinned key (e.g. the one embedded in pvmfw).
+pub fn vbmeta_public_key(image: &[u8]) -> Result<&[u8], PvmfwVerifyError> {
+    find_public_key(image).ok_or(SlotVerifyError::InvalidMetadata.into())
+}
+
+/// Returns the `(offset, size)` of the vbmeta of `image`, as given by its AVB footer.
+pub(crate) fn vbmeta_offset_and_size(image: &[u8]) -> Option<(usize, usize)> {
+    let footer = read_avb_footer(image)?;
+    Some((footer.vbmeta_offset.try_into().ok()?, footer.vbmeta_size.try_into().ok()?))
+}
+
+/// Reads the AVB footer at the end of `image`, validated and in host byte order.
+fn read_avb_footer(image: &[u8]) -> Option<AvbFooter> {
+    let raw_footer = image.get(image.len().checked_sub(size_of::<AvbFooter>())?..)?;
+    let raw_footer: [u8; size_of::<AvbFooter>()] = raw_footer.try_into().ok()?;
+    // SAFETY: the slice is the same size as the struct which only contains simple data types.
+    let mut footer = unsafe { transmute::<[u8; size_of::<AvbFooter>()], AvbFooter>(raw_footer) };
+    // SAFETY: the function updates the struct in-place.
+    if unsafe { avb_footer_validate_and_byteswap(&footer, &mut footer) } {
+        Some(footer)
+    } else {
+        None
+    }
+}
+
+fn find_public_key(image: &[u8]) -> Option<&[u8]> {
+    let (vbmeta_offset, vbmeta_size) = vbmeta_offset_and_size(image)?;
+    let vbmeta = image.get(vbmeta_offset..vbmeta_offset.checked_add(vbmeta_size)?)?;
+
+    let mut key = ptr::null();
+    let mut key_size = 0;
+    // SAFETY: libavb only reads `vbmeta` within its bounds, and only writes the location of the
+    // public key within it to `key` and `key_size`.
+    let result =
+        unsafe { avb_vbmeta_image_verify(vbmeta.as_ptr(), vbmeta.len(), &mut key, &mut key_size) };
+    if !matches!(result, AvbVBMetaVerifyResult::AVB_VBMETA_VERIFY_RESULT_OK) {
+        return None;
+    }
+
+    // Locate the key from its address rather than trusting the pointer to be within `vbmeta`.
+    let key_offset = (key as usize).checked_sub(vbmeta.as_ptr() as usize)?;
+    vbmeta.get(key_offset..key_offset.checked_add(key_size)?)
+}
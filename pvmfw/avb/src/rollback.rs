@@ -0,0 +1,48 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Storage of the minimum rollback indexes that the payload verification enforces.
+
+use alloc::collections::BTreeMap;
+use avb::IoResult;
+
+/// Storage of the minimum rollback index accepted at each rollback index location.
+///
+/// During verification, libavb rejects a vbmeta whose rollback index is lower than the one read
+/// from the location given in its header.
+pub trait RollbackStore {
+    /// Returns the minimum rollback index accepted at `location`.
+    fn read(&self, location: usize) -> IoResult<u64>;
+
+    /// Sets the minimum rollback index accepted at `location` to `index`.
+    fn write(&mut self, location: usize, index: u64) -> IoResult<()>;
+}
+
+/// `RollbackStore` kept in memory, which accepts any rollback index at the locations that
+/// haven't been written to.
+#[derive(Debug, Default)]
+pub struct InMemoryRollbackStore {
+    indexes: BTreeMap<usize, u64>,
+}
+
+impl RollbackStore for InMemoryRollbackStore {
+    fn read(&self, location: usize) -> IoResult<u64> {
+        Ok(self.indexes.get(&location).copied().unwrap_or(0))
+    }
+
+    fn write(&mut self, location: usize, index: u64) -> IoResult<()> {
+        self.indexes.insert(location, index);
+        Ok(())
+    }
+}
@@ -15,7 +15,9 @@
 //! This module handles the pvmfw payload verification.
 
 use crate::ops::{Ops, Payload};
-use crate::partition::PartitionName;
+use crate::partition::{ImagePartition, PartitionName};
+use crate::public_key::vbmeta_offset_and_size;
+use crate::rollback::{InMemoryRollbackStore, RollbackStore};
 use crate::PvmfwVerifyError;
 use alloc::vec;
 use alloc::vec::Vec;
@@ -45,6 +47,19 @@ pub struct VerifiedBootData<'a> {
     pub capabilities: Vec<Capability>,
     /// Rollback index of kernel.
     pub rollback_index: u64,
+    /// Offset of the verified vbmeta within the kernel partition.
+    pub vbmeta_offset: usize,
+    /// Size of the verified vbmeta.
+    pub vbmeta_size: usize,
+}
+
+/// Verified data returned when the verification of a kernel along with other images succeeds.
+#[derive(Debug, PartialEq, Eq)]
+pub struct VerifiedImages<'a> {
+    /// Verified data of the kernel, whose vbmeta describes every image.
+    pub kernel: VerifiedBootData<'a>,
+    /// Digest of each image, or why it failed verification, in the order they were given.
+    pub image_digests: Vec<Result<Digest, PvmfwVerifyError>>,
 }
 
 impl VerifiedBootData<'_> {
@@ -166,11 +181,14 @@ fn verify_property_and_get_capabilities(
 
 /// Hash descriptors extracted from a vbmeta image.
 ///
-/// We always have a kernel hash descriptor and may have initrd normal or debug descriptors.
+/// We always have a kernel hash descriptor and may have initrd normal or debug descriptors, as well
+/// as descriptors of the other images.
 struct HashDescriptors<'a> {
     kernel: &'a HashDescriptor<'a>,
     initrd_normal: Option<&'a HashDescriptor<'a>>,
     initrd_debug: Option<&'a HashDescriptor<'a>>,
+    dtbo: Option<&'a HashDescriptor<'a>>,
+    vendor_boot: Option<&'a HashDescriptor<'a>>,
 }
 
 impl<'a> HashDescriptors<'a> {
@@ -180,6 +198,8 @@ impl<'a> HashDescriptors<'a> {
         let mut kernel = None;
         let mut initrd_normal = None;
         let mut initrd_debug = None;
+        let mut dtbo = None;
+        let mut vendor_boot = None;
 
         for descriptor in descriptors.iter().filter_map(|d| match d {
             Descriptor::Hash(h) => Some(h),
@@ -194,6 +214,8 @@ impl<'a> HashDescriptors<'a> {
                 PartitionName::Kernel => &mut kernel,
                 PartitionName::InitrdNormal => &mut initrd_normal,
                 PartitionName::InitrdDebug => &mut initrd_debug,
+                PartitionName::Dtbo => &mut dtbo,
+                PartitionName::VendorBoot => &mut vendor_boot,
             };
 
             if target.is_some() {
@@ -208,9 +230,33 @@ impl<'a> HashDescriptors<'a> {
             kernel: kernel.ok_or(DescriptorError::InvalidContents)?,
             initrd_normal,
             initrd_debug,
+            dtbo,
+            vendor_boot,
         })
     }
 
+    /// Returns the descriptor of the given image, if any.
+    fn image(&self, partition: ImagePartition) -> Option<&'a HashDescriptor<'a>> {
+        match partition {
+            ImagePartition::Dtbo => self.dtbo,
+            ImagePartition::VendorBoot => self.vendor_boot,
+        }
+    }
+
+    /// Returns an error if a described image is missing from `images`, as it would be neither
+    /// verified nor measured.
+    fn verify_images_are_provided(
+        &self,
+        images: &[(ImagePartition, &[u8])],
+    ) -> Result<(), PvmfwVerifyError> {
+        for partition in [ImagePartition::Dtbo, ImagePartition::VendorBoot] {
+            if self.image(partition).is_some() && !images.iter().any(|(p, _)| *p == partition) {
+                return Err(SlotVerifyError::InvalidMetadata.into());
+            }
+        }
+        Ok(())
+    }
+
     /// Returns an error if either initrd descriptor exists.
     fn verify_no_initrd(&self) -> Result<(), PvmfwVerifyError> {
         match self.initrd_normal.or(self.initrd_debug) {
@@ -230,29 +276,92 @@ fn copy_digest(descriptor: &HashDescriptor) -> SlotVerifyNoDataResult<Digest> {
     Ok(digest)
 }
 
-/// Verifies the given initrd partition, and checks that the resulting contents looks like expected.
-fn verify_initrd(
+/// Verifies the given partition, and checks that the resulting contents looks like expected.
+fn verify_partition_data(
     ops: &mut Ops,
     partition_name: PartitionName,
-    expected_initrd: &[u8],
+    expected_data: &[u8],
 ) -> SlotVerifyNoDataResult<()> {
     let result =
         ops.verify_partition(partition_name.as_cstr()).map_err(|e| e.without_verify_data())?;
     verify_loaded_partition_has_expected_length(
         result.partition_data(),
         partition_name,
-        expected_initrd.len(),
+        expected_data.len(),
     )
 }
 
+/// Verifies the given image against the hash descriptor of its partition, and returns its digest.
+fn verify_image(
+    ops: &mut Ops,
+    hash_descriptors: &HashDescriptors,
+    partition: ImagePartition,
+    image: &[u8],
+) -> Result<Digest, PvmfwVerifyError> {
+    let descriptor = hash_descriptors.image(partition).ok_or(SlotVerifyError::InvalidMetadata)?;
+    verify_partition_data(ops, partition.into(), image)?;
+    Ok(copy_digest(descriptor)?)
+}
+
 /// Verifies the payload (signed kernel + initrd) against the trusted public key.
 pub fn verify_payload<'a>(
     kernel: &[u8],
     initrd: Option<&[u8]>,
     trusted_public_key: &'a [u8],
 ) -> Result<VerifiedBootData<'a>, PvmfwVerifyError> {
-    let payload = Payload::new(kernel, initrd, trusted_public_key);
-    let mut ops = Ops::new(&payload);
+    // TODO(291213394) : Refine this comment once capability for rollback protection is defined.
+    // pvmfw does not compare stored_rollback_index with rollback_index for Antirollback
+    // protection. Hence, an empty store reads 0 at every location to ensure that the
+    // rollback_index (including default: 0) is never smaller than it, thus the check will pass.
+    let mut rollback_store = InMemoryRollbackStore::default();
+    verify_payload_with_rollback_store(kernel, initrd, trusted_public_key, &mut rollback_store)
+}
+
+/// Verifies the signed kernel against the trusted public key, then each of `images` against the
+/// hash descriptor of its partition in the vbmeta of the kernel.
+///
+/// Fails if the kernel doesn't pass verification, if its vbmeta describes an image missing from
+/// `images`, or if `images` holds several images of the same partition. Otherwise, returns the
+/// result of each image, in the order of `images`, so that a failure can be traced back to the
+/// image that caused it and the digests of the verified ones can all be measured.
+pub fn verify_images<'a>(
+    kernel: &[u8],
+    images: &[(ImagePartition, &[u8])],
+    trusted_public_key: &'a [u8],
+) -> Result<VerifiedImages<'a>, PvmfwVerifyError> {
+    if images.iter().enumerate().any(|(i, (p, _))| images[..i].iter().any(|(q, _)| q == p)) {
+        return Err(SlotVerifyError::InvalidMetadata.into());
+    }
+    // As in verify_payload(), rollback indexes are not enforced.
+    let mut rollback_store = InMemoryRollbackStore::default();
+    verify_kernel_and_images(kernel, None, images, trusted_public_key, &mut rollback_store)
+}
+
+/// Verifies the payload (signed kernel + initrd) against the trusted public key, rejecting it if
+/// its rollback index is lower than the one stored in `rollback_store` at the rollback index
+/// location of its vbmeta.
+pub fn verify_payload_with_rollback_store<'a>(
+    kernel: &[u8],
+    initrd: Option<&[u8]>,
+    trusted_public_key: &'a [u8],
+    rollback_store: &mut dyn RollbackStore,
+) -> Result<VerifiedBootData<'a>, PvmfwVerifyError> {
+    let verified_images =
+        verify_kernel_and_images(kernel, initrd, &[], trusted_public_key, rollback_store)?;
+    Ok(verified_images.kernel)
+}
+
+/// Verifies the kernel, initrd and other images, and returns the verified data of the kernel along
+/// with the result of each image.
+fn verify_kernel_and_images<'a>(
+    kernel: &[u8],
+    initrd: Option<&[u8]>,
+    images: &[(ImagePartition, &[u8])],
+    trusted_public_key: &'a [u8],
+    rollback_store: &mut dyn RollbackStore,
+) -> Result<VerifiedImages<'a>, PvmfwVerifyError> {
+    let payload = Payload::new(kernel, initrd, images, trusted_public_key);
+    let mut ops = Ops::new(&payload, rollback_store);
     let kernel_verify_result = ops.verify_partition(PartitionName::Kernel.as_cstr())?;
 
     let vbmeta_images = kernel_verify_result.vbmeta_data();
@@ -266,35 +375,56 @@ pub fn verify_payload<'a>(
     let descriptors = vbmeta_image.descriptors()?;
     let hash_descriptors = HashDescriptors::get(&descriptors)?;
     let capabilities = verify_property_and_get_capabilities(&descriptors)?;
+    // libavb located the vbmeta through the footer of the kernel partition, but only the bytes it
+    // returned were verified, so the size comes from them rather than from the unsigned footer.
+    let (vbmeta_offset, _) =
+        vbmeta_offset_and_size(kernel).ok_or(SlotVerifyError::InvalidMetadata)?;
+    let vbmeta_size = vbmeta_image.data().len();
+    let vbmeta_end =
+        vbmeta_offset.checked_add(vbmeta_size).ok_or(SlotVerifyError::InvalidMetadata)?;
+    if kernel.get(vbmeta_offset..vbmeta_end) != Some(vbmeta_image.data()) {
+        return Err(SlotVerifyError::InvalidMetadata.into());
+    }
+    hash_descriptors.verify_images_are_provided(images)?;
+    let image_digests = images
+        .iter()
+        .map(|(partition, image)| verify_image(&mut ops, &hash_descriptors, *partition, image))
+        .collect();
 
     if initrd.is_none() {
         hash_descriptors.verify_no_initrd()?;
-        return Ok(VerifiedBootData {
+        let verified_kernel = VerifiedBootData {
             debug_level: DebugLevel::None,
             kernel_digest: copy_digest(hash_descriptors.kernel)?,
             initrd_digest: None,
             public_key: trusted_public_key,
             capabilities,
             rollback_index,
-        });
+            vbmeta_offset,
+            vbmeta_size,
+        };
+        return Ok(VerifiedImages { kernel: verified_kernel, image_digests });
     }
 
     let initrd = initrd.unwrap();
     let (debug_level, initrd_descriptor) =
-        if verify_initrd(&mut ops, PartitionName::InitrdNormal, initrd).is_ok() {
+        if verify_partition_data(&mut ops, PartitionName::InitrdNormal, initrd).is_ok() {
             (DebugLevel::None, hash_descriptors.initrd_normal)
-        } else if verify_initrd(&mut ops, PartitionName::InitrdDebug, initrd).is_ok() {
+        } else if verify_partition_data(&mut ops, PartitionName::InitrdDebug, initrd).is_ok() {
             (DebugLevel::Full, hash_descriptors.initrd_debug)
         } else {
             return Err(SlotVerifyError::Verification(None).into());
         };
     let initrd_descriptor = initrd_descriptor.ok_or(DescriptorError::InvalidContents)?;
-    Ok(VerifiedBootData {
+    let verified_kernel = VerifiedBootData {
         debug_level,
         kernel_digest: copy_digest(hash_descriptors.kernel)?,
         initrd_digest: Some(copy_digest(initrd_descriptor)?),
         public_key: trusted_public_key,
         capabilities,
         rollback_index,
-    })
+        vbmeta_offset,
+        vbmeta_size,
+    };
+    Ok(VerifiedImages { kernel: verified_kernel, image_digests })
 }
@@ -19,7 +19,11 @@ mod utils;
 use anyhow::{anyhow, Result};
 use avb::{DescriptorError, SlotVerifyError};
 use avb_bindgen::{AvbFooter, AvbVBMetaImageHeader};
-use pvmfw_avb::{verify_payload, Capability, DebugLevel, PvmfwVerifyError, VerifiedBootData};
+use pvmfw_avb::{
+    vbmeta_public_key, verify_images, verify_payload, verify_payload_with_rollback_store,
+    Capability, DebugLevel, ImagePartition, InMemoryRollbackStore, PvmfwVerifyError, RollbackStore,
+    VerifiedBootData,
+};
 use std::{fs, mem::size_of, ptr};
 use utils::*;
 
@@ -34,7 +38,11 @@ const TEST_IMG_WITH_NON_INITRD_HASHDESC_PATH: &str = "test_image_with_non_initrd
 const TEST_IMG_WITH_INITRD_AND_NON_INITRD_DESC_PATH: &str =
     "test_image_with_initrd_and_non_initrd_desc.img";
 const TEST_IMG_WITH_MULTIPLE_CAPABILITIES: &str = "test_image_with_multiple_capabilities.img";
+const TEST_IMG_WITH_DTBO_AND_VENDOR_BOOT_HASHDESCS_PATH: &str =
+    "test_image_with_dtbo_and_vendor_boot_hashdescs.img";
 const UNSIGNED_TEST_IMG_PATH: &str = "unsigned_test.img";
+const UNSIGNED_TEST_DTBO_IMG_PATH: &str = "unsigned_test_dtbo.img";
+const UNSIGNED_TEST_VENDOR_BOOT_IMG_PATH: &str = "unsigned_test_vendor_boot.img";
 
 const RANDOM_FOOTER_POS: usize = 30;
 
@@ -61,13 +69,11 @@ fn latest_debug_payload_passes_verification() -> Result<()> {
 #[test]
 fn payload_expecting_no_initrd_passes_verification_with_no_initrd() -> Result<()> {
     let public_key = load_trusted_public_key()?;
-    let verified_boot_data = verify_payload(
-        &fs::read(TEST_IMG_WITH_ONE_HASHDESC_PATH)?,
-        /* initrd= */ None,
-        &public_key,
-    )
-    .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
+    let kernel = fs::read(TEST_IMG_WITH_ONE_HASHDESC_PATH)?;
+    let verified_boot_data = verify_payload(&kernel, /* initrd= */ None, &public_key)
+        .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
 
+    let (vbmeta_offset, vbmeta_size) = verified_vbmeta_region(&kernel)?;
     let kernel_digest = hash(&[&hex::decode("1111")?, &fs::read(UNSIGNED_TEST_IMG_PATH)?]);
     let expected_boot_data = VerifiedBootData {
         debug_level: DebugLevel::None,
@@ -76,6 +82,8 @@ fn payload_expecting_no_initrd_passes_verification_with_no_initrd() -> Result<()
         public_key: &public_key,
         capabilities: vec![],
         rollback_index: 0,
+        vbmeta_offset,
+        vbmeta_size,
     };
     assert_eq!(expected_boot_data, verified_boot_data);
 
@@ -105,13 +113,11 @@ fn payload_with_non_initrd_descriptor_fails_verification_with_initrd() -> Result
 #[test]
 fn payload_expecting_no_initrd_passes_verification_with_service_vm_prop() -> Result<()> {
     let public_key = load_trusted_public_key()?;
-    let verified_boot_data = verify_payload(
-        &fs::read(TEST_IMG_WITH_SERVICE_VM_PROP_PATH)?,
-        /* initrd= */ None,
-        &public_key,
-    )
-    .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
+    let kernel = fs::read(TEST_IMG_WITH_SERVICE_VM_PROP_PATH)?;
+    let verified_boot_data = verify_payload(&kernel, /* initrd= */ None, &public_key)
+        .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
 
+    let (vbmeta_offset, vbmeta_size) = verified_vbmeta_region(&kernel)?;
     let kernel_digest = hash(&[&hex::decode("2131")?, &fs::read(UNSIGNED_TEST_IMG_PATH)?]);
     let expected_boot_data = VerifiedBootData {
         debug_level: DebugLevel::None,
@@ -120,6 +126,8 @@ fn payload_expecting_no_initrd_passes_verification_with_service_vm_prop() -> Res
         public_key: &public_key,
         capabilities: vec![Capability::RemoteAttest],
         rollback_index: 0,
+        vbmeta_offset,
+        vbmeta_size,
     };
     assert_eq!(expected_boot_data, verified_boot_data);
 
@@ -239,6 +247,78 @@ fn tampered_kernel_fails_verification() -> Result<()> {
     )
 }
 
+#[test]
+fn verify_images_measures_each_image() -> Result<()> {
+    let public_key = load_trusted_public_key()?;
+    let kernel = fs::read(TEST_IMG_WITH_DTBO_AND_VENDOR_BOOT_HASHDESCS_PATH)?;
+    let dtbo = fs::read(UNSIGNED_TEST_DTBO_IMG_PATH)?;
+    let vendor_boot = fs::read(UNSIGNED_TEST_VENDOR_BOOT_IMG_PATH)?;
+    let images = [(ImagePartition::Dtbo, &dtbo[..]), (ImagePartition::VendorBoot, &vendor_boot)];
+
+    let verified_images = verify_images(&kernel, &images, &public_key).unwrap();
+
+    let kernel_digest = hash(&[&hex::decode("5500")?, &fs::read(UNSIGNED_TEST_IMG_PATH)?]);
+    assert_eq!(verified_images.kernel.kernel_digest, kernel_digest);
+    let dtbo_digest = hash(&[&hex::decode("5511")?, &dtbo]);
+    let vendor_boot_digest = hash(&[&hex::decode("5522")?, &vendor_boot]);
+    assert_eq!(verified_images.image_digests, [Ok(dtbo_digest), Ok(vendor_boot_digest)]);
+    Ok(())
+}
+
+#[test]
+fn verify_images_reports_each_image() -> Result<()> {
+    let public_key = load_trusted_public_key()?;
+    let kernel = fs::read(TEST_IMG_WITH_DTBO_AND_VENDOR_BOOT_HASHDESCS_PATH)?;
+    let dtbo = fs::read(UNSIGNED_TEST_DTBO_IMG_PATH)?;
+    // Same size as the expected vendor_boot image, but not the one its descriptor was made from.
+    let images = [(ImagePartition::Dtbo, &dtbo[..]), (ImagePartition::VendorBoot, &dtbo)];
+
+    let verified_images = verify_images(&kernel, &images, &public_key).unwrap();
+
+    let dtbo_digest = hash(&[&hex::decode("5511")?, &dtbo]);
+    assert_eq!(
+        verified_images.image_digests,
+        [Ok(dtbo_digest), Err(SlotVerifyError::Verification(None).into())]
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_images_fails_when_a_described_image_is_missing() -> Result<()> {
+    let public_key = load_trusted_public_key()?;
+    let kernel = fs::read(TEST_IMG_WITH_DTBO_AND_VENDOR_BOOT_HASHDESCS_PATH)?;
+    let dtbo = fs::read(UNSIGNED_TEST_DTBO_IMG_PATH)?;
+
+    assert_eq!(
+        verify_images(&kernel, &[(ImagePartition::Dtbo, &dtbo)], &public_key),
+        Err(SlotVerifyError::InvalidMetadata.into())
+    );
+    assert_eq!(
+        verify_payload(&kernel, None, &public_key),
+        Err(SlotVerifyError::InvalidMetadata.into())
+    );
+    Ok(())
+}
+
+#[test]
+fn verify_images_fails_with_duplicated_image() -> Result<()> {
+    let public_key = load_trusted_public_key()?;
+    let kernel = fs::read(TEST_IMG_WITH_DTBO_AND_VENDOR_BOOT_HASHDESCS_PATH)?;
+    let dtbo = fs::read(UNSIGNED_TEST_DTBO_IMG_PATH)?;
+    let vendor_boot = fs::read(UNSIGNED_TEST_VENDOR_BOOT_IMG_PATH)?;
+    let images = [
+        (ImagePartition::Dtbo, &dtbo[..]),
+        (ImagePartition::VendorBoot, &vendor_boot),
+        (ImagePartition::Dtbo, &dtbo),
+    ];
+
+    assert_eq!(
+        verify_images(&kernel, &images, &public_key),
+        Err(SlotVerifyError::InvalidMetadata.into())
+    );
+    Ok(())
+}
+
 #[test]
 fn kernel_footer_with_vbmeta_offset_overwritten_fails_verification() -> Result<()> {
     // Arrange.
@@ -392,13 +472,11 @@ fn vbmeta_with_verification_flag_disabled_fails_verification() -> Result<()> {
 #[test]
 fn payload_with_rollback_index() -> Result<()> {
     let public_key = load_trusted_public_key()?;
-    let verified_boot_data = verify_payload(
-        &fs::read(TEST_IMG_WITH_ROLLBACK_INDEX_5)?,
-        /* initrd= */ None,
-        &public_key,
-    )
-    .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
+    let kernel = fs::read(TEST_IMG_WITH_ROLLBACK_INDEX_5)?;
+    let verified_boot_data = verify_payload(&kernel, /* initrd= */ None, &public_key)
+        .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
 
+    let (vbmeta_offset, vbmeta_size) = verified_vbmeta_region(&kernel)?;
     let kernel_digest = hash(&[&hex::decode("1211")?, &fs::read(UNSIGNED_TEST_IMG_PATH)?]);
     let expected_boot_data = VerifiedBootData {
         debug_level: DebugLevel::None,
@@ -407,11 +485,64 @@ fn payload_with_rollback_index() -> Result<()> {
         public_key: &public_key,
         capabilities: vec![],
         rollback_index: 5,
+        vbmeta_offset,
+        vbmeta_size,
     };
     assert_eq!(expected_boot_data, verified_boot_data);
     Ok(())
 }
 
+#[test]
+fn payload_with_rollback_index_lower_than_stored_fails() -> Result<()> {
+    let public_key = load_trusted_public_key()?;
+    let kernel = fs::read(TEST_IMG_WITH_ROLLBACK_INDEX_5)?;
+    let mut rollback_store = InMemoryRollbackStore::default();
+    rollback_store.write(0, 6).unwrap();
+
+    let err = verify_payload_with_rollback_store(&kernel, None, &public_key, &mut rollback_store)
+        .unwrap_err();
+
+    assert_eq!(err, SlotVerifyError::RollbackIndex.into());
+    Ok(())
+}
+
+#[test]
+fn payload_with_rollback_index_equal_to_stored_passes() -> Result<()> {
+    let public_key = load_trusted_public_key()?;
+    let kernel = fs::read(TEST_IMG_WITH_ROLLBACK_INDEX_5)?;
+    let mut rollback_store = InMemoryRollbackStore::default();
+    rollback_store.write(0, 5).unwrap();
+
+    let verified_boot_data =
+        verify_payload_with_rollback_store(&kernel, None, &public_key, &mut rollback_store)
+            .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
+
+    assert_eq!(verified_boot_data.rollback_index, 5);
+    Ok(())
+}
+
+#[test]
+fn verified_vbmeta_region_covers_the_signed_vbmeta() -> Result<()> {
+    let public_key = load_trusted_public_key()?;
+    let kernel = load_latest_signed_kernel()?;
+    let verified_boot_data =
+        verify_payload(&kernel, Some(&load_latest_initrd_normal()?), &public_key)
+            .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
+
+    let footer = extract_avb_footer(&kernel)?;
+    let vbmeta_header = extract_vbmeta_header(&kernel, &footer)?;
+    let vbmeta_offset = verified_boot_data.vbmeta_offset;
+    let vbmeta_size = verified_boot_data.vbmeta_size;
+    assert_eq!(vbmeta_offset, usize::try_from(footer.vbmeta_offset)?);
+    assert!(kernel[vbmeta_offset..].starts_with(b"AVB0"));
+    let header_and_blocks_size = size_of::<AvbVBMetaImageHeader>()
+        + usize::try_from(vbmeta_header.authentication_data_block_size)?
+        + usize::try_from(vbmeta_header.auxiliary_data_block_size)?;
+    assert_eq!(vbmeta_size, header_and_blocks_size);
+    assert!(vbmeta_size <= usize::try_from(footer.vbmeta_size)?);
+    Ok(())
+}
+
 #[test]
 fn payload_with_multiple_capabilities() -> Result<()> {
     let public_key = load_trusted_public_key()?;
@@ -426,3 +557,22 @@ fn payload_with_multiple_capabilities() -> Result<()> {
     assert!(verified_boot_data.has_capability(Capability::SecretkeeperProtection));
     Ok(())
 }
+
+#[test]
+fn vbmeta_public_key_matches_signing_key() -> Result<()> {
+    let kernel = load_latest_signed_kernel()?;
+
+    let public_key = vbmeta_public_key(&kernel)
+        .map_err(|e| anyhow!("Failed to extract the public key. Error: {}", e))?;
+
+    assert_eq!(load_trusted_public_key()?, public_key);
+    Ok(())
+}
+
+#[test]
+fn vbmeta_public_key_fails_for_unsigned_image() -> Result<()> {
+    let image = fs::read(UNSIGNED_TEST_IMG_PATH)?;
+
+    assert_eq!(vbmeta_public_key(&image), Err(SlotVerifyError::InvalidMetadata.into()));
+    Ok(())
+}
@@ -99,6 +99,17 @@ pub fn extract_vbmeta_header(kernel: &[u8], footer: &AvbFooter) -> Result<AvbVBM
     Ok(vbmeta_header)
 }
 
+/// Returns the `(offset, size)` of the vbmeta verified by libavb in `kernel`, i.e. its header and
+/// its authentication and auxiliary data blocks, at the offset given by the footer.
+pub fn verified_vbmeta_region(kernel: &[u8]) -> Result<(usize, usize)> {
+    let footer = extract_avb_footer(kernel)?;
+    let vbmeta_header = extract_vbmeta_header(kernel, &footer)?;
+    let vbmeta_size = size_of::<AvbVBMetaImageHeader>()
+        + usize::try_from(vbmeta_header.authentication_data_block_size)?
+        + usize::try_from(vbmeta_header.auxiliary_data_block_size)?;
+    Ok((footer.vbmeta_offset.try_into()?, vbmeta_size))
+}
+
 pub fn assert_latest_payload_verification_passes(
     initrd: &[u8],
     initrd_salt: &[u8],
@@ -110,6 +121,7 @@ pub fn assert_latest_payload_verification_passes(
         .map_err(|e| anyhow!("Verification failed. Error: {}", e))?;
 
     let footer = extract_avb_footer(&kernel)?;
+    let (vbmeta_offset, vbmeta_size) = verified_vbmeta_region(&kernel)?;
     let kernel_digest =
         hash(&[&hash(&[b"bootloader"]), &kernel[..usize::try_from(footer.original_image_size)?]]);
     let capabilities =
@@ -122,6 +134,8 @@ pub fn assert_latest_payload_verification_passes(
         public_key: &public_key,
         capabilities,
         rollback_index: if cfg!(llpvm_changes) { 1 } else { 0 },
+        vbmeta_offset,
+        vbmeta_size,
     };
     assert_eq!(expected_boot_data, verified_boot_data);
 
@@ -216,6 +216,8 @@ mod tests {
         public_key: b"public key",
         capabilities: vec![],
         rollback_index: 42,
+        vbmeta_offset: 0,
+        vbmeta_size: 0,
     };
     const HASH: Hash = *b"sixtyfourbyteslongsentencearerarebutletsgiveitatrycantbethathard";
 
@@ -16,7 +16,10 @@
 
 use crate::config;
 use crate::fdt;
+use crate::fdt_util;
 use crate::memory;
+use crate::regions::{combined_size, find_overlap};
+use crate::RebootReason;
 use bssl_sys::CRYPTO_library_init;
 use core::arch::asm;
 use core::mem::{drop, size_of};
@@ -27,40 +30,23 @@ use log::debug;
 use log::error;
 use log::info;
 use log::warn;
-use log::LevelFilter;
 use vmbase::util::RangeExt as _;
 use vmbase::{
     configure_heap, console,
-    hyp::{get_mem_sharer, get_mmio_guard},
+    heap::heap_stats,
+    hyp::{get_mem_sharer, get_mmio_guard, hypervisor_features},
     layout::{self, crosvm},
     main,
-    memory::{min_dcache_line_size, MemoryTracker, MEMORY, SIZE_128KB, SIZE_4KB},
+    memory::{min_dcache_line_size, MemoryTracker, MEMORY, SIZE_128KB, SIZE_256KB, SIZE_4KB},
     power::reboot,
 };
 use zeroize::Zeroize;
 
-#[derive(Debug, Clone)]
-pub enum RebootReason {
-    /// A malformed BCC was received.
-    InvalidBcc,
-    /// An invalid configuration was appended to pvmfw.
-    InvalidConfig,
-    /// An unexpected internal error happened.
-    InternalError,
-    /// The provided FDT was invalid.
-    InvalidFdt,
-    /// The provided payload was invalid.
-    InvalidPayload,
-    /// The provided ramdisk was invalid.
-    InvalidRamdisk,
-    /// Failed to verify the payload.
-    PayloadVerificationError,
-    /// DICE layering process failed.
-    SecretDerivationError,
-}
-
 main!(start);
-configure_heap!(SIZE_128KB);
+/// Size of the heap, which must be large enough for the AVB metadata of the payload.
+const HEAP_SIZE: usize = if cfg!(feature = "large_heap") { SIZE_256KB } else { SIZE_128KB };
+
+configure_heap!(HEAP_SIZE);
 
 /// Entry point for pVM firmware.
 pub fn start(fdt_address: u64, payload_start: u64, payload_size: u64, _arg3: u64) {
@@ -102,6 +88,12 @@ impl<'a> MemorySlices<'a> {
         // SAFETY: The tracker validated the range to be in main memory, mapped, and not overlap.
         let fdt = unsafe { slice::from_raw_parts_mut(range.start as *mut u8, range.len()) };
 
+        // Lower the verbosity if requested by the host before the DT gets replaced by the template.
+        // An invalid DT is reported by sanitize_device_tree() below.
+        if let Ok(fdt) = libfdt::Fdt::from_slice(fdt) {
+            log::set_max_level(fdt_util::log_level(fdt));
+        }
+
         let info = fdt::sanitize_device_tree(fdt, vm_dtbo, vm_ref_dt)?;
         let fdt = libfdt::Fdt::from_mut_slice(fdt).map_err(|e| {
             error!("Failed to load sanitized FDT: {e}");
@@ -109,7 +101,18 @@ impl<'a> MemorySlices<'a> {
         })?;
         debug!("Fdt passed validation!");
 
+        let regions = [
+            ("FDT", Some(&range)),
+            ("kernel", info.kernel_range.as_ref()),
+            ("ramdisk", info.initrd_range.as_ref()),
+        ];
+        if let Some((first, second)) = find_overlap(&regions) {
+            error!("The {first} and {second} regions overlap");
+            return Err(RebootReason::OverlappingRegions);
+        }
+
         let memory_range = info.memory_range;
+        check_fits_in_memory(&regions, memory_range.len())?;
         debug!("Resizing MemoryTracker to range {memory_range:#x?}");
         MEMORY.lock().as_mut().unwrap().shrink(&memory_range).map_err(|e| {
             error!("Failed to use memory range value from DT: {memory_range:#x?}: {e}");
@@ -196,7 +199,7 @@ fn main_wrapper(
     // - only perform logging once the logger has been initialized
     // - only access non-pvmfw memory once (and while) it has been mapped
 
-    log::set_max_level(LevelFilter::Info);
+    log::set_max_level(fdt_util::MAX_LOG_LEVEL);
     // TODO(https://crbug.com/boringssl/35): Remove this init when BoringSSL can handle this
     // internally.
     // SAFETY: Configures the internal state of the library - may be called multiple times.
@@ -204,6 +207,11 @@ fn main_wrapper(
         CRYPTO_library_init();
     }
 
+    match hypervisor_features() {
+        Ok(features) => debug!("Hypervisor features: {features:x?}"),
+        Err(e) => warn!("Failed to query the hypervisor features: {e}"),
+    }
+
     let page_table = memory::init_page_table().map_err(|e| {
         error!("Failed to set up the dynamic page tables: {e}");
         RebootReason::InternalError
@@ -234,7 +242,11 @@ fn main_wrapper(
         payload_size,
         config_entries.vm_dtbo,
         config_entries.vm_ref_dt,
-    )?;
+    )
+    .map_err(|e| {
+        error!("Aborting boot: {e}");
+        e
+    })?;
 
     // This wrapper allows main() to be blissfully ignorant of platform details.
     let next_bcc = crate::main(
@@ -243,7 +255,11 @@ fn main_wrapper(
         slices.ramdisk,
         config_entries.bcc,
         config_entries.debug_policy,
-    )?;
+    )
+    .map_err(|e| {
+        error!("Aborting boot: {e}");
+        e
+    })?;
 
     // Writable-dirty regions will be flushed when MemoryTracker is dropped.
     config_entries.bcc.zeroize();
@@ -262,6 +278,9 @@ fn main_wrapper(
         })?;
     }
 
+    let heap_stats = heap_stats();
+    debug!("Heap usage: {heap_stats:?}");
+
     // Drop MemoryTracker and deactivate page table.
     drop(MEMORY.lock().take());
 
@@ -445,3 +464,19 @@ impl<'a> AppendedPayload<'a> {
         }
     }
 }
+
+/// Checks that the combined size of the given regions doesn't exceed `memory_size`.
+fn check_fits_in_memory(
+    regions: &[(&str, Option<&Range<usize>>)],
+    memory_size: usize,
+) -> Result<(), RebootReason> {
+    let total = combined_size(regions).ok_or_else(|| {
+        error!("The combined size of the FDT, kernel, and ramdisk overflows");
+        RebootReason::PayloadTooLarge
+    })?;
+    if total > memory_size {
+        error!("The FDT, kernel, and ramdisk need {total:#x} bytes but memory is {memory_size:#x}");
+        return Err(RebootReason::PayloadTooLarge);
+    }
+    Ok(())
+}
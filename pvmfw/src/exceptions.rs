@@ -14,11 +14,12 @@
 
 //! Exception handlers.
 
+use crate::fault_stats::{record_permission_fault, record_translation_fault};
 use vmbase::{
     eprintln,
     exceptions::{ArmException, Esr, HandleExceptionError},
     logger,
-    memory::{handle_permission_fault, handle_translation_fault},
+    memory::{handle_permission_fault, handle_translation_fault, is_mmio_address},
     power::reboot,
     read_sysreg,
 };
@@ -27,11 +28,24 @@ fn handle_exception(exception: &ArmException) -> Result<(), HandleExceptionError
     // Handle all translation faults on both read and write, and MMIO guard map
     // flagged invalid pages or blocks that caused the exception.
     // Handle permission faults for DBM flagged entries, and flag them as dirty on write.
-    match exception.esr {
-        Esr::DataAbortTranslationFault => handle_translation_fault(exception.far),
+    let result = match exception.esr {
+        Esr::DataAbortTranslationFault => {
+            // Only MMIO guard map pages of the MMIO ranges, never main memory or unknown addresses.
+            match is_mmio_address(exception.far) {
+                Ok(true) => handle_translation_fault(exception.far),
+                Ok(false) => Err(HandleExceptionError::UnknownAddress),
+                Err(e) => Err(e),
+            }
+        }
         Esr::DataAbortPermissionFault => handle_permission_fault(exception.far),
         _ => Err(HandleExceptionError::UnknownException),
+    };
+    match exception.esr {
+        Esr::DataAbortTranslationFault => record_translation_fault(result.is_ok()),
+        Esr::DataAbortPermissionFault => record_permission_fault(),
+        _ => {}
     }
+    result
 }
 
 #[no_mangle]
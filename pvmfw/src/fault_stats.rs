@@ -0,0 +1,73 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Counters of the faults handled by the exception handler, for boot time analysis.
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+static TRANSLATION_FAULTS: AtomicUsize = AtomicUsize::new(0);
+static PERMISSION_FAULTS: AtomicUsize = AtomicUsize::new(0);
+static MMIO_FAULTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Numbers of faults handled by the exception handler since boot.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct FaultStats {
+    /// Translation faults, whether they were successfully handled or not.
+    pub translation_faults: usize,
+    /// Permission faults, whether they were successfully handled or not.
+    pub permission_faults: usize,
+    /// Translation faults successfully handled by lazily mapping MMIO.
+    pub mmio_faults: usize,
+}
+
+/// Returns the numbers of faults handled so far.
+pub fn fault_stats() -> FaultStats {
+    FaultStats {
+        translation_faults: TRANSLATION_FAULTS.load(Ordering::Relaxed),
+        permission_faults: PERMISSION_FAULTS.load(Ordering::Relaxed),
+        mmio_faults: MMIO_FAULTS.load(Ordering::Relaxed),
+    }
+}
+
+/// Records a translation fault, which was `handled` by lazily mapping MMIO or not.
+pub fn record_translation_fault(handled: bool) {
+    TRANSLATION_FAULTS.fetch_add(1, Ordering::Relaxed);
+    if handled {
+        MMIO_FAULTS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Records a permission fault.
+pub fn record_permission_fault() {
+    PERMISSION_FAULTS.fetch_add(1, Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn faults_increment_matching_counters() {
+        let before = fault_stats();
+
+        record_translation_fault(true);
+        record_translation_fault(false);
+        record_permission_fault();
+
+        let after = fault_stats();
+        assert_eq!(after.translation_faults - before.translation_faults, 2);
+        assert_eq!(after.mmio_faults - before.mmio_faults, 1);
+        assert_eq!(after.permission_faults - before.permission_faults, 1);
+    }
+}
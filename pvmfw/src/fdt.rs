@@ -16,6 +16,7 @@
 
 use crate::bootargs::BootArgsIterator;
 use crate::device_assignment::{self, DeviceAssignmentInfo, VmDtbo};
+use crate::fdt_util::{dice_handover_range, usable_dram};
 use crate::helpers::GUEST_PAGE_SIZE;
 use crate::Box;
 use crate::RebootReason;
@@ -159,6 +160,18 @@ fn read_and_validate_memory_range(fdt: &Fdt) -> Result<Range<usize>, RebootReaso
              while only one is expected."
         );
     }
+    let range = match usable_dram(fdt) {
+        Ok(None) => range,
+        Ok(Some(dram)) if dram.is_within(&range) => dram,
+        Ok(Some(dram)) => {
+            error!("Usable DRAM {dram:#x?} in /chosen is outside of /memory {range:#x?}");
+            return Err(RebootReason::InvalidFdt);
+        }
+        Err(e) => {
+            error!("Failed to read usable DRAM from /chosen: {e}");
+            return Err(RebootReason::InvalidFdt);
+        }
+    };
     let base = range.start;
     if base != MEM_START {
         error!("Memory base address {:#x} is not {:#x}", base, MEM_START);
@@ -878,11 +891,7 @@ fn patch_timer(fdt: &mut Fdt, num_cpus: usize) -> libfdt::Result<()> {
 }
 
 fn patch_untrusted_props(fdt: &mut Fdt, props: &BTreeMap<CString, Vec<u8>>) -> libfdt::Result<()> {
-    let avf_node = if let Some(node) = fdt.node_mut(cstr!("/avf"))? {
-        node
-    } else {
-        fdt.root_mut().add_subnode(cstr!("avf"))?
-    };
+    let avf_node = fdt.get_or_add_node(cstr!("/avf"))?;
 
     // The node shouldn't already be present; if it is, return the error.
     let mut node = avf_node.add_subnode(cstr!("untrusted"))?;
@@ -1204,6 +1213,7 @@ pub fn modify_for_next_stage(
     }
 
     patch_dice_node(fdt, bcc.as_ptr() as usize, bcc.len())?;
+    debug!("DICE handover at {:#x?}", dice_handover_range(fdt)?);
 
     if let Some(mut chosen) = fdt.chosen_mut()? {
         empty_or_delete_prop(&mut chosen, cstr!("avf,strict-boot"), strict_boot)?;
@@ -1216,7 +1226,7 @@ pub fn modify_for_next_stage(
         }
     }
 
-    fdt.pack()?;
+    fdt.finalize()?;
 
     Ok(())
 }
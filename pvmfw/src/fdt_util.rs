@@ -0,0 +1,705 @@
+// Copyright 2022, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! DT readers and formatters that don't depend on the rest of pvmfw.
+//! Kept apart from fdt.rs so that it can be unit tested on its own.
+
+#[cfg(test)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::ffi::CStr;
+use core::fmt;
+use core::mem::size_of;
+use core::ops::Range;
+use cstr::cstr;
+use libfdt::Fdt;
+use libfdt::FdtError;
+use libfdt::FdtNode;
+use libfdt::Reg;
+use log::debug;
+use log::error;
+use log::warn;
+use log::LevelFilter;
+
+/// Reads the "avf,new-instance" flag from /chosen.
+///
+/// The flag is set if the property is empty or holds a non-zero cell and cleared if it holds a
+/// zero cell. Absence of the property (or of /chosen) conservatively defaults to an existing
+/// instance.
+pub fn is_new_instance(fdt: &Fdt) -> libfdt::Result<bool> {
+    let Some(chosen) = fdt.chosen()? else {
+        return Ok(false);
+    };
+    match chosen.getprop(cstr!("avf,new-instance"))? {
+        None => Ok(false),
+        Some([]) => Ok(true),
+        Some(value) => {
+            let value: [u8; size_of::<u32>()] = value.try_into().map_err(|_| FdtError::BadValue)?;
+            Ok(u32::from_be_bytes(value) != 0)
+        }
+    }
+}
+
+/// Reads the 16-byte instance identifier from the "avf,instance-id" property of /chosen.
+///
+/// Absence of the property is not an error but a value of unexpected length is.
+pub fn instance_id(fdt: &Fdt) -> libfdt::Result<Option<[u8; 16]>> {
+    let Some(chosen) = fdt.chosen()? else {
+        return Ok(None);
+    };
+    let Some(id) = chosen.getprop(cstr!("avf,instance-id"))? else {
+        return Ok(None);
+    };
+    id.try_into().map(Some).map_err(|_| {
+        error!("Invalid avf,instance-id length: {}", id.len());
+        FdtError::BadValue
+    })
+}
+
+/// Most verbose level logged by pvmfw.
+///
+/// Debug logs, such as the boot timeline and the DT dump, are only available in builds with the
+/// `debug_logs` feature, which are meant for local development.
+pub const MAX_LOG_LEVEL: LevelFilter =
+    if cfg!(feature = "debug_logs") { LevelFilter::Debug } else { LevelFilter::Info };
+
+/// Reads the log level requested by the host from the "avf,log-level" property of /chosen.
+///
+/// The property is one of the strings "off", "error", "warn", "info", "debug" or "trace". Absence
+/// of the property or an invalid value defaults to [`MAX_LOG_LEVEL`]. As the DT comes from the
+/// untrusted host, which must not get the details of the boot of a protected VM logged to the
+/// console, it can lower the verbosity but never raise it above [`MAX_LOG_LEVEL`].
+pub fn log_level(fdt: &Fdt) -> LevelFilter {
+    match read_log_level_from(fdt) {
+        Ok(level) => level.map_or(MAX_LOG_LEVEL, |level| level.min(MAX_LOG_LEVEL)),
+        Err(e) => {
+            warn!("Ignoring invalid avf,log-level: {e}");
+            MAX_LOG_LEVEL
+        }
+    }
+}
+
+fn read_log_level_from(fdt: &Fdt) -> libfdt::Result<Option<LevelFilter>> {
+    let Some(chosen) = fdt.chosen()? else {
+        return Ok(None);
+    };
+    let Some(level) = chosen.getprop_utf8_str(cstr!("avf,log-level"))? else {
+        return Ok(None);
+    };
+    level.parse().map(Some).map_err(|_| FdtError::BadValue)
+}
+
+/// Reads the "rng-seed" supplied by the VMM in /chosen, if any, so that it can be reused or folded
+/// into the seeds generated by pvmfw. An empty seed is rejected as it carries no entropy.
+pub fn existing_rng_seed(fdt: &Fdt) -> libfdt::Result<Option<&[u8]>> {
+    let Some(chosen) = fdt.chosen()? else {
+        return Ok(None);
+    };
+    match chosen.getprop(cstr!("rng-seed"))? {
+        Some([]) => Err(FdtError::BadValue),
+        seed => Ok(seed),
+    }
+}
+
+/// Longest serial number accepted by `set_serial_number()`, excluding the NUL terminator.
+pub const MAX_SERIAL_NUMBER_LEN: usize = 128;
+
+/// Passes the device serial number to the payload through the standard "serial-number" property
+/// of the root node, replacing any value provided by the VMM.
+pub fn set_serial_number(fdt: &mut Fdt, serial: &str) -> libfdt::Result<()> {
+    if serial.is_empty() || serial.len() > MAX_SERIAL_NUMBER_LEN || serial.contains('\0') {
+        return Err(FdtError::BadValue);
+    }
+    let mut value = Vec::from(serial.as_bytes());
+    value.push(b'\0');
+    fdt.root_mut().setprop(cstr!("serial-number"), &value)
+}
+
+/// Reads the usable DRAM window that the VMM may pass in /chosen, which takes precedence over the
+/// /memory node. Absence of the window is not an error but a partial one is.
+pub fn usable_dram(fdt: &Fdt) -> libfdt::Result<Option<Range<usize>>> {
+    let Some(chosen) = fdt.chosen()? else {
+        return Ok(None);
+    };
+    let base = chosen.getprop_u64(cstr!("avf,dram-base"))?;
+    let size = chosen.getprop_u64(cstr!("avf,dram-size"))?;
+    let (base, size) = match (base, size) {
+        (None, None) => return Ok(None),
+        (Some(base), Some(size)) => (base, size),
+        _ => return Err(FdtError::NotFound),
+    };
+    let base = usize::try_from(base).map_err(|_| FdtError::BadValue)?;
+    let size = usize::try_from(size).map_err(|_| FdtError::BadValue)?;
+    let end = base.checked_add(size).ok_or(FdtError::BadValue)?;
+
+    Ok(Some(base..end))
+}
+
+/// Translates `bus_addr`, from the address space of the children of the node at `path` to the one
+/// of its parent, using the translation windows of the "ranges" property of the node.
+///
+/// Returns `None` if the node has no "ranges" or if none of its windows contains `bus_addr`. An
+/// empty "ranges" property maps the child address space to the parent one without translation.
+pub fn translate_bus_address(fdt: &Fdt, path: &CStr, bus_addr: u64) -> libfdt::Result<Option<u64>> {
+    let node = fdt.node(path)?.ok_or(FdtError::NotFound)?;
+    let Some(ranges) = node.getprop(cstr!("ranges"))? else {
+        return Ok(None);
+    };
+    if ranges.is_empty() {
+        return Ok(Some(bus_addr));
+    }
+
+    // The parsing of "ranges" panics for cell counts which can't be represented by its types.
+    let cells = |node: &FdtNode, name: &CStr, default: u32| -> libfdt::Result<u32> {
+        Ok(node.getprop_u32(name)?.unwrap_or(default))
+    };
+    let addr_cells = cells(&node, cstr!("#address-cells"), 2)?;
+    let size_cells = cells(&node, cstr!("#size-cells"), 1)?;
+    let parent_addr_cells = cells(&node.parent()?, cstr!("#address-cells"), 2)?;
+    if !matches!(size_cells, 1 | 2) || !matches!(parent_addr_cells, 1 | 2) {
+        return Err(FdtError::BadNCells);
+    }
+
+    let windows: Vec<_> = match addr_cells {
+        1 | 2 => node
+            .ranges::<u64, u64, u64>()?
+            .ok_or(FdtError::NotFound)?
+            .map(|r| (r.addr, r.parent_addr, r.size))
+            .collect(),
+        // PCI addresses start with a cell of flags (see PciMemoryFlags), which isn't translated.
+        3 => node
+            .ranges::<(u32, u64), u64, u64>()?
+            .ok_or(FdtError::NotFound)?
+            .map(|r| (r.addr.1, r.parent_addr, r.size))
+            .collect(),
+        _ => return Err(FdtError::BadNCells),
+    };
+
+    for (addr, parent_addr, size) in windows {
+        let Some(offset) = bus_addr.checked_sub(addr) else {
+            continue;
+        };
+        if offset < size {
+            return parent_addr.checked_add(offset).ok_or(FdtError::BadValue).map(Some);
+        }
+    }
+
+    Ok(None)
+}
+
+/// Register ranges of the interrupt controller.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GicInfo {
+    /// GICv2, with a memory-mapped CPU interface.
+    V2 {
+        /// Distributor (GICD) registers.
+        distributor: Range<usize>,
+        /// CPU interface (GICC) registers.
+        cpu_interface: Range<usize>,
+    },
+    /// GICv3, with one redistributor frame per CPU.
+    V3 {
+        /// Distributor (GICD) registers.
+        distributor: Range<usize>,
+        /// Redistributor (GICR) registers, covering all CPUs.
+        redistributor: Range<usize>,
+    },
+}
+
+/// Reads the register ranges of the first GICv3 node, or else of the first GICv2 node. Absence of
+/// an interrupt controller is not an error.
+pub fn gic_info(fdt: &Fdt) -> libfdt::Result<Option<GicInfo>> {
+    const GIC_V2_COMPATIBLES: [&CStr; 2] = [cstr!("arm,gic-400"), cstr!("arm,cortex-a15-gic")];
+
+    if let Some(node) = fdt.compatible_nodes(cstr!("arm,gic-v3"))?.next() {
+        let (distributor, redistributor) = first_two_reg_ranges(&node)?;
+        return Ok(Some(GicInfo::V3 { distributor, redistributor }));
+    }
+    for compatible in GIC_V2_COMPATIBLES {
+        if let Some(node) = fdt.compatible_nodes(compatible)?.next() {
+            let (distributor, cpu_interface) = first_two_reg_ranges(&node)?;
+            return Ok(Some(GicInfo::V2 { distributor, cpu_interface }));
+        }
+    }
+    Ok(None)
+}
+
+fn first_two_reg_ranges(node: &FdtNode) -> libfdt::Result<(Range<usize>, Range<usize>)> {
+    let mut regs = node.reg()?.ok_or(FdtError::NotFound)?;
+    let first = reg_to_range(regs.next().ok_or(FdtError::NotFound)?)?;
+    let second = reg_to_range(regs.next().ok_or(FdtError::NotFound)?)?;
+    Ok((first, second))
+}
+
+fn reg_to_range(reg: Reg<u64>) -> libfdt::Result<Range<usize>> {
+    let addr = usize::try_from(reg.addr).map_err(|_| FdtError::BadValue)?;
+    let size =
+        usize::try_from(reg.size.ok_or(FdtError::NotFound)?).map_err(|_| FdtError::BadValue)?;
+    let end = addr.checked_add(size).ok_or(FdtError::BadValue)?;
+    Ok(addr..end)
+}
+
+/// Logs the content of the DT, in a format similar to the DTS output of `dtc`.
+///
+/// The DT is logged at the debug level, so it is only seen in builds with the `debug_logs` feature
+/// (see [`MAX_LOG_LEVEL`]).
+pub fn dump_fdt(fdt: &Fdt) -> libfdt::Result<()> {
+    let mut open_nodes = 0;
+    walk_fdt(fdt, |node, depth| {
+        close_dts_nodes(depth, open_nodes);
+        let name = node.name()?.to_str().map_err(|_| FdtError::BadValue)?;
+        let name = if depth == 0 { "/" } else { name };
+        debug!("{:indent$}{name} {{", "", indent = depth * DTS_INDENT);
+        for property in node.properties()? {
+            let name = property.name()?.to_str().map_err(|_| FdtError::BadValue)?;
+            let value = DtsValue(property.value()?);
+            debug!("{:indent$}{name}{value};", "", indent = (depth + 1) * DTS_INDENT);
+        }
+        open_nodes = depth + 1;
+        Ok(())
+    })?;
+    close_dts_nodes(0, open_nodes);
+    Ok(())
+}
+
+const DTS_INDENT: usize = 4;
+
+/// Calls `visit` on every node of the DT in depth-first order, along with the node depth.
+fn walk_fdt<'a>(
+    fdt: &'a Fdt,
+    mut visit: impl FnMut(FdtNode<'a>, usize) -> libfdt::Result<()>,
+) -> libfdt::Result<()> {
+    let root = fdt.root();
+    visit(root, 0)?;
+    for (node, depth) in root.descendants() {
+        visit(node, depth)?;
+    }
+    Ok(())
+}
+
+/// Closes the DTS nodes deeper than `depth`, given that `open_nodes` are currently open.
+fn close_dts_nodes(depth: usize, open_nodes: usize) {
+    for depth in (depth..open_nodes).rev() {
+        debug!("{:indent$}}};", "", indent = depth * DTS_INDENT);
+    }
+}
+
+/// Formats a DT property value as in DTS: as strings, cells or bytes.
+struct DtsValue<'a>(&'a [u8]);
+
+impl fmt::Display for DtsValue<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let value = self.0;
+        if value.is_empty() {
+            return Ok(());
+        }
+        let is_printable =
+            |s: &[u8]| !s.is_empty() && s.iter().all(|c| c.is_ascii_graphic() || *c == b' ');
+        if let Some(strings) = value.strip_suffix(&[0]) {
+            if strings.split(|c| *c == 0).all(is_printable) {
+                f.write_str(" = ")?;
+                for (i, string) in strings.split(|c| *c == 0).enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    // The bytes were checked to be printable ASCII.
+                    write!(f, "\"{}\"", core::str::from_utf8(string).unwrap())?;
+                }
+                return Ok(());
+            }
+        }
+        if value.len() % size_of::<u32>() == 0 {
+            f.write_str(" = <")?;
+            for (i, cell) in value.chunks_exact(size_of::<u32>()).enumerate() {
+                let cell = u32::from_be_bytes(cell.try_into().unwrap());
+                write!(f, "{}{cell:#x}", if i > 0 { " " } else { "" })?;
+            }
+            f.write_str(">")
+        } else {
+            f.write_str(" = [")?;
+            for (i, byte) in value.iter().enumerate() {
+                write!(f, "{}{byte:02x}", if i > 0 { " " } else { "" })?;
+            }
+            f.write_str("]")
+        }
+    }
+}
+
+/// Reads the range of the "google,open-dice"-compatible reserved-memory node, which holds the DICE
+/// handover. Absence of the node is not an error.
+pub fn dice_handover_range(fdt: &Fdt) -> libfdt::Result<Option<Range<usize>>> {
+    reserved_memory_by_compatible(fdt, cstr!("google,open-dice"))
+}
+
+/// Reads the `reg` range of the first child of /reserved-memory listing `compatible` in its
+/// "compatible" property. Absence of such a node is not an error.
+pub fn reserved_memory_by_compatible(
+    fdt: &Fdt,
+    compatible: &CStr,
+) -> libfdt::Result<Option<Range<usize>>> {
+    let Some(reserved_memory) = fdt.node(cstr!("/reserved-memory"))? else {
+        return Ok(None);
+    };
+    for node in reserved_memory.subnodes()? {
+        let Some(compatibles) = node.getprop(cstr!("compatible"))? else {
+            continue;
+        };
+        if !compatibles.split(|c| *c == 0).any(|c| c == compatible.to_bytes()) {
+            continue;
+        }
+
+        return reg_to_range(node.first_reg()?).map(Some);
+    }
+
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::vec;
+
+    fn fdt_with_chosen(data: &mut [u8]) -> &mut Fdt {
+        let fdt = Fdt::create_empty_tree(data).unwrap();
+        fdt.root_mut().add_subnode(cstr!("chosen")).unwrap();
+        fdt
+    }
+
+    #[test]
+    fn new_instance_present() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        let mut chosen = fdt.chosen_mut().unwrap().unwrap();
+        chosen.setprop_empty(cstr!("avf,new-instance")).unwrap();
+        assert_eq!(is_new_instance(fdt), Ok(true));
+
+        let mut chosen = fdt.chosen_mut().unwrap().unwrap();
+        chosen.setprop(cstr!("avf,new-instance"), &0_u32.to_be_bytes()).unwrap();
+        assert_eq!(is_new_instance(fdt), Ok(false));
+    }
+
+    #[test]
+    fn new_instance_absent() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+
+        assert_eq!(is_new_instance(fdt), Ok(false));
+    }
+
+    #[test]
+    fn instance_id_valid() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        let id = [0xab_u8; 16];
+        fdt.chosen_mut().unwrap().unwrap().setprop(cstr!("avf,instance-id"), &id).unwrap();
+
+        assert_eq!(instance_id(fdt), Ok(Some(id)));
+    }
+
+    #[test]
+    fn instance_id_wrong_length() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        let id = [0xab_u8; 15];
+        fdt.chosen_mut().unwrap().unwrap().setprop(cstr!("avf,instance-id"), &id).unwrap();
+
+        assert_eq!(instance_id(fdt), Err(FdtError::BadValue));
+    }
+
+    #[test]
+    fn instance_id_absent() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+
+        assert_eq!(instance_id(fdt), Ok(None));
+    }
+
+    #[test]
+    fn log_level_from_property() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        assert_eq!(log_level(fdt), MAX_LOG_LEVEL);
+
+        for (value, level) in [
+            (&b"off\0"[..], LevelFilter::Off),
+            (b"error\0", LevelFilter::Error),
+            (b"warn\0", LevelFilter::Warn),
+            (b"info\0", LevelFilter::Info),
+            (b"debug\0", LevelFilter::Debug.min(MAX_LOG_LEVEL)),
+            (b"trace\0", MAX_LOG_LEVEL),
+            (b"verbose\0", MAX_LOG_LEVEL),
+            (b"debug", MAX_LOG_LEVEL),
+            (&1_u32.to_be_bytes(), MAX_LOG_LEVEL),
+        ] {
+            let mut chosen = fdt.chosen_mut().unwrap().unwrap();
+            chosen.setprop(cstr!("avf,log-level"), value).unwrap();
+            assert_eq!(log_level(fdt), level, "avf,log-level = {value:?}");
+        }
+    }
+
+    #[test]
+    fn max_log_level_only_includes_debug_with_debug_logs() {
+        let expected =
+            if cfg!(feature = "debug_logs") { LevelFilter::Debug } else { LevelFilter::Info };
+        assert_eq!(MAX_LOG_LEVEL, expected);
+    }
+
+    #[test]
+    fn dice_handover_range_locates_node() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+        assert_eq!(dice_handover_range(fdt), Ok(None));
+
+        let mut node = fdt.root_mut().add_subnode(cstr!("reserved-memory")).unwrap();
+        node.setprop(cstr!("#address-cells"), &2_u32.to_be_bytes()).unwrap();
+        node.setprop(cstr!("#size-cells"), &2_u32.to_be_bytes()).unwrap();
+        let mut node = node.add_subnode(cstr!("dice")).unwrap();
+        node.setprop(cstr!("compatible"), b"google,open-dice\0").unwrap();
+        node.setprop(
+            cstr!("reg"),
+            &[0x8000_0000_u64.to_be_bytes(), 0x1000_u64.to_be_bytes()].concat(),
+        )
+        .unwrap();
+
+        assert_eq!(dice_handover_range(fdt), Ok(Some(0x8000_0000..0x8000_1000)));
+    }
+
+    #[test]
+    fn existing_rng_seed_absent() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+        assert_eq!(existing_rng_seed(fdt), Ok(None));
+
+        let fdt = fdt_with_chosen(&mut data);
+        assert_eq!(existing_rng_seed(fdt), Ok(None));
+    }
+
+    #[test]
+    fn existing_rng_seed_present() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        let seed = [0xa5_u8; 32];
+        fdt.chosen_mut().unwrap().unwrap().setprop(cstr!("rng-seed"), &seed).unwrap();
+
+        assert_eq!(existing_rng_seed(fdt), Ok(Some(seed.as_slice())));
+    }
+
+    #[test]
+    fn existing_rng_seed_empty_is_rejected() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        fdt.chosen_mut().unwrap().unwrap().setprop_empty(cstr!("rng-seed")).unwrap();
+
+        assert_eq!(existing_rng_seed(fdt), Err(FdtError::BadValue));
+    }
+
+    #[test]
+    fn usable_dram_valid_window() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        assert_eq!(usable_dram(fdt), Ok(None));
+
+        let mut chosen = fdt.chosen_mut().unwrap().unwrap();
+        chosen.setprop(cstr!("avf,dram-base"), &0x8000_0000_u64.to_be_bytes()).unwrap();
+        assert_eq!(usable_dram(fdt), Err(FdtError::NotFound));
+
+        let mut chosen = fdt.chosen_mut().unwrap().unwrap();
+        chosen.setprop(cstr!("avf,dram-size"), &0x1000_0000_u64.to_be_bytes()).unwrap();
+        assert_eq!(usable_dram(fdt), Ok(Some(0x8000_0000..0x9000_0000)));
+    }
+
+    #[test]
+    fn usable_dram_overflowing_window() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_chosen(&mut data);
+        let mut chosen = fdt.chosen_mut().unwrap().unwrap();
+        chosen.setprop(cstr!("avf,dram-base"), &0x8000_0000_u64.to_be_bytes()).unwrap();
+        chosen.setprop(cstr!("avf,dram-size"), &u64::MAX.to_be_bytes()).unwrap();
+
+        assert_eq!(usable_dram(fdt), Err(FdtError::BadValue));
+    }
+
+    #[test]
+    fn set_serial_number_reads_back() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+
+        set_serial_number(fdt, "0123456789ABCDEF").unwrap();
+        assert_eq!(
+            fdt.root().getprop_str(cstr!("serial-number")),
+            Ok(Some(cstr!("0123456789ABCDEF")))
+        );
+
+        set_serial_number(fdt, "FEDCBA").unwrap();
+        assert_eq!(fdt.root().getprop_str(cstr!("serial-number")), Ok(Some(cstr!("FEDCBA"))));
+    }
+
+    #[test]
+    fn set_serial_number_rejects_invalid_serials() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+        let too_long = "0".repeat(MAX_SERIAL_NUMBER_LEN + 1);
+
+        assert_eq!(set_serial_number(fdt, ""), Err(FdtError::BadValue));
+        assert_eq!(set_serial_number(fdt, "0123\04567"), Err(FdtError::BadValue));
+        assert_eq!(set_serial_number(fdt, &too_long), Err(FdtError::BadValue));
+        assert_eq!(fdt.root().getprop(cstr!("serial-number")), Ok(None));
+    }
+
+    #[test]
+    fn reserved_memory_by_compatible_finds_child() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+        let compatible = cstr!("restricted-dma-pool");
+        assert_eq!(reserved_memory_by_compatible(fdt, compatible), Ok(None));
+
+        let mut node = fdt.root_mut().add_subnode(cstr!("reserved-memory")).unwrap();
+        node.setprop(cstr!("#address-cells"), &2_u32.to_be_bytes()).unwrap();
+        node.setprop(cstr!("#size-cells"), &2_u32.to_be_bytes()).unwrap();
+        let mut dice = node.add_subnode(cstr!("dice")).unwrap();
+        dice.setprop(cstr!("compatible"), b"google,open-dice\0").unwrap();
+        dice.setprop(
+            cstr!("reg"),
+            &[0x8000_0000_u64.to_be_bytes(), 0x1000_u64.to_be_bytes()].concat(),
+        )
+        .unwrap();
+        assert_eq!(reserved_memory_by_compatible(fdt, compatible), Ok(None));
+
+        let node = fdt.node_mut(cstr!("/reserved-memory")).unwrap().unwrap();
+        let mut swiotlb = node.add_subnode(cstr!("swiotlb")).unwrap();
+        swiotlb.setprop(cstr!("compatible"), b"vendor,dma-pool\0restricted-dma-pool\0").unwrap();
+        swiotlb
+            .setprop(
+                cstr!("reg"),
+                &[0x9000_0000_u64.to_be_bytes(), 0x40_0000_u64.to_be_bytes()].concat(),
+            )
+            .unwrap();
+
+        assert_eq!(
+            reserved_memory_by_compatible(fdt, compatible),
+            Ok(Some(0x9000_0000..0x9040_0000))
+        );
+        assert_eq!(
+            reserved_memory_by_compatible(fdt, cstr!("google,open-dice")),
+            Ok(Some(0x8000_0000..0x8000_1000))
+        );
+        assert_eq!(reserved_memory_by_compatible(fdt, cstr!("restricted")), Ok(None));
+    }
+
+    #[test]
+    fn gic_info_decodes_gic_v3() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+        assert_eq!(gic_info(fdt), Ok(None));
+
+        let mut root = fdt.root_mut();
+        root.setprop(cstr!("#address-cells"), &2_u32.to_be_bytes()).unwrap();
+        root.setprop(cstr!("#size-cells"), &2_u32.to_be_bytes()).unwrap();
+        let mut gic = root.add_subnode(cstr!("intc")).unwrap();
+        gic.setprop(cstr!("compatible"), b"arm,gic-v3\0").unwrap();
+        let reg = [0x3fff_0000_u64, 0x1_0000, 0x3ffd_0000, 0x2_0000];
+        gic.setprop(cstr!("reg"), &reg.map(u64::to_be_bytes).concat()).unwrap();
+
+        assert_eq!(
+            gic_info(fdt),
+            Ok(Some(GicInfo::V3 {
+                distributor: 0x3fff_0000..0x4000_0000,
+                redistributor: 0x3ffd_0000..0x3fff_0000,
+            }))
+        );
+    }
+
+    #[test]
+    fn gic_info_rejects_gic_v3_without_redistributor() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+        let mut root = fdt.root_mut();
+        root.setprop(cstr!("#address-cells"), &2_u32.to_be_bytes()).unwrap();
+        root.setprop(cstr!("#size-cells"), &2_u32.to_be_bytes()).unwrap();
+        let mut gic = root.add_subnode(cstr!("intc")).unwrap();
+        gic.setprop(cstr!("compatible"), b"arm,gic-v3\0").unwrap();
+        let reg = [0x3fff_0000_u64, 0x1_0000];
+        gic.setprop(cstr!("reg"), &reg.map(u64::to_be_bytes).concat()).unwrap();
+
+        assert_eq!(gic_info(fdt), Err(FdtError::NotFound));
+    }
+
+    fn fdt_with_bus_ranges(data: &mut [u8]) -> &mut Fdt {
+        let fdt = Fdt::create_empty_tree(data).unwrap();
+        let mut root = fdt.root_mut();
+        root.setprop(cstr!("#address-cells"), &2_u32.to_be_bytes()).unwrap();
+        root.setprop(cstr!("#size-cells"), &2_u32.to_be_bytes()).unwrap();
+        let mut bus = root.add_subnode(cstr!("bus")).unwrap();
+        bus.setprop(cstr!("#address-cells"), &1_u32.to_be_bytes()).unwrap();
+        bus.setprop(cstr!("#size-cells"), &1_u32.to_be_bytes()).unwrap();
+        // <child-addr parent-addr-hi parent-addr-lo size>
+        let ranges = [0x1000_u32, 0x1, 0x2000_0000, 0x800];
+        bus.setprop(cstr!("ranges"), &ranges.map(u32::to_be_bytes).concat()).unwrap();
+        fdt
+    }
+
+    #[test]
+    fn translate_bus_address_inside_window() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_bus_ranges(&mut data);
+
+        assert_eq!(translate_bus_address(fdt, cstr!("/bus"), 0x1000), Ok(Some(0x1_2000_0000)));
+        assert_eq!(translate_bus_address(fdt, cstr!("/bus"), 0x17ff), Ok(Some(0x1_2000_07ff)));
+    }
+
+    #[test]
+    fn translate_bus_address_outside_window() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = fdt_with_bus_ranges(&mut data);
+
+        assert_eq!(translate_bus_address(fdt, cstr!("/bus"), 0xfff), Ok(None));
+        assert_eq!(translate_bus_address(fdt, cstr!("/bus"), 0x1800), Ok(None));
+        assert_eq!(translate_bus_address(fdt, cstr!("/"), 0x1000), Ok(None));
+    }
+
+    #[test]
+    fn walk_fdt_visits_all_nodes() {
+        let mut data = vec![0_u8; 1000];
+        let fdt = Fdt::create_empty_tree(&mut data).unwrap();
+        let node = fdt.root_mut().add_subnode(cstr!("a")).unwrap();
+        node.add_subnodes(&[cstr!("b"), cstr!("c")]).unwrap();
+        fdt.root_mut().add_subnode(cstr!("d")).unwrap();
+
+        let mut visited = Vec::new();
+        walk_fdt(fdt, |node, depth| {
+            visited.push((node.name()?.to_str().unwrap(), depth));
+            Ok(())
+        })
+        .unwrap();
+
+        let mut expected = vec![("", 0), ("a", 1), ("b", 2), ("c", 2), ("d", 1)];
+        // Subnodes are added at the front by libfdt, which doesn't matter here.
+        visited.sort();
+        expected.sort();
+        assert_eq!(visited, expected);
+    }
+
+    #[test]
+    fn dts_value_format() {
+        assert_eq!(format!("{}", DtsValue(b"")), "");
+        assert_eq!(
+            format!("{}", DtsValue(b"ns16550a\0arm,pl011\0")),
+            " = \"ns16550a\", \"arm,pl011\""
+        );
+        assert_eq!(format!("{}", DtsValue(&[0, 0, 0, 1, 0, 0, 0x10, 0])), " = <0x1 0x1000>");
+        assert_eq!(format!("{}", DtsValue(&[0xde, 0xad, 0])), " = [de ad 00]");
+    }
+}
@@ -15,6 +15,17 @@
 //! Miscellaneous helper functions.
 
 use vmbase::memory::{PAGE_SIZE, SIZE_4KB};
+use vmbase::read_sysreg;
 
 pub const GUEST_PAGE_SIZE: usize = SIZE_4KB;
 pub const PVMFW_PAGE_SIZE: usize = PAGE_SIZE;
+
+/// Returns the current value of the virtual counter, in ticks.
+pub fn cycle_counter() -> u64 {
+    read_sysreg!("cntvct_el0") as u64
+}
+
+/// Returns the frequency of the virtual counter, in Hz.
+pub fn cycle_counter_frequency() -> u64 {
+    read_sysreg!("cntfrq_el0") as u64
+}
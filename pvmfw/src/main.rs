@@ -26,20 +26,27 @@ mod device_assignment;
 mod dice;
 mod entry;
 mod exceptions;
+mod fault_stats;
 mod fdt;
+mod fdt_util;
 mod gpt;
 mod helpers;
 mod instance;
 mod memory;
+mod reboot_reason;
+mod regions;
+mod timeline;
+mod virtio;
 
 use crate::bcc::Bcc;
 use crate::dice::PartialInputs;
-use crate::entry::RebootReason;
 use crate::fdt::modify_for_next_stage;
 use crate::helpers::GUEST_PAGE_SIZE;
 use crate::instance::EntryBody;
 use crate::instance::Error as InstanceError;
 use crate::instance::{get_recorded_entry, record_instance_entry};
+use crate::reboot_reason::RebootReason;
+use crate::timeline::{ticks_to_us, BootTimeline};
 use alloc::borrow::Cow;
 use alloc::boxed::Box;
 use bssl_avf::Digester;
@@ -48,11 +55,12 @@ use cstr::cstr;
 use diced_open_dice::{bcc_handover_parse, DiceArtifacts, Hidden};
 use fdtpci::{PciError, PciInfo};
 use libfdt::{Fdt, FdtNode};
-use log::{debug, error, info, trace, warn};
+use log::{debug, error, info, log_enabled, trace, warn, Level};
 use pvmfw_avb::verify_payload;
 use pvmfw_avb::Capability;
 use pvmfw_avb::DebugLevel;
 use pvmfw_embedded_key::PUBLIC_KEY;
+use virtio_drivers::transport::DeviceType;
 use vmbase::heap;
 use vmbase::memory::flush;
 use vmbase::memory::MEMORY;
@@ -69,6 +77,10 @@ fn main(
     mut debug_policy: Option<&[u8]>,
 ) -> Result<Range<usize>, RebootReason> {
     info!("pVM firmware");
+    // The timeline only feeds debug logs so skip reading the counter when they are disabled, i.e.
+    // unless pvmfw is built with the debug_logs feature.
+    let mut timeline =
+        log_enabled!(Level::Debug).then(|| BootTimeline::new(helpers::cycle_counter()));
     debug!("FDT: {:?}", fdt.as_ptr());
     debug!("Signed kernel: {:?} ({:#x} bytes)", signed_kernel.as_ptr(), signed_kernel.len());
     debug!("AVB public key: addr={:?}, size={:#x} ({1})", PUBLIC_KEY.as_ptr(), PUBLIC_KEY.len());
@@ -90,6 +102,7 @@ fn main(
         error!("{e}");
         RebootReason::InvalidBcc
     })?;
+    record_milestone(&mut timeline, "BCC parsed");
 
     // The bootloader should never pass us a debug policy when the boot is secure (the bootloader
     // is locked). If it gets it wrong, disregard it & log it, to avoid it causing problems.
@@ -98,6 +111,8 @@ fn main(
         debug_policy = None;
     }
 
+    let strict_boot = true;
+
     // Set up PCI bus for VirtIO devices.
     let pci_info = PciInfo::from_fdt(fdt).map_err(handle_pci_error)?;
     debug!("PCI: {:#x?}", pci_info);
@@ -105,11 +120,23 @@ fn main(
         error!("Failed to initialize PCI: {e}");
         RebootReason::InternalError
     })?;
+    let virtio_devices = virtio::find_virtio_devices(&mut pci_root);
+    debug!("VirtIO devices: {:x?}", virtio_devices);
+    if !virtio_devices.iter().any(|d| d.device_type == DeviceType::Block) {
+        warn!("No VirtIO block device found");
+    }
+    let vsock_bar = virtio::find_vsock_device(&virtio_devices, strict_boot).map_err(|e| {
+        error!("{e}");
+        RebootReason::InternalError
+    })?;
+    debug!("virtio-vsock BAR: {vsock_bar:#x?}");
+    record_milestone(&mut timeline, "PCI probed");
 
     let verified_boot_data = verify_payload(signed_kernel, ramdisk, PUBLIC_KEY).map_err(|e| {
         error!("Failed to verify the payload: {e}");
         RebootReason::PayloadVerificationError
     })?;
+    record_milestone(&mut timeline, "AVB verified");
     let debuggable = verified_boot_data.debug_level != DebugLevel::None;
     if debuggable {
         info!("Successfully verified a debuggable payload.");
@@ -181,6 +208,7 @@ fn main(
             )?;
             (true, salt)
         };
+        debug!("New instance: {new_instance} (host: {:?})", fdt_util::is_new_instance(fdt));
         (new_instance, salt)
     };
     trace!("Got salt for instance: {salt:x?}");
@@ -213,12 +241,12 @@ fn main(
             RebootReason::SecretDerivationError
         })?;
     flush(next_bcc);
+    record_milestone(&mut timeline, "DICE derived");
 
     let kaslr_seed = u64::from_ne_bytes(rand::random_array().map_err(|e| {
         error!("Failed to generated guest KASLR seed: {e}");
         RebootReason::InternalError
     })?);
-    let strict_boot = true;
     modify_for_next_stage(
         fdt,
         next_bcc,
@@ -232,7 +260,18 @@ fn main(
         error!("Failed to configure device tree: {e}");
         RebootReason::InternalError
     })?;
+    record_milestone(&mut timeline, "DT patched");
+    // Skip walking the DT when its dump would be filtered out anyway.
+    if debuggable && log_enabled!(Level::Debug) {
+        if let Err(e) = fdt_util::dump_fdt(fdt) {
+            warn!("Failed to dump the device tree: {e}");
+        }
+    }
 
+    debug!("Faults handled during boot: {:?}", fault_stats::fault_stats());
+    if let Some(timeline) = &timeline {
+        log_boot_timeline(timeline);
+    }
     info!("Starting payload...");
 
     let bcc_range = {
@@ -243,6 +282,22 @@ fn main(
     Ok(bcc_range)
 }
 
+fn record_milestone(timeline: &mut Option<BootTimeline>, name: &'static str) {
+    if let Some(timeline) = timeline {
+        timeline.record(name, helpers::cycle_counter());
+    }
+}
+
+fn log_boot_timeline(timeline: &BootTimeline) {
+    let frequency = helpers::cycle_counter_frequency();
+    for (name, ticks) in timeline.deltas() {
+        match ticks_to_us(ticks, frequency) {
+            Some(us) => debug!("Boot timeline: {name}: +{us}us"),
+            None => debug!("Boot timeline: {name}: +{ticks} ticks"),
+        }
+    }
+}
+
 fn check_dice_measurements_match_entry(
     dice_inputs: &PartialInputs,
     entry: &EntryBody,
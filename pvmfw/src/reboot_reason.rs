@@ -0,0 +1,91 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Reasons for pvmfw to abort the boot and reboot the VM.
+
+#[cfg(test)]
+extern crate alloc;
+
+use core::fmt;
+
+#[derive(Debug, Clone)]
+pub enum RebootReason {
+    /// A malformed BCC was received.
+    InvalidBcc,
+    /// An invalid configuration was appended to pvmfw.
+    InvalidConfig,
+    /// An unexpected internal error happened.
+    InternalError,
+    /// The provided FDT was invalid.
+    InvalidFdt,
+    /// The provided payload was invalid.
+    InvalidPayload,
+    /// The provided ramdisk was invalid.
+    InvalidRamdisk,
+    /// Failed to verify the payload.
+    PayloadVerificationError,
+    /// DICE layering process failed.
+    SecretDerivationError,
+    /// The FDT, kernel, or ramdisk regions overlap.
+    OverlappingRegions,
+    /// The FDT, kernel, and ramdisk regions don't fit in the guest memory.
+    PayloadTooLarge,
+}
+
+impl fmt::Display for RebootReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Self::InvalidBcc => "Invalid BCC",
+            Self::InvalidConfig => "Invalid appended configuration",
+            Self::InternalError => "Internal error",
+            Self::InvalidFdt => "Invalid FDT",
+            Self::InvalidPayload => "Invalid payload",
+            Self::InvalidRamdisk => "Invalid ramdisk",
+            Self::PayloadVerificationError => "Payload verification failed",
+            Self::SecretDerivationError => "Secret derivation failed",
+            Self::OverlappingRegions => "Overlapping FDT, kernel, or ramdisk regions",
+            Self::PayloadTooLarge => "FDT, kernel, and ramdisk don't fit in memory",
+        };
+        f.write_str(reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn reboot_reasons_have_distinct_messages() {
+        let reasons = [
+            RebootReason::InvalidBcc,
+            RebootReason::InvalidConfig,
+            RebootReason::InternalError,
+            RebootReason::InvalidFdt,
+            RebootReason::InvalidPayload,
+            RebootReason::InvalidRamdisk,
+            RebootReason::PayloadVerificationError,
+            RebootReason::SecretDerivationError,
+            RebootReason::OverlappingRegions,
+            RebootReason::PayloadTooLarge,
+        ];
+        let messages: Vec<_> = reasons.iter().map(|r| format!("{r}")).collect();
+
+        assert!(messages.iter().all(|m| !m.is_empty()));
+        for (i, m) in messages.iter().enumerate() {
+            assert!(!messages[i + 1..].contains(m), "{m:?} is not unique");
+        }
+    }
+}
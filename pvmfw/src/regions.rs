@@ -0,0 +1,94 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Checks of the memory regions holding the FDT, kernel, and ramdisk.
+
+use core::ops::Range;
+
+/// Returns the names of the first two of the given regions found to overlap, if any.
+pub fn find_overlap<'a>(
+    regions: &[(&'a str, Option<&Range<usize>>)],
+) -> Option<(&'a str, &'a str)> {
+    let regions = regions.iter().filter_map(|(name, range)| Some((*name, (*range)?)));
+    for (i, (name, range)) in regions.clone().enumerate() {
+        let overlapping = regions.clone().skip(i + 1).find(|(_, other)| overlaps(range, other));
+        if let Some((other_name, _)) = overlapping {
+            return Some((name, other_name));
+        }
+    }
+    None
+}
+
+/// Returns the combined size of the given regions, or `None` if it overflows.
+pub fn combined_size(regions: &[(&str, Option<&Range<usize>>)]) -> Option<usize> {
+    regions
+        .iter()
+        .filter_map(|(_, range)| *range)
+        .try_fold(0_usize, |total, range| total.checked_add(range.len()))
+}
+
+/// Same as `RangeExt::overlaps()`, as vmbase can't be used by host tests.
+fn overlaps(a: &Range<usize>, b: &Range<usize>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn disjoint_regions_have_no_overlap() {
+        let fdt = 0x8000_0000..0x8020_0000;
+        let kernel = 0x8020_0000..0x8100_0000;
+        let ramdisk = 0x8200_0000..0x8300_0000;
+
+        let regions = [("FDT", Some(&fdt)), ("kernel", Some(&kernel)), ("ramdisk", Some(&ramdisk))];
+        assert_eq!(find_overlap(&regions), None);
+        let regions = [("FDT", Some(&fdt)), ("kernel", Some(&kernel)), ("ramdisk", None)];
+        assert_eq!(find_overlap(&regions), None);
+    }
+
+    #[test]
+    fn overlapping_regions_are_detected() {
+        let fdt = 0x8000_0000..0x8020_0000;
+        let kernel = 0x8020_0000..0x8100_0000;
+        let ramdisk = 0x80ff_f000..0x8300_0000;
+
+        let regions = [("FDT", Some(&fdt)), ("kernel", Some(&kernel)), ("ramdisk", Some(&ramdisk))];
+        assert_eq!(find_overlap(&regions), Some(("kernel", "ramdisk")));
+        let regions = [("FDT", Some(&fdt)), ("kernel", Some(&fdt)), ("ramdisk", None)];
+        assert_eq!(find_overlap(&regions), Some(("FDT", "kernel")));
+    }
+
+    #[test]
+    fn combined_size_adds_present_regions() {
+        let fdt = 0x8000_0000..0x8020_0000;
+        let kernel = 0x8020_0000..0x8100_0000;
+        let ramdisk = 0x8200_0000..0x8300_0000;
+
+        let regions = [("FDT", Some(&fdt)), ("kernel", Some(&kernel)), ("ramdisk", Some(&ramdisk))];
+        assert_eq!(combined_size(&regions), Some(0x200_0000));
+        let regions = [("FDT", Some(&fdt)), ("kernel", Some(&kernel)), ("ramdisk", None)];
+        assert_eq!(combined_size(&regions), Some(0x100_0000));
+    }
+
+    #[test]
+    fn combined_size_overflow() {
+        let fdt = 0x8000_0000..0x8020_0000;
+        let huge = 0..usize::MAX;
+
+        let regions = [("FDT", Some(&fdt)), ("kernel", Some(&huge))];
+        assert_eq!(combined_size(&regions), None);
+    }
+}
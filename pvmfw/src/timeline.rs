@@ -0,0 +1,95 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Record of the time taken by the main stages of pvmfw, for boot time tuning.
+//! Declared in a separate lib for adding unit tests, which requires libstd.
+
+/// Maximum number of milestones that can be recorded.
+const MAX_MILESTONES: usize = 8;
+
+/// Timestamps of boot milestones, in ticks of a monotonic counter.
+#[derive(Debug, Default)]
+pub struct BootTimeline {
+    start: u64,
+    milestones: [(&'static str, u64); MAX_MILESTONES],
+    len: usize,
+}
+
+impl BootTimeline {
+    /// Creates a timeline starting at `start` ticks.
+    pub fn new(start: u64) -> Self {
+        Self { start, ..Default::default() }
+    }
+
+    /// Records that the milestone `name` was reached at `ticks`.
+    ///
+    /// Milestones recorded once the timeline is full are dropped.
+    pub fn record(&mut self, name: &'static str, ticks: u64) {
+        if let Some(milestone) = self.milestones.get_mut(self.len) {
+            *milestone = (name, ticks);
+            self.len += 1;
+        }
+    }
+
+    /// Returns the recorded milestones with the ticks elapsed since the previous one.
+    pub fn deltas(&self) -> impl Iterator<Item = (&'static str, u64)> + '_ {
+        let previous = [self.start].into_iter().chain(self.milestones.iter().map(|(_, t)| *t));
+        self.milestones[..self.len]
+            .iter()
+            .zip(previous)
+            .map(|((name, ticks), previous)| (*name, ticks.saturating_sub(previous)))
+    }
+}
+
+/// Converts a number of ticks of a counter running at `frequency` Hz to microseconds.
+pub fn ticks_to_us(ticks: u64, frequency: u64) -> Option<u64> {
+    if frequency == 0 {
+        return None;
+    }
+    (u128::from(ticks) * 1_000_000 / u128::from(frequency)).try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deltas_are_relative_to_previous_milestone() {
+        let mut timeline = BootTimeline::new(100);
+        timeline.record("a", 150);
+        timeline.record("b", 400);
+
+        let deltas: Vec<_> = timeline.deltas().collect();
+
+        assert_eq!(deltas, [("a", 50), ("b", 250)]);
+    }
+
+    #[test]
+    fn milestones_beyond_capacity_are_dropped() {
+        let mut timeline = BootTimeline::new(0);
+        for i in 0..(MAX_MILESTONES as u64 + 2) {
+            timeline.record("m", i + 1);
+        }
+
+        assert_eq!(timeline.deltas().count(), MAX_MILESTONES);
+        assert!(timeline.deltas().all(|(_, delta)| delta == 1));
+    }
+
+    #[test]
+    fn ticks_to_us_handles_frequency() {
+        assert_eq!(ticks_to_us(62_500_000, 62_500_000), Some(1_000_000));
+        assert_eq!(ticks_to_us(3, 1_000_000), Some(3));
+        assert_eq!(ticks_to_us(1, 0), None);
+    }
+}
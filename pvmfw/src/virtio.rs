@@ -0,0 +1,171 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Discovery of the VirtIO devices exposed to pvmfw on the PCI bus.
+//! Declared in a separate lib for adding unit tests, which requires libstd.
+
+#[cfg(test)]
+extern crate alloc;
+
+use alloc::vec::Vec;
+use core::fmt;
+use log::debug;
+use virtio_drivers::transport::pci::bus::{DeviceFunction, DeviceFunctionInfo, PciRoot};
+use virtio_drivers::transport::pci::virtio_device_type;
+use virtio_drivers::transport::DeviceType;
+
+/// Number of BARs of a PCI type 0 header.
+const MAX_BARS: u8 = 6;
+
+/// A VirtIO device found on the PCI bus.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VirtIODevice {
+    /// Type of the VirtIO device.
+    pub device_type: DeviceType,
+    /// Address of the first memory BAR of the device, if it has any.
+    pub bar_address: Option<u64>,
+}
+
+/// Returns the VirtIO devices found on bus 0 of `pci_root`.
+pub fn find_virtio_devices(pci_root: &mut PciRoot) -> Vec<VirtIODevice> {
+    let bus = pci_root.enumerate_bus(0);
+    collect_virtio_devices(bus, |device_function| first_memory_bar(pci_root, device_function))
+}
+
+/// Error returned when a required virtio-vsock device is missing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MissingVsockDevice;
+
+impl fmt::Display for MissingVsockDevice {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "No virtio-vsock device with a memory BAR was found")
+    }
+}
+
+/// Returns the BAR address of the first virtio-vsock device in `devices` that has one.
+///
+/// If there is none, fails when `required` is set (e.g. on strict boot) and returns `None`
+/// otherwise.
+pub fn find_vsock_device(
+    devices: &[VirtIODevice],
+    required: bool,
+) -> Result<Option<u64>, MissingVsockDevice> {
+    let bar_address = devices
+        .iter()
+        .filter(|device| device.device_type == DeviceType::Socket)
+        .find_map(|device| device.bar_address);
+    match bar_address {
+        None if required => Err(MissingVsockDevice),
+        bar_address => Ok(bar_address),
+    }
+}
+
+fn collect_virtio_devices(
+    devices: impl IntoIterator<Item = (DeviceFunction, DeviceFunctionInfo)>,
+    mut bar_address: impl FnMut(DeviceFunction) -> Option<u64>,
+) -> Vec<VirtIODevice> {
+    devices
+        .into_iter()
+        .filter_map(|(device_function, info)| {
+            let device_type = virtio_device_type(&info)?;
+            let device = VirtIODevice { device_type, bar_address: bar_address(device_function) };
+            debug!("Found VirtIO device at {device_function}: {device:x?}");
+            Some(device)
+        })
+        .collect()
+}
+
+fn first_memory_bar(pci_root: &mut PciRoot, device_function: DeviceFunction) -> Option<u64> {
+    let mut bar_index = 0;
+    while bar_index < MAX_BARS {
+        let info = pci_root.bar_info(device_function, bar_index).ok()?;
+        if let Some((address, _size)) = info.memory_address_size() {
+            return Some(address);
+        }
+        bar_index += if info.takes_two_entries() { 2 } else { 1 };
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use virtio_drivers::transport::pci::bus::HeaderType;
+
+    const VIRTIO_VENDOR_ID: u16 = 0x1af4;
+    const VIRTIO_BLOCK_DEVICE_ID: u16 = 0x1042;
+    const VIRTIO_SOCKET_DEVICE_ID: u16 = 0x1053;
+
+    fn device(device: u8, vendor_id: u16, device_id: u16) -> (DeviceFunction, DeviceFunctionInfo) {
+        let device_function = DeviceFunction { bus: 0, device, function: 0 };
+        let info = DeviceFunctionInfo {
+            vendor_id,
+            device_id,
+            class: 0,
+            subclass: 0,
+            prog_if: 0,
+            revision: 1,
+            header_type: HeaderType::Standard,
+        };
+        (device_function, info)
+    }
+
+    #[test]
+    fn lists_virtio_devices_of_mock_pci_root() {
+        let pci_root = [
+            device(1, VIRTIO_VENDOR_ID, VIRTIO_BLOCK_DEVICE_ID),
+            device(2, 0x8086, 0x1237),
+            device(3, VIRTIO_VENDOR_ID, VIRTIO_SOCKET_DEVICE_ID),
+        ];
+        let bar_address = |device_function: DeviceFunction| match device_function.device {
+            1 => Some(0x8000_0000),
+            3 => None,
+            _ => panic!("Unexpected BAR lookup for {device_function}"),
+        };
+
+        assert_eq!(
+            collect_virtio_devices(pci_root, bar_address),
+            [
+                VirtIODevice { device_type: DeviceType::Block, bar_address: Some(0x8000_0000) },
+                VirtIODevice { device_type: DeviceType::Socket, bar_address: None },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_vsock_device_of_mock_pci_root() {
+        let pci_root = [
+            device(1, VIRTIO_VENDOR_ID, VIRTIO_BLOCK_DEVICE_ID),
+            device(2, VIRTIO_VENDOR_ID, VIRTIO_SOCKET_DEVICE_ID),
+        ];
+        let bar_address = |device_function: DeviceFunction| match device_function.device {
+            1 => Some(0x8000_0000),
+            2 => Some(0x8000_4000),
+            _ => panic!("Unexpected BAR lookup for {device_function}"),
+        };
+        let devices = collect_virtio_devices(pci_root, bar_address);
+
+        assert_eq!(find_vsock_device(&devices, true), Ok(Some(0x8000_4000)));
+        assert_eq!(find_vsock_device(&devices, false), Ok(Some(0x8000_4000)));
+    }
+
+    #[test]
+    fn missing_vsock_device_is_only_an_error_when_required() {
+        let pci_root = [device(1, VIRTIO_VENDOR_ID, VIRTIO_BLOCK_DEVICE_ID)];
+        let devices = collect_virtio_devices(pci_root, |_| Some(0x8000_0000));
+
+        assert_eq!(find_vsock_device(&devices, true), Err(MissingVsockDevice));
+        assert_eq!(find_vsock_device(&devices, false), Ok(None));
+    }
+}
@@ -0,0 +1,167 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Support for `vm run --keep`, which leaves the VM running once `vm` has exited.
+//!
+//! A VM is killed as soon as the last reference to it is dropped, and virtmgr, which manages it,
+//! exits with its client. So the VM is held by a child process, which outlives the process that
+//! the user is waiting for and which must be forked before Binder is used.
+
+use anyhow::{Context, Error};
+use std::fs::File;
+use std::io::{self, Read, Write};
+use std::os::unix::io::FromRawFd;
+use std::ptr;
+
+/// Process returning from [`fork_keeper`].
+pub enum Keeper {
+    /// The original process, which can exit once the VM has started.
+    Parent {
+        /// The process holding the VM.
+        child: libc::pid_t,
+        /// Whether the child reported that the VM started.
+        started: bool,
+    },
+    /// The process which must run the VM, hold it, and notify the parent once it has started.
+    Child(StartedNotifier),
+}
+
+/// Lets the child of [`fork_keeper`] report that the VM has started.
+///
+/// Dropping it without calling [`StartedNotifier::notify`] reports a failure.
+pub struct StartedNotifier(File);
+
+impl StartedNotifier {
+    /// Reports to the parent that the VM has started, so that it can exit.
+    pub fn notify(mut self) -> io::Result<()> {
+        self.0.write_all(&[1])
+    }
+}
+
+/// Forks a child process, in its own session, to hold the VM.
+///
+/// In the parent, this returns once the child has notified that the VM started or has failed.
+pub fn fork_keeper() -> Result<Keeper, Error> {
+    let (mut read_end, write_end) = pipe().context("Failed to create pipe")?;
+    // SAFETY: fork() has no memory safety requirement, and the child only uses its own copy of
+    // the memory of the parent.
+    match unsafe { libc::fork() } {
+        -1 => Err(io::Error::last_os_error()).context("Failed to fork"),
+        0 => {
+            drop(read_end);
+            // Leave the session of the parent so that the VM isn't killed along with it, e.g. by
+            // the SIGHUP sent when its terminal is closed.
+            // SAFETY: setsid() has no memory safety requirement.
+            if unsafe { libc::setsid() } < 0 {
+                return Err(io::Error::last_os_error()).context("Failed to create a new session");
+            }
+            Ok(Keeper::Child(StartedNotifier(write_end)))
+        }
+        child => {
+            drop(write_end);
+            let mut buf = [0];
+            // The pipe is closed without any data if the child fails or exits before the VM starts.
+            let started = read_end.read(&mut buf).context("Failed to wait for the VM")? == 1;
+            Ok(Keeper::Parent { child, started })
+        }
+    }
+}
+
+/// Waits for the given child process to exit.
+pub fn wait_for_child(child: libc::pid_t) -> io::Result<()> {
+    // SAFETY: waitpid() has no memory safety requirement and only accepts a null status pointer.
+    if unsafe { libc::waitpid(child, ptr::null_mut(), 0) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+pub(crate) fn pipe() -> io::Result<(File, File)> {
+    let mut fds = [0; 2];
+    // The write end mustn't leak into the processes spawned by the child, e.g. virtmgr, or the
+    // parent would never see it closed.
+    // SAFETY: pipe2() only writes two file descriptors to the given array.
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: Both file descriptors were just created by pipe2() and are owned here.
+    Ok(unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `f` in the child of `fork_keeper()`, which never returns to the test harness.
+    fn fork_keeper_running(f: impl FnOnce(StartedNotifier)) -> (libc::pid_t, bool) {
+        match fork_keeper().unwrap() {
+            Keeper::Parent { child, started } => (child, started),
+            Keeper::Child(notifier) => {
+                f(notifier);
+                // SAFETY: _exit() doesn't return, so nothing can use the state of the process.
+                unsafe { libc::_exit(0) }
+            }
+        }
+    }
+
+    /// Notifies the parent then holds on, like `vm run --keep` does with the VM, until killed.
+    fn notify_and_hold(notifier: StartedNotifier) {
+        let _ = notifier.notify();
+        loop {
+            // SAFETY: pause() has no memory safety requirement.
+            unsafe { libc::pause() };
+        }
+    }
+
+    fn is_alive(pid: libc::pid_t) -> bool {
+        // SAFETY: kill() has no memory safety requirement, and signal 0 is only an existence check.
+        unsafe { libc::kill(pid, 0) == 0 }
+    }
+
+    fn kill_and_reap(pid: libc::pid_t) {
+        // SAFETY: kill() has no memory safety requirement.
+        unsafe { libc::kill(pid, libc::SIGKILL) };
+        wait_for_child(pid).unwrap();
+    }
+
+    #[test]
+    fn keeper_outlives_the_notification() {
+        let (child, started) = fork_keeper_running(notify_and_hold);
+
+        assert!(started);
+        // The parent could exit now, while the keeper still holds the VM.
+        assert!(is_alive(child));
+        kill_and_reap(child);
+    }
+
+    #[test]
+    fn keeper_failing_before_start_is_reported() {
+        let (child, started) = fork_keeper_running(drop);
+
+        assert!(!started);
+        wait_for_child(child).unwrap();
+    }
+
+    #[test]
+    fn keeper_is_in_its_own_session() {
+        let (child, started) = fork_keeper_running(notify_and_hold);
+
+        assert!(started);
+        // SAFETY: getsid() has no memory safety requirement.
+        let (session, own_session) = unsafe { (libc::getsid(child), libc::getsid(0)) };
+        assert_eq!(session, child);
+        assert_ne!(session, own_session);
+        kill_and_reap(child);
+    }
+}
@@ -16,22 +16,23 @@
 
 mod create_idsig;
 mod create_partition;
+mod keep;
 mod run;
 
 use android_system_virtualizationservice::aidl::android::system::virtualizationservice::{
     CpuTopology::CpuTopology, IVirtualizationService::IVirtualizationService,
     PartitionType::PartitionType, VirtualMachineAppConfig::DebugLevel::DebugLevel,
 };
-#[cfg(not(llpvm_changes))]
-use anyhow::anyhow;
-use anyhow::{Context, Error};
+use anyhow::{anyhow, bail, Context, Error};
 use binder::{ProcessState, Strong};
 use clap::{Args, Parser};
 use create_idsig::command_create_idsig;
 use create_partition::command_create_partition;
-use run::{command_run, command_run_app, command_run_microdroid};
+use keep::{fork_keeper, wait_for_child, Keeper};
+use run::{command_run, command_run_app, command_run_microdroid, command_validate, OnStarted};
 use serde::Serialize;
-use std::num::NonZeroU16;
+use std::num::{NonZeroU16, NonZeroU32};
+use std::os::unix::io::RawFd;
 use std::path::{Path, PathBuf};
 
 #[derive(Args, Default)]
@@ -106,6 +107,30 @@ pub struct DebugConfig {
     /// Note: this is only supported on Android kernels android14-5.15 and higher.
     #[arg(long)]
     gdb: Option<NonZeroU16>,
+
+    /// Start a gdb server at a well-known port and print how to connect to it.
+    /// Only allowed for debuggable VMs.
+    #[arg(long, conflicts_with = "gdb")]
+    debug_port: bool,
+}
+
+/// Port of the gdb server started with --debug-port.
+const DEFAULT_DEBUG_PORT: NonZeroU16 = match NonZeroU16::new(3456) {
+    Some(port) => port,
+    None => unreachable!(),
+};
+
+impl DebugConfig {
+    /// Returns the port at which crosvm should start a gdb server, if any.
+    fn gdb_port(&self) -> Result<Option<NonZeroU16>, Error> {
+        if !self.debug_port {
+            return Ok(self.gdb);
+        }
+        if self.debug == DebugLevel::NONE {
+            return Err(anyhow!("--debug-port is only allowed for debuggable VMs"));
+        }
+        Ok(Some(DEFAULT_DEBUG_PORT))
+    }
 }
 
 #[derive(Args, Default)]
@@ -276,6 +301,36 @@ pub struct RunCustomVmConfig {
     #[command(flatten)]
     debug: DebugConfig,
 
+    /// Number of vCPUs of the VM. Only 1 or the number of host CPUs are supported. Overrides the
+    /// CPU topology of the VM config file and of --cpu-topology.
+    #[arg(long, conflicts_with = "cpu_topology")]
+    cpus: Option<NonZeroU32>,
+
+    /// Memory size (in MiB) of the VM. Unlike --mem, it is an error to request less memory than
+    /// the `memory_mib` declared in the VM config file.
+    #[arg(long, conflicts_with = "mem")]
+    memory_mib: Option<NonZeroU32>,
+
+    /// File descriptors inherited from the caller to pass to the VM as additional read-only disk
+    /// images, e.g. `--extra-disk-fd 3 3<image.img`.
+    #[arg(long = "extra-disk-fd")]
+    extra_disk_fds: Vec<RawFd>,
+
+    /// Text to append to the kernel command line of the VM config file, e.g. debug flags.
+    #[arg(long)]
+    append: Option<String>,
+
+    /// Print the CID of the started VM as a JSON object, e.g. `{"cid":2048}`, instead of the
+    /// human-readable status line.
+    #[arg(long)]
+    json: bool,
+
+    /// Exit once the VM has started, leaving it running. A child process in its own session holds
+    /// the VM until it dies; console and log output still go to where they were directed when the
+    /// VM was created.
+    #[arg(long)]
+    keep: bool,
+
     /// Path to VM config JSON
     config: PathBuf,
 }
@@ -299,6 +354,11 @@ enum Opt {
         #[command(flatten)]
         config: RunCustomVmConfig,
     },
+    /// Check a VM config file for problems without running it
+    Validate {
+        /// Path to VM config JSON
+        config: PathBuf,
+    },
     /// List running virtual machines
     List,
     /// Print information about virtual machine support
@@ -367,6 +427,21 @@ fn main() -> Result<(), Error> {
     env_logger::init();
     let opt = Opt::parse();
 
+    // With --keep, the VM is held by a child process which outlives this one. It must be forked
+    // before Binder is used.
+    let started_notifier = match &opt {
+        Opt::Run { config } if config.keep => match fork_keeper()? {
+            Keeper::Parent { started: true, .. } => return Ok(()),
+            Keeper::Parent { child, started: false } => {
+                // Let the child report why the VM didn't start first.
+                wait_for_child(child).context("Failed to wait for the VM process")?;
+                bail!("Failed to start the VM");
+            }
+            Keeper::Child(started_notifier) => Some(started_notifier),
+        },
+        _ => None,
+    };
+
     // We need to start the thread pool for Binder to work properly, especially link_to_death.
     ProcessState::start_thread_pool();
 
@@ -375,9 +450,18 @@ fn main() -> Result<(), Error> {
             command_check_feature_enabled(&feature);
             Ok(())
         }
-        Opt::RunApp { config } => command_run_app(config),
-        Opt::RunMicrodroid { config } => command_run_microdroid(config),
-        Opt::Run { config } => command_run(config),
+        Opt::RunApp { config } => command_run_app(config).map(|_| ()),
+        Opt::RunMicrodroid { config } => command_run_microdroid(config).map(|_| ()),
+        Opt::Run { config } => {
+            let on_started = started_notifier.map(|started_notifier| -> OnStarted {
+                // Let the parent process exit, this one keeps holding the VM.
+                Box::new(|_| {
+                    started_notifier.notify().context("Failed to notify that the VM started")
+                })
+            });
+            command_run(config, on_started).map(|_| ())
+        }
+        Opt::Validate { config } => command_validate(&config),
         Opt::List => command_list(get_service()?.as_ref()),
         Opt::Info => command_info(),
         Opt::CreatePartition { path, size, partition_type } => {
@@ -460,4 +544,32 @@ mod tests {
         // Check that the command parsing has been configured in a valid way.
         Opt::command().debug_assert();
     }
+
+    #[test]
+    fn run_parses_keep_flag() {
+        let Opt::Run { config } = Opt::parse_from(["vm", "run", "--keep", "config.json"]) else {
+            panic!("Expected the run subcommand");
+        };
+        assert!(config.keep);
+        assert!(!config.json);
+    }
+
+    #[test]
+    fn debug_port_sets_default_gdb_port() {
+        let Opt::Run { config } = Opt::parse_from(["vm", "run", "--debug-port", "config.json"])
+        else {
+            panic!("Expected the run subcommand");
+        };
+        assert_eq!(config.debug.gdb_port().unwrap(), Some(DEFAULT_DEBUG_PORT));
+    }
+
+    #[test]
+    fn debug_port_requires_debuggable_vm() {
+        let Opt::Run { config } =
+            Opt::parse_from(["vm", "run", "--debug-port", "--debug", "none", "config.json"])
+        else {
+            panic!("Expected the run subcommand");
+        };
+        assert!(config.debug.gdb_port().is_err());
+    }
 }
@@ -15,8 +15,10 @@
 //! Command to run a VM.
 
 use crate::create_partition::command_create_partition;
-use crate::{get_service, RunAppConfig, RunCustomVmConfig, RunMicrodroidConfig};
+use crate::keep::pipe;
+use crate::{get_service, DebugConfig, RunAppConfig, RunCustomVmConfig, RunMicrodroidConfig};
 use android_system_virtualizationservice::aidl::android::system::virtualizationservice::{
+    DiskImage::DiskImage,
     IVirtualizationService::IVirtualizationService,
     PartitionType::PartitionType,
     VirtualMachineAppConfig::{
@@ -36,14 +38,39 @@ use std::fs;
 use std::fs::File;
 use std::io;
 use std::io::{Read, Write};
-use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::num::{NonZeroU16, NonZeroU32};
+use std::os::unix::io::{AsRawFd, FromRawFd, RawFd};
 use std::path::{Path, PathBuf};
-use vmclient::{ErrorCode, VmInstance};
-use vmconfig::{get_debug_level, open_parcel_file, VmConfig};
+use vmclient::{DeathReason, ErrorCode, VmInstance};
+use vmconfig::{get_debug_level, open_parcel_file, ConfigProblems, VmConfig};
 use zip::ZipArchive;
 
+/// Outcome of a `vm run*` command, for callers which need more than the printed status.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RunOutcome {
+    /// The CID assigned to the VM.
+    pub cid: i32,
+    /// Why the VM died, or `None` while it is still running.
+    pub death_reason: Option<DeathReason>,
+}
+
+impl RunOutcome {
+    /// Returns the outcome of the VM with the given CID, which has just started.
+    fn started(cid: i32) -> Self {
+        Self { cid, death_reason: None }
+    }
+
+    /// Returns this outcome, updated once the VM has died.
+    fn died(self, death_reason: DeathReason) -> Self {
+        Self { death_reason: Some(death_reason), ..self }
+    }
+}
+
+/// Called with the outcome of a VM once it has started, while `run()` keeps holding it.
+pub type OnStarted = Box<dyn FnOnce(&RunOutcome) -> Result<(), Error>>;
+
 /// Run a VM from the given APK, idsig, and config.
-pub fn command_run_app(config: RunAppConfig) -> Result<(), Error> {
+pub fn command_run_app(config: RunAppConfig) -> Result<RunOutcome, Error> {
     let service = get_service()?;
     let apk = File::open(&config.apk).context("Failed to open APK file")?;
 
@@ -148,8 +175,13 @@ pub fn command_run_app(config: RunAppConfig) -> Result<(), Error> {
 
     let payload_config_str = format!("{:?}!{:?}", config.apk, payload);
 
+    let gdb_port = config.debug.gdb_port()?;
+    if let Some(port) = gdb_port {
+        print_gdb_connect_command(port);
+    }
+
     let custom_config = CustomConfig {
-        gdbPort: config.debug.gdb.map(u16::from).unwrap_or(0) as i32, // 0 means no gdb
+        gdbPort: gdb_port.map(u16::from).unwrap_or(0) as i32, // 0 means no gdb
         vendorImage: vendor,
         devices: config
             .microdroid
@@ -181,14 +213,12 @@ pub fn command_run_app(config: RunAppConfig) -> Result<(), Error> {
         hugePages: config.common.hugepages,
         boostUclamp: config.common.boost_uclamp,
     });
-    run(
-        service.as_ref(),
-        &vm_config,
-        &payload_config_str,
-        config.debug.console.as_ref().map(|p| p.as_ref()),
-        config.debug.console_in.as_ref().map(|p| p.as_ref()),
-        config.debug.log.as_ref().map(|p| p.as_ref()),
-    )
+    run(service.as_ref(), &vm_config, RunOptions::new(&payload_config_str, &config.debug))
+}
+
+fn print_gdb_connect_command(port: NonZeroU16) {
+    eprintln!("gdb server port: {port}. To connect from the host, run:");
+    eprintln!("  adb forward tcp:{port} tcp:{port} && gdb -ex 'target remote :{port}'");
 }
 
 fn find_empty_payload_apk_path() -> Result<PathBuf, Error> {
@@ -214,7 +244,7 @@ fn create_work_dir() -> Result<PathBuf, Error> {
 }
 
 /// Run a VM with Microdroid
-pub fn command_run_microdroid(config: RunMicrodroidConfig) -> Result<(), Error> {
+pub fn command_run_microdroid(config: RunMicrodroidConfig) -> Result<RunOutcome, Error> {
     let apk = find_empty_payload_apk_path()?;
     println!("found path {}", apk.display());
 
@@ -244,10 +274,28 @@ pub fn command_run_microdroid(config: RunMicrodroidConfig) -> Result<(), Error>
 }
 
 /// Run a VM from the given configuration file.
-pub fn command_run(config: RunCustomVmConfig) -> Result<(), Error> {
+///
+/// `on_started` is called once the VM has started, e.g. to let the parent of a `--keep` process
+/// exit.
+pub fn command_run(
+    config: RunCustomVmConfig,
+    on_started: Option<OnStarted>,
+) -> Result<RunOutcome, Error> {
     let config_file = File::open(&config.config).context("Failed to open config file")?;
-    let mut vm_config =
-        VmConfig::load(&config_file).context("Failed to parse config file")?.to_parcelable()?;
+    let mut vm_config = VmConfig::load(&config_file).context("Failed to parse config file")?;
+    if let Some(cpus) = config.cpus {
+        set_cpus(&mut vm_config, cpus, host_cpus()?)?;
+    }
+    if let Some(memory_mib) = config.memory_mib {
+        set_memory_mib(&mut vm_config, memory_mib)?;
+    }
+    if let Some(append) = &config.append {
+        append_params(&mut vm_config, append);
+    }
+    let mut vm_config = vm_config.to_parcelable()?;
+    for image in parcel_fds_from_raw(&config.extra_disk_fds)? {
+        vm_config.disks.push(DiskImage { image: Some(image), writable: false, partitions: vec![] });
+    }
     if let Some(mem) = config.common.mem {
         vm_config.memoryMib = mem as i32;
     }
@@ -256,20 +304,106 @@ pub fn command_run(config: RunCustomVmConfig) -> Result<(), Error> {
     } else {
         vm_config.name = String::from("VmRun");
     }
-    if let Some(gdb) = config.debug.gdb {
+    if let Some(gdb) = config.debug.gdb_port()? {
         vm_config.gdbPort = gdb.get() as i32;
+        print_gdb_connect_command(gdb);
+    }
+    if config.cpus.is_none() {
+        vm_config.cpuTopology = config.common.cpu_topology;
     }
-    vm_config.cpuTopology = config.common.cpu_topology;
     vm_config.hugePages = config.common.hugepages;
     vm_config.boostUclamp = config.common.boost_uclamp;
-    run(
-        get_service()?.as_ref(),
-        &VirtualMachineConfig::RawConfig(vm_config),
-        &format!("{:?}", &config.config),
-        config.debug.console.as_ref().map(|p| p.as_ref()),
-        config.debug.console_in.as_ref().map(|p| p.as_ref()),
-        config.debug.log.as_ref().map(|p| p.as_ref()),
-    )
+    let payload_config = format!("{:?}", &config.config);
+    let options = RunOptions {
+        json_output: config.json,
+        on_started,
+        ..RunOptions::new(&payload_config, &config.debug)
+    };
+    run(get_service()?.as_ref(), &VirtualMachineConfig::RawConfig(vm_config), options)
+}
+
+/// Checks a VM config file without running it, printing every problem found rather than just the
+/// first one.
+pub fn command_validate(config_path: &Path) -> Result<(), Error> {
+    let config_file = File::open(config_path).context("Failed to open config file")?;
+    let vm_config: VmConfig = serde_json::from_reader(io::BufReader::new(config_file))
+        .context("Failed to parse config file")?;
+    let problems = config_problems(&vm_config);
+    if problems.is_empty() {
+        println!("{config_path:?} is valid");
+        return Ok(());
+    }
+    for problem in &problems {
+        eprintln!("{config_path:?}: {problem:#}");
+    }
+    bail!("Found {} problem(s) in {config_path:?}", problems.len())
+}
+
+/// Returns all the problems that would prevent the given config from being turned into a
+/// parcelable config.
+fn config_problems(vm_config: &VmConfig) -> Vec<Error> {
+    let mut problems: Vec<_> = vm_config.validate().err().into_iter().collect();
+    if let Err(e) = vm_config.to_parcelable() {
+        match e.downcast::<ConfigProblems>() {
+            Ok(ConfigProblems(errors)) => problems.extend(errors),
+            Err(e) => problems.push(e),
+        }
+    }
+    problems
+}
+
+/// Returns the number of CPUs configured on the host.
+fn host_cpus() -> Result<NonZeroU32, Error> {
+    // SAFETY: sysconf() has no side effects and only reads the given name.
+    let cpus = unsafe { libc::sysconf(libc::_SC_NPROCESSORS_CONF) };
+    u32::try_from(cpus)
+        .ok()
+        .and_then(NonZeroU32::new)
+        .ok_or_else(|| anyhow!("Failed to get the number of host CPUs: {cpus}"))
+}
+
+/// Overrides the CPU topology of `vm_config` to give the VM `cpus` vCPUs.
+///
+/// As the topology can only describe a single vCPU or as many vCPUs as the host has CPUs, any
+/// other count is rejected.
+fn set_cpus(
+    vm_config: &mut VmConfig,
+    cpus: NonZeroU32,
+    host_cpus: NonZeroU32,
+) -> Result<(), Error> {
+    if cpus > host_cpus {
+        bail!("Requested {cpus} vCPUs but the host only has {host_cpus} CPUs");
+    }
+    let cpu_topology = if cpus.get() == 1 {
+        "one_cpu"
+    } else if cpus == host_cpus {
+        "match_host"
+    } else {
+        bail!("Unsupported number of vCPUs {cpus}: only 1 or {host_cpus} are supported");
+    };
+    vm_config.cpu_topology = Some(cpu_topology.to_owned());
+    Ok(())
+}
+
+/// Overrides the amount of memory of `vm_config`, which must not be less than the amount it
+/// declares.
+fn set_memory_mib(vm_config: &mut VmConfig, memory_mib: NonZeroU32) -> Result<(), Error> {
+    if let Some(declared) = vm_config.memory_mib {
+        if memory_mib < declared {
+            bail!("Requested {memory_mib} MiB of memory but the VM config requires {declared} MiB");
+        }
+    }
+    vm_config.memory_mib = Some(memory_mib);
+    Ok(())
+}
+
+fn append_params(vm_config: &mut VmConfig, append: &str) {
+    vm_config.params = match vm_config.params.take() {
+        Some(params) if !params.trim_end().is_empty() => {
+            Some(format!("{} {}", params.trim_end(), append))
+        }
+        _ => Some(append.to_owned()),
+    };
 }
 
 fn state_to_str(vm_state: VirtualMachineState) -> &'static str {
@@ -284,36 +418,61 @@ fn state_to_str(vm_state: VirtualMachineState) -> &'static str {
     }
 }
 
+/// How `run()` sets up the VM and reports on it.
+struct RunOptions<'a> {
+    /// Description of what the VM runs, for the status message.
+    payload_config: &'a str,
+    console_out_path: Option<&'a Path>,
+    console_in_path: Option<&'a Path>,
+    log_path: Option<&'a Path>,
+    /// Whether to print the CID as JSON instead of the status message.
+    json_output: bool,
+    /// Called once the VM has started.
+    on_started: Option<OnStarted>,
+}
+
+impl<'a> RunOptions<'a> {
+    /// Returns the options printing the status message, with the console and log set up as
+    /// requested by `debug`.
+    fn new(payload_config: &'a str, debug: &'a DebugConfig) -> Self {
+        Self {
+            payload_config,
+            console_out_path: debug.console.as_deref(),
+            console_in_path: debug.console_in.as_deref(),
+            log_path: debug.log.as_deref(),
+            json_output: false,
+            on_started: None,
+        }
+    }
+}
+
 fn run(
     service: &dyn IVirtualizationService,
     config: &VirtualMachineConfig,
-    payload_config: &str,
-    console_out_path: Option<&Path>,
-    console_in_path: Option<&Path>,
-    log_path: Option<&Path>,
-) -> Result<(), Error> {
-    let console_out = if let Some(console_out_path) = console_out_path {
+    options: RunOptions,
+) -> Result<RunOutcome, Error> {
+    let console_out = if let Some(console_out_path) = options.console_out_path {
         Some(File::create(console_out_path).with_context(|| {
             format!("Failed to open console output file {:?}", console_out_path)
         })?)
     } else {
-        Some(duplicate_fd(io::stdout())?)
+        Some(duplicate_fd(io::stdout().as_raw_fd())?)
     };
     let console_in =
-        if let Some(console_in_path) = console_in_path {
+        if let Some(console_in_path) = options.console_in_path {
             Some(File::open(console_in_path).with_context(|| {
                 format!("Failed to open console input file {:?}", console_in_path)
             })?)
         } else {
-            Some(duplicate_fd(io::stdin())?)
+            Some(duplicate_fd(io::stdin().as_raw_fd())?)
         };
-    let log = if let Some(log_path) = log_path {
+    let log = if let Some(log_path) = options.log_path {
         Some(
             File::create(log_path)
                 .with_context(|| format!("Failed to open log file {:?}", log_path))?,
         )
     } else {
-        Some(duplicate_fd(io::stdout())?)
+        Some(duplicate_fd(io::stdout().as_raw_fd())?)
     };
     let callback = Box::new(Callback {});
     let vm = VmInstance::create(service, config, console_out, console_in, log, Some(callback))
@@ -322,19 +481,33 @@ fn run(
 
     let debug_level = get_debug_level(config).unwrap_or(DebugLevel::NONE);
 
-    println!(
-        "Created {} from {} with CID {}, state is {}.",
-        if debug_level == DebugLevel::FULL { "debuggable VM" } else { "VM" },
-        payload_config,
-        vm.cid(),
-        state_to_str(vm.state()?)
-    );
+    if options.json_output {
+        println!("{}", cid_json(vm.cid()));
+    } else {
+        println!(
+            "Created {} from {} with CID {}, state is {}.",
+            if debug_level == DebugLevel::FULL { "debuggable VM" } else { "VM" },
+            options.payload_config,
+            vm.cid(),
+            state_to_str(vm.state()?)
+        );
+    }
+
+    let outcome = RunOutcome::started(vm.cid());
+    if let Some(on_started) = options.on_started {
+        on_started(&outcome)?;
+    }
 
     // Wait until the VM or VirtualizationService dies. If we just returned immediately then the
     // IVirtualMachine Binder object would be dropped and the VM would be killed.
     let death_reason = vm.wait_for_death();
     println!("VM ended: {:?}", death_reason);
-    Ok(())
+    Ok(outcome.died(death_reason))
+}
+
+/// Formats the CID of a started VM for `--json`.
+fn cid_json(cid: i32) -> String {
+    serde_json::json!({ "cid": cid }).to_string()
 }
 
 fn parse_extra_apk_list(apk: &Path, config_path: &str) -> Result<Vec<PathBuf>, Error> {
@@ -365,10 +538,9 @@ impl vmclient::VmCallback for Callback {
 }
 
 /// Safely duplicate the file descriptor.
-fn duplicate_fd<T: AsRawFd>(file: T) -> io::Result<File> {
-    let fd = file.as_raw_fd();
-    // SAFETY: This just duplicates a file descriptor which we know to be valid, and we check for an
-    // an error.
+fn duplicate_fd(fd: RawFd) -> io::Result<File> {
+    // SAFETY: This just duplicates a file descriptor, which the kernel checks to be valid, and we
+    // check for an error.
     let dup_fd = unsafe { libc::dup(fd) };
     if dup_fd < 0 {
         Err(io::Error::last_os_error())
@@ -378,3 +550,221 @@ fn duplicate_fd<T: AsRawFd>(file: T) -> io::Result<File> {
         Ok(unsafe { File::from_raw_fd(dup_fd) })
     }
 }
+
+/// Creates a pipe and lends its write end to `handoff`, e.g. to pass it to a VM as its console, then
+/// returns what `handoff` returned along with the read end, from which the output can be streamed
+/// without a file on disk.
+///
+/// The write end is closed in this process once `handoff` returns, so reads hit EOF as soon as the
+/// copies made by `handoff` are closed too.
+fn console_pipe<T>(handoff: impl FnOnce(&File) -> Result<T, Error>) -> Result<(T, File), Error> {
+    let (read_end, write_end) = pipe().context("Failed to create console pipe")?;
+    let handed_off = handoff(&write_end)?;
+    drop(write_end);
+    Ok((handed_off, read_end))
+}
+
+/// Creates and starts a VM whose console output is written to a pipe, and returns it along with
+/// the read end of the pipe.
+#[allow(dead_code)] // For callers capturing the console output in-process.
+fn start_vm_with_console_pipe(
+    service: &dyn IVirtualizationService,
+    config: &VirtualMachineConfig,
+    log: Option<File>,
+) -> Result<(VmInstance, File), Error> {
+    console_pipe(|write_end| {
+        // create() closes its copy once it has been handed to the service.
+        let console_out = write_end.try_clone().context("Failed to duplicate console pipe")?;
+        let callback = Box::new(Callback {});
+        let vm = VmInstance::create(service, config, Some(console_out), None, log, Some(callback))
+            .context("Failed to create VM")?;
+        vm.start().context("Failed to start VM")?;
+        Ok(vm)
+    })
+}
+
+/// Checks that the file descriptor is open, without taking ownership of it.
+fn check_fd(fd: RawFd) -> io::Result<()> {
+    // SAFETY: F_GETFD only reads the descriptor flags, which the kernel checks to be valid, and we
+    // check for an error.
+    if unsafe { libc::fcntl(fd, libc::F_GETFD) } < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(())
+    }
+}
+
+/// Validates all the given file descriptors, then duplicates each of them into a
+/// `ParcelFileDescriptor`. Fails on the first descriptor that isn't open.
+fn parcel_fds_from_raw(fds: &[RawFd]) -> Result<Vec<ParcelFileDescriptor>, Error> {
+    for &fd in fds {
+        check_fd(fd).with_context(|| format!("Invalid fd {fd}"))?;
+    }
+    fds.iter()
+        .map(|&fd| {
+            let file = duplicate_fd(fd).with_context(|| format!("Failed to duplicate fd {fd}"))?;
+            Ok(ParcelFileDescriptor::new(file))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use android_system_virtualizationservice::aidl::android::system::virtualizationservice::CpuTopology::CpuTopology;
+
+    fn test_vm_config() -> VmConfig {
+        serde_json::from_str(r#"{ "platform_version": "~1.0" }"#).unwrap()
+    }
+
+    #[test]
+    fn cpus_override_is_reflected_in_parcelable() -> Result<(), Error> {
+        let host_cpus = NonZeroU32::new(8).unwrap();
+
+        let mut vm_config = test_vm_config();
+        vm_config.cpu_topology = Some("match_host".to_owned());
+        set_cpus(&mut vm_config, NonZeroU32::new(1).unwrap(), host_cpus)?;
+        assert_eq!(vm_config.to_parcelable()?.cpuTopology, CpuTopology::ONE_CPU);
+
+        let mut vm_config = test_vm_config();
+        set_cpus(&mut vm_config, host_cpus, host_cpus)?;
+        assert_eq!(vm_config.to_parcelable()?.cpuTopology, CpuTopology::MATCH_HOST);
+        Ok(())
+    }
+
+    #[test]
+    fn cpus_override_above_host_cpus_fails() {
+        let mut vm_config = test_vm_config();
+        let host_cpus = NonZeroU32::new(8).unwrap();
+
+        assert!(set_cpus(&mut vm_config, NonZeroU32::new(9).unwrap(), host_cpus).is_err());
+        assert!(set_cpus(&mut vm_config, NonZeroU32::new(4).unwrap(), host_cpus).is_err());
+        assert_eq!(vm_config.cpu_topology, None);
+    }
+
+    #[test]
+    fn memory_mib_override_is_reflected_in_parcelable() -> Result<(), Error> {
+        let mut vm_config = test_vm_config();
+        vm_config.memory_mib = NonZeroU32::new(256);
+        set_memory_mib(&mut vm_config, NonZeroU32::new(512).unwrap())?;
+        assert_eq!(vm_config.to_parcelable()?.memoryMib, 512);
+        Ok(())
+    }
+
+    #[test]
+    fn memory_mib_override_below_declared_fails() {
+        let mut vm_config = test_vm_config();
+        vm_config.memory_mib = NonZeroU32::new(256);
+
+        assert!(set_memory_mib(&mut vm_config, NonZeroU32::new(128).unwrap()).is_err());
+        assert_eq!(vm_config.memory_mib, NonZeroU32::new(256));
+    }
+
+    #[test]
+    fn appended_params_are_reflected_in_parcelable() -> Result<(), Error> {
+        let mut vm_config = test_vm_config();
+        vm_config.params = Some("console=hvc0 ".to_owned());
+        append_params(&mut vm_config, "loglevel=8");
+        assert_eq!(vm_config.to_parcelable()?.params.as_deref(), Some("console=hvc0 loglevel=8"));
+
+        let mut vm_config = test_vm_config();
+        append_params(&mut vm_config, "loglevel=8");
+        assert_eq!(vm_config.to_parcelable()?.params.as_deref(), Some("loglevel=8"));
+        Ok(())
+    }
+
+    #[test]
+    fn duplicated_pipe_fd_is_usable() -> Result<(), Error> {
+        let mut fds = [0; 2];
+        // SAFETY: pipe() only writes two file descriptors to the given array.
+        assert_eq!(unsafe { libc::pipe(fds.as_mut_ptr()) }, 0);
+        // SAFETY: Both file descriptors were just created by pipe() and are owned here.
+        let (mut read_end, write_end) =
+            unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+
+        let mut dup_write_end = duplicate_fd(write_end.as_raw_fd())?;
+        drop(write_end);
+        dup_write_end.write_all(b"hello")?;
+        drop(dup_write_end);
+
+        let mut buf = String::new();
+        read_end.read_to_string(&mut buf)?;
+        assert_eq!(buf, "hello");
+        Ok(())
+    }
+
+    #[test]
+    fn console_pipe_reads_bytes_written_to_the_pipe() -> Result<(), Error> {
+        // Stands for the copy of the write end held by the VM.
+        let (mut vm_console, mut read_end) = console_pipe(|write_end| Ok(write_end.try_clone()?))?;
+
+        vm_console.write_all(b"console output")?;
+        drop(vm_console);
+
+        // Only returns once every copy of the write end is closed, including the one lent out.
+        let mut buf = String::new();
+        read_end.read_to_string(&mut buf)?;
+        assert_eq!(buf, "console output");
+        Ok(())
+    }
+
+    #[test]
+    fn parcel_fds_from_raw_accepts_open_fd() -> Result<(), Error> {
+        let file = File::open("/dev/null")?;
+        let parcel_fds = parcel_fds_from_raw(&[file.as_raw_fd()])?;
+        assert_eq!(parcel_fds.len(), 1);
+        assert_ne!(parcel_fds[0].as_raw_fd(), file.as_raw_fd());
+        Ok(())
+    }
+
+    #[test]
+    fn parcel_fds_from_raw_rejects_invalid_fd() -> Result<(), Error> {
+        let file = File::open("/dev/null")?;
+        // Closing a real fd and reusing its number would race with other tests opening files.
+        let invalid_fd = -1;
+
+        let err = parcel_fds_from_raw(&[file.as_raw_fd(), invalid_fd]).unwrap_err();
+        assert_eq!(err.to_string(), format!("Invalid fd {invalid_fd}"));
+        Ok(())
+    }
+
+    #[test]
+    fn config_problems_reports_all_problems() {
+        let vm_config: VmConfig = serde_json::from_str(
+            r#"{
+                "kernel": "/nonexistent/kernel",
+                "cpu_topology": "two_cpus",
+                "platform_version": "~1.0"
+            }"#,
+        )
+        .unwrap();
+
+        let problems: Vec<_> = config_problems(&vm_config).iter().map(|e| e.to_string()).collect();
+        assert_eq!(problems, ["Invalid cpu topology two_cpus", "Invalid kernel"]);
+    }
+
+    #[test]
+    fn config_problems_accepts_valid_config() {
+        let vm_config: VmConfig =
+            serde_json::from_str(r#"{ "kernel": "/dev/null", "platform_version": "~1.0" }"#)
+                .unwrap();
+
+        assert!(config_problems(&vm_config).is_empty());
+    }
+
+    #[test]
+    fn run_outcome_carries_cid() {
+        let outcome = RunOutcome::started(2048);
+        assert_eq!(outcome, RunOutcome { cid: 2048, death_reason: None });
+
+        let outcome = outcome.died(DeathReason::Shutdown);
+        assert_eq!(outcome, RunOutcome { cid: 2048, death_reason: Some(DeathReason::Shutdown) });
+    }
+
+    #[test]
+    fn cid_json_parses_back_to_cid() {
+        let json: serde_json::Value = serde_json::from_str(&cid_json(2048)).unwrap();
+
+        assert_eq!(json["cid"], 2048);
+    }
+}
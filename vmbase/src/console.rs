@@ -14,12 +14,13 @@
 
 //! Console driver for 8250 UART.
 
+mod base_address;
+
 use crate::uart::Uart;
 use core::fmt::{write, Arguments, Write};
 use spin::mutex::SpinMutex;
 
-/// Base memory-mapped address of the primary UART device.
-pub const BASE_ADDRESS: usize = 0x3f8;
+pub use base_address::BASE_ADDRESS;
 
 static CONSOLE: SpinMutex<Option<Uart>> = SpinMutex::new(None);
 
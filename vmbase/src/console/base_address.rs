@@ -0,0 +1,77 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Build-time selection of the console UART.
+
+/// Base memory-mapped address of the primary UART device.
+///
+/// This is the first UART allocated by crosvm unless one of the `uart_2f8`, `uart_3e8` or
+/// `uart_2e8` features selects another legacy 8250 port, for platforms that place it elsewhere.
+pub const BASE_ADDRESS: usize = if cfg!(feature = "uart_2f8") {
+    0x2f8
+} else if cfg!(feature = "uart_3e8") {
+    0x3e8
+} else if cfg!(feature = "uart_2e8") {
+    0x2e8
+} else {
+    0x3f8
+};
+
+// At most one UART base address can be selected.
+const _: () = assert!(
+    (cfg!(feature = "uart_2f8") as u8)
+        + (cfg!(feature = "uart_3e8") as u8)
+        + (cfg!(feature = "uart_2e8") as u8)
+        <= 1
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(not(any(feature = "uart_2f8", feature = "uart_3e8", feature = "uart_2e8")))]
+    fn defaults_to_first_crosvm_uart() {
+        assert_eq!(BASE_ADDRESS, 0x3f8);
+    }
+
+    #[test]
+    #[cfg(feature = "uart_2f8")]
+    fn uart_2f8_selects_0x2f8() {
+        assert_eq!(BASE_ADDRESS, 0x2f8);
+    }
+
+    #[test]
+    #[cfg(feature = "uart_3e8")]
+    fn uart_3e8_selects_0x3e8() {
+        assert_eq!(BASE_ADDRESS, 0x3e8);
+    }
+
+    #[test]
+    #[cfg(feature = "uart_2e8")]
+    fn uart_2e8_selects_0x2e8() {
+        assert_eq!(BASE_ADDRESS, 0x2e8);
+    }
+
+    /// The exception handler ignores external aborts in the page of the UART, and entry.rs maps
+    /// it through the MMIO guard before the page table is set up, assuming it is page 0.
+    #[test]
+    fn uart_registers_are_in_page_zero() {
+        const PAGE_SIZE: usize = 0x1000;
+        const UART_REGISTERS_SIZE: usize = 8;
+
+        assert_eq!(BASE_ADDRESS / PAGE_SIZE, 0);
+        assert_eq!((BASE_ADDRESS + UART_REGISTERS_SIZE - 1) / PAGE_SIZE, 0);
+    }
+}
@@ -14,6 +14,10 @@
 
 //! Helper functions and structs for exception handlers.
 
+mod esr;
+
+pub use esr::Esr;
+
 use crate::{
     console, eprintln,
     memory::{page_4kb_of, MemoryTrackerError},
@@ -35,6 +39,8 @@ pub enum HandleExceptionError {
     InternalError(MemoryTrackerError),
     /// An unknown exception occurred.
     UnknownException,
+    /// The faulting address is outside of the MMIO ranges of the memory tracker.
+    UnknownAddress,
 }
 
 impl From<MemoryTrackerError> for HandleExceptionError {
@@ -50,55 +56,11 @@ impl fmt::Display for HandleExceptionError {
             Self::PageTableNotInitialized => write!(f, "Page table is not initialized."),
             Self::InternalError(e) => write!(f, "Error while updating page table: {e}"),
             Self::UnknownException => write!(f, "An unknown exception occurred, not handled."),
+            Self::UnknownAddress => write!(f, "The faulting address is not in an MMIO range."),
         }
     }
 }
 
-/// Represents the possible types of exception syndrome register (ESR) values.
-#[derive(Debug, PartialEq, Copy, Clone)]
-pub enum Esr {
-    /// Data abort due to translation fault.
-    DataAbortTranslationFault,
-    /// Data abort due to permission fault.
-    DataAbortPermissionFault,
-    /// Data abort due to a synchronous external abort.
-    DataAbortSyncExternalAbort,
-    /// An unknown ESR value.
-    Unknown(usize),
-}
-
-impl Esr {
-    const EXT_DABT_32BIT: usize = 0x96000010;
-    const TRANSL_FAULT_BASE_32BIT: usize = 0x96000004;
-    const TRANSL_FAULT_ISS_MASK_32BIT: usize = !0x143;
-    const PERM_FAULT_BASE_32BIT: usize = 0x9600004C;
-    const PERM_FAULT_ISS_MASK_32BIT: usize = !0x103;
-}
-
-impl From<usize> for Esr {
-    fn from(esr: usize) -> Self {
-        if esr == Self::EXT_DABT_32BIT {
-            Self::DataAbortSyncExternalAbort
-        } else if esr & Self::TRANSL_FAULT_ISS_MASK_32BIT == Self::TRANSL_FAULT_BASE_32BIT {
-            Self::DataAbortTranslationFault
-        } else if esr & Self::PERM_FAULT_ISS_MASK_32BIT == Self::PERM_FAULT_BASE_32BIT {
-            Self::DataAbortPermissionFault
-        } else {
-            Self::Unknown(esr)
-        }
-    }
-}
-
-impl fmt::Display for Esr {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        match self {
-            Self::DataAbortSyncExternalAbort => write!(f, "Synchronous external abort"),
-            Self::DataAbortTranslationFault => write!(f, "Translation fault"),
-            Self::DataAbortPermissionFault => write!(f, "Permission fault"),
-            Self::Unknown(v) => write!(f, "Unknown exception esr={v:#08x}"),
-        }
-    }
-}
 /// A struct representing an Armv8 exception.
 pub struct ArmException {
     /// The value of the exception syndrome register.
@@ -0,0 +1,92 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Decoding of the exception syndrome register.
+
+use core::fmt;
+
+/// Represents the possible types of exception syndrome register (ESR) values.
+#[derive(Debug, PartialEq, Copy, Clone)]
+pub enum Esr {
+    /// Data abort due to translation fault.
+    DataAbortTranslationFault,
+    /// Data abort due to permission fault.
+    DataAbortPermissionFault,
+    /// Data abort due to a synchronous external abort.
+    DataAbortSyncExternalAbort,
+    /// Trapped WFI or WFE instruction.
+    WfxTrap,
+    /// An unknown ESR value.
+    Unknown(usize),
+}
+
+impl Esr {
+    const EXT_DABT_32BIT: usize = 0x96000010;
+    const TRANSL_FAULT_BASE_32BIT: usize = 0x96000004;
+    const TRANSL_FAULT_ISS_MASK_32BIT: usize = !0x143;
+    const PERM_FAULT_BASE_32BIT: usize = 0x9600004C;
+    const PERM_FAULT_ISS_MASK_32BIT: usize = !0x103;
+    const EC_SHIFT: usize = 26;
+    const EC_MASK: usize = 0x3f;
+    const EC_WFX: usize = 0x01;
+}
+
+impl From<usize> for Esr {
+    fn from(esr: usize) -> Self {
+        if esr == Self::EXT_DABT_32BIT {
+            Self::DataAbortSyncExternalAbort
+        } else if esr & Self::TRANSL_FAULT_ISS_MASK_32BIT == Self::TRANSL_FAULT_BASE_32BIT {
+            Self::DataAbortTranslationFault
+        } else if esr & Self::PERM_FAULT_ISS_MASK_32BIT == Self::PERM_FAULT_BASE_32BIT {
+            Self::DataAbortPermissionFault
+        } else if (esr >> Self::EC_SHIFT) & Self::EC_MASK == Self::EC_WFX {
+            Self::WfxTrap
+        } else {
+            Self::Unknown(esr)
+        }
+    }
+}
+
+impl fmt::Display for Esr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DataAbortSyncExternalAbort => write!(f, "Synchronous external abort"),
+            Self::DataAbortTranslationFault => write!(f, "Translation fault"),
+            Self::DataAbortPermissionFault => write!(f, "Permission fault"),
+            Self::WfxTrap => write!(f, "Trapped WFI/WFE"),
+            Self::Unknown(v) => write!(f, "Unknown exception esr={v:#08x}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wfx_trap_syndromes_are_recognized() {
+        // EC = 0x01 with IL set, and TI selecting WFI, WFE, WFIT and WFET respectively.
+        for esr in [0x0600_0000, 0x0600_0001, 0x0600_0002, 0x0600_0003] {
+            assert_eq!(Esr::from(esr), Esr::WfxTrap);
+        }
+        assert_eq!(Esr::from(0x0200_0000), Esr::Unknown(0x0200_0000));
+    }
+
+    #[test]
+    fn data_abort_syndromes_are_not_wfx_traps() {
+        assert_eq!(Esr::from(0x9600_0004), Esr::DataAbortTranslationFault);
+        assert_eq!(Esr::from(0x9600_004c), Esr::DataAbortPermissionFault);
+        assert_eq!(Esr::from(0x9600_0010), Esr::DataAbortSyncExternalAbort);
+    }
+}
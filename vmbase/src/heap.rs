@@ -14,6 +14,8 @@
 
 //! Heap implementation.
 
+mod tracking_heap;
+
 use alloc::alloc::alloc;
 use alloc::alloc::Layout;
 use alloc::boxed::Box;
@@ -25,7 +27,8 @@ use core::num::NonZeroUsize;
 use core::ptr;
 use core::ptr::NonNull;
 
-use buddy_system_allocator::LockedHeap;
+pub use tracking_heap::HeapStats;
+use tracking_heap::TrackingHeap;
 
 /// Configures the size of the global allocator.
 #[macro_export]
@@ -44,7 +47,12 @@ extern "Rust" {
 }
 
 #[global_allocator]
-static HEAP_ALLOCATOR: LockedHeap<32> = LockedHeap::<32>::new();
+static HEAP_ALLOCATOR: TrackingHeap = TrackingHeap::new();
+
+/// Returns the current usage statistics of the heap, e.g. to size it appropriately.
+pub fn heap_stats() -> HeapStats {
+    HEAP_ALLOCATOR.stats()
+}
 
 /// Initialize the global allocator.
 ///
@@ -56,9 +64,8 @@ pub(crate) unsafe fn init() {
     // never touch it again. The heap is locked, so there cannot be any races.
     let (start, size) = unsafe { (HEAP.as_mut_ptr() as usize, HEAP.len()) };
 
-    let mut heap = HEAP_ALLOCATOR.lock();
     // SAFETY: We are supplying a valid memory range, and we only do this once.
-    unsafe { heap.init(start, size) };
+    unsafe { HEAP_ALLOCATOR.init(start, size) };
 }
 
 /// Allocate an aligned but uninitialized slice of heap.
@@ -0,0 +1,153 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Buddy allocator keeping track of its usage.
+
+use core::alloc::{GlobalAlloc, Layout};
+use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use buddy_system_allocator::LockedHeap;
+
+/// Buddy allocator keeping track of its high-water mark.
+pub(crate) struct TrackingHeap {
+    heap: LockedHeap<32>,
+    peak: AtomicUsize,
+}
+
+impl TrackingHeap {
+    /// Creates an empty heap.
+    pub(crate) const fn new() -> Self {
+        Self { heap: LockedHeap::<32>::new(), peak: AtomicUsize::new(0) }
+    }
+
+    /// Hands the memory range `[start, start + size)` over to the heap.
+    ///
+    /// # Safety
+    ///
+    /// The range must be valid, unused by anything else and only be given to the heap once.
+    pub(crate) unsafe fn init(&self, start: usize, size: usize) {
+        // SAFETY: Our caller guarantees that the memory range is valid and ours to give.
+        unsafe { self.heap.lock().init(start, size) };
+    }
+
+    /// Returns the current usage statistics of the heap.
+    pub(crate) fn stats(&self) -> HeapStats {
+        let heap = self.heap.lock();
+        HeapStats {
+            total: heap.stats_total_bytes(),
+            allocated: heap.stats_alloc_actual(),
+            peak: self.peak.load(Ordering::Relaxed),
+        }
+    }
+}
+
+// SAFETY: All allocations are delegated to the underlying `LockedHeap`.
+unsafe impl GlobalAlloc for TrackingHeap {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let mut heap = self.heap.lock();
+        let Ok(ptr) = heap.alloc(layout) else { return ptr::null_mut() };
+        self.peak.fetch_max(heap.stats_alloc_actual(), Ordering::Relaxed);
+        ptr.as_ptr()
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: Our caller guarantees that `ptr` was allocated by us with `layout`.
+        unsafe { self.heap.dealloc(ptr, layout) }
+    }
+}
+
+/// Usage statistics of the heap, in bytes.
+#[derive(Clone, Copy, Debug)]
+pub struct HeapStats {
+    /// Size of the heap.
+    pub total: usize,
+    /// Memory currently allocated, including the allocator overhead.
+    pub allocated: usize,
+    /// Highest value reached by `allocated` since the heap was initialized.
+    pub peak: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEAP_SIZE: usize = 4096;
+
+    #[repr(align(4096))]
+    struct HeapMemory([u8; HEAP_SIZE]);
+
+    fn new_heap(memory: &mut HeapMemory) -> TrackingHeap {
+        let heap = TrackingHeap::new();
+        // SAFETY: The memory is exclusively borrowed for as long as the test uses the heap.
+        unsafe { heap.init(memory.0.as_mut_ptr() as usize, HEAP_SIZE) };
+        heap
+    }
+
+    #[test]
+    fn stats_track_allocations() {
+        let mut memory = HeapMemory([0; HEAP_SIZE]);
+        let heap = new_heap(&mut memory);
+        let layout = Layout::from_size_align(256, 8).unwrap();
+
+        // SAFETY: The layout has a non-zero size.
+        let ptr = unsafe { heap.alloc(layout) };
+        assert!(!ptr.is_null());
+        let stats = heap.stats();
+        assert_eq!(stats.total, HEAP_SIZE);
+        assert_eq!(stats.allocated, 256);
+        assert_eq!(stats.peak, 256);
+
+        // SAFETY: `ptr` was allocated by `heap` with `layout`.
+        unsafe { heap.dealloc(ptr, layout) };
+        let stats = heap.stats();
+        assert_eq!(stats.allocated, 0);
+        assert_eq!(stats.peak, 256);
+    }
+
+    #[test]
+    fn peak_is_the_high_water_mark() {
+        let mut memory = HeapMemory([0; HEAP_SIZE]);
+        let heap = new_heap(&mut memory);
+        let small = Layout::from_size_align(128, 8).unwrap();
+        let large = Layout::from_size_align(1024, 8).unwrap();
+
+        // SAFETY: The layouts have non-zero sizes and each pointer is freed with its own layout.
+        unsafe {
+            let a = heap.alloc(large);
+            let b = heap.alloc(small);
+            heap.dealloc(a, large);
+            let c = heap.alloc(small);
+            heap.dealloc(b, small);
+            heap.dealloc(c, small);
+        }
+
+        let stats = heap.stats();
+        assert_eq!(stats.allocated, 0);
+        assert_eq!(stats.peak, 1024 + 128);
+    }
+
+    #[test]
+    fn failed_allocations_do_not_count() {
+        let mut memory = HeapMemory([0; HEAP_SIZE]);
+        let heap = new_heap(&mut memory);
+        let layout = Layout::from_size_align(2 * HEAP_SIZE, 8).unwrap();
+
+        // SAFETY: The layout has a non-zero size.
+        let ptr = unsafe { heap.alloc(layout) };
+
+        assert!(ptr.is_null());
+        assert_eq!(heap.stats().peak, 0);
+    }
+}
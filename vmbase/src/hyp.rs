@@ -19,5 +19,6 @@ mod hypervisor;
 
 pub use error::{Error, Result};
 pub use hypervisor::{
-    get_device_assigner, get_mem_sharer, get_mmio_guard, DeviceAssigningHypervisor, KvmError,
+    get_device_assigner, get_mem_sharer, get_mmio_guard, hypervisor_features,
+    DeviceAssigningHypervisor, KvmError, KvmFeatures, MmioGuardMapping, MmioGuardUnmap,
 };
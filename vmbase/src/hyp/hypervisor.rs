@@ -18,6 +18,8 @@ mod common;
 mod geniezone;
 mod gunyah;
 mod kvm;
+mod kvm_features;
+mod mmio_guard_mapping;
 
 use super::{Error, Result};
 use alloc::boxed::Box;
@@ -28,6 +30,8 @@ use geniezone::GeniezoneHypervisor;
 use gunyah::GunyahHypervisor;
 pub use kvm::KvmError;
 use kvm::{ProtectedKvmHypervisor, RegularKvmHypervisor};
+pub use kvm_features::KvmFeatures;
+pub use mmio_guard_mapping::{MmioGuardMapping, MmioGuardUnmap};
 use once_cell::race::OnceBox;
 use smccc::hvc64;
 use uuid::Uuid;
@@ -104,11 +108,26 @@ fn detect_hypervisor() -> HypervisorBackend {
     query_vendor_hyp_call_uid().try_into().expect("Failed to detect hypervisor")
 }
 
+/// Gets the detected hypervisor backend.
+fn get_hypervisor_backend() -> &'static HypervisorBackend {
+    static HYPERVISOR: OnceBox<HypervisorBackend> = OnceBox::new();
+
+    HYPERVISOR.get_or_init(|| Box::new(detect_hypervisor()))
+}
+
 /// Gets the hypervisor singleton.
 fn get_hypervisor() -> &'static dyn Hypervisor {
-    static HYPERVISOR: OnceBox<HypervisorBackend> = OnceBox::new();
+    get_hypervisor_backend().get_hypervisor()
+}
 
-    HYPERVISOR.get_or_init(|| Box::new(detect_hypervisor())).get_hypervisor()
+/// Queries the vendor-specific services supported by the hypervisor, for firmware to adapt to it.
+///
+/// Only KVM-based hypervisors implement the query; others report no feature.
+pub fn hypervisor_features() -> Result<KvmFeatures> {
+    match get_hypervisor_backend() {
+        HypervisorBackend::RegularKvm | HypervisorBackend::ProtectedKvm => kvm::kvm_features(),
+        HypervisorBackend::Gunyah | HypervisorBackend::Geniezone => Ok(KvmFeatures::default()),
+    }
 }
 
 /// Gets the MMIO_GUARD hypervisor singleton, if any.
@@ -14,7 +14,8 @@
 
 //! This module regroups some common traits shared by all the hypervisors.
 
-use crate::hyp::Result;
+use super::mmio_guard_mapping::{MmioGuardMapping, MmioGuardUnmap};
+use crate::hyp::{Error, Result};
 
 /// Trait for the hypervisor.
 pub trait Hypervisor {
@@ -51,6 +52,23 @@ pub trait MmioGuardedHypervisor {
     fn granule(&self) -> Result<usize>;
 }
 
+impl<'a> dyn MmioGuardedHypervisor + 'a {
+    /// Maps a page containing the given memory address to the hypervisor MMIO guard, returning a
+    /// mapping that unmaps it when dropped.
+    pub fn map_guarded(&'a self, addr: usize) -> Result<MmioGuardMapping<'a, Self>> {
+        self.map(addr)?;
+        Ok(MmioGuardMapping::new(self, addr))
+    }
+}
+
+impl<'a> MmioGuardUnmap for dyn MmioGuardedHypervisor + 'a {
+    type Error = Error;
+
+    fn unmap_page(&self, addr: usize) -> Result<()> {
+        self.unmap(addr)
+    }
+}
+
 pub trait MemSharingHypervisor {
     /// Shares a region of memory with host, granting it read, write and execute permissions.
     /// The size of the region is equal to the memory protection granule returned by
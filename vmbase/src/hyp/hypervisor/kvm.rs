@@ -16,6 +16,12 @@
 
 use core::fmt::{self, Display, Formatter};
 
+use super::kvm_features::{
+    KvmFeatures, ARM_SMCCC_KVM_FUNC_HYP_MEMINFO, ARM_SMCCC_KVM_FUNC_MEM_SHARE,
+    ARM_SMCCC_KVM_FUNC_MEM_UNSHARE, VENDOR_HYP_KVM_MMIO_GUARD_ENROLL_FUNC_ID,
+    VENDOR_HYP_KVM_MMIO_GUARD_INFO_FUNC_ID, VENDOR_HYP_KVM_MMIO_GUARD_MAP_FUNC_ID,
+    VENDOR_HYP_KVM_MMIO_GUARD_UNMAP_FUNC_ID,
+};
 use super::{DeviceAssigningHypervisor, Hypervisor, MemSharingHypervisor, MmioGuardedHypervisor};
 use crate::{
     hyp::{Error, Result},
@@ -65,18 +71,14 @@ impl Display for KvmError {
     }
 }
 
-const ARM_SMCCC_KVM_FUNC_HYP_MEMINFO: u32 = 0xc6000002;
-const ARM_SMCCC_KVM_FUNC_MEM_SHARE: u32 = 0xc6000003;
-const ARM_SMCCC_KVM_FUNC_MEM_UNSHARE: u32 = 0xc6000004;
-
-const VENDOR_HYP_KVM_MMIO_GUARD_INFO_FUNC_ID: u32 = 0xc6000005;
-const VENDOR_HYP_KVM_MMIO_GUARD_ENROLL_FUNC_ID: u32 = 0xc6000006;
-const VENDOR_HYP_KVM_MMIO_GUARD_MAP_FUNC_ID: u32 = 0xc6000007;
-const VENDOR_HYP_KVM_MMIO_GUARD_UNMAP_FUNC_ID: u32 = 0xc6000008;
-
 const VENDOR_HYP_KVM_DEV_REQ_MMIO_FUNC_ID: u32 = 0xc6000012;
 const VENDOR_HYP_KVM_DEV_REQ_DMA_FUNC_ID: u32 = 0xc6000013;
 
+/// Queries the vendor-specific services supported by a KVM hypervisor.
+pub(super) fn kvm_features() -> Result<KvmFeatures> {
+    KvmFeatures::query(checked_hvc64)
+}
+
 pub(super) struct RegularKvmHypervisor;
 
 impl RegularKvmHypervisor {
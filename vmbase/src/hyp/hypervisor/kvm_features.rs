@@ -0,0 +1,132 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Discovery of the vendor-specific services of the KVM hypervisor.
+
+pub(crate) const ARM_SMCCC_VENDOR_HYP_KVM_FEATURES_FUNC_ID: u32 = 0x86000000;
+
+pub(crate) const ARM_SMCCC_KVM_FUNC_HYP_MEMINFO: u32 = 0xc6000002;
+pub(crate) const ARM_SMCCC_KVM_FUNC_MEM_SHARE: u32 = 0xc6000003;
+pub(crate) const ARM_SMCCC_KVM_FUNC_MEM_UNSHARE: u32 = 0xc6000004;
+
+pub(crate) const VENDOR_HYP_KVM_MMIO_GUARD_INFO_FUNC_ID: u32 = 0xc6000005;
+pub(crate) const VENDOR_HYP_KVM_MMIO_GUARD_ENROLL_FUNC_ID: u32 = 0xc6000006;
+pub(crate) const VENDOR_HYP_KVM_MMIO_GUARD_MAP_FUNC_ID: u32 = 0xc6000007;
+pub(crate) const VENDOR_HYP_KVM_MMIO_GUARD_UNMAP_FUNC_ID: u32 = 0xc6000008;
+
+/// Vendor-specific KVM services supported by the hypervisor, as reported by
+/// ARM_SMCCC_VENDOR_HYP_KVM_FEATURES_FUNC_ID.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub struct KvmFeatures(u32);
+
+impl KvmFeatures {
+    /// Queries the supported services through `hvc`, which issues the given HVC with the given
+    /// arguments and returns its checked result.
+    pub(crate) fn query<E>(hvc: impl FnOnce(u32, [u64; 17]) -> Result<u64, E>) -> Result<Self, E> {
+        let bitmap = hvc(ARM_SMCCC_VENDOR_HYP_KVM_FEATURES_FUNC_ID, [0u64; 17])?;
+        // Only the bits of the 32-bit return value are defined.
+        Ok(Self(bitmap as u32))
+    }
+
+    /// Returns whether the vendor hypervisor service `function` is supported.
+    pub fn supports(&self, function: u32) -> bool {
+        // The bitmap is indexed by the function number, the low 16 bits of the function ID.
+        match 1u32.checked_shl(function & 0xffff) {
+            Some(bit) => self.0 & bit != 0,
+            None => false,
+        }
+    }
+
+    /// Returns whether the MMIO_GUARD services are all supported.
+    pub fn mmio_guard(&self) -> bool {
+        [
+            VENDOR_HYP_KVM_MMIO_GUARD_INFO_FUNC_ID,
+            VENDOR_HYP_KVM_MMIO_GUARD_ENROLL_FUNC_ID,
+            VENDOR_HYP_KVM_MMIO_GUARD_MAP_FUNC_ID,
+            VENDOR_HYP_KVM_MMIO_GUARD_UNMAP_FUNC_ID,
+        ]
+        .into_iter()
+        .all(|function| self.supports(function))
+    }
+
+    /// Returns whether dynamic memory sharing is supported.
+    pub fn mem_sharing(&self) -> bool {
+        [
+            ARM_SMCCC_KVM_FUNC_HYP_MEMINFO,
+            ARM_SMCCC_KVM_FUNC_MEM_SHARE,
+            ARM_SMCCC_KVM_FUNC_MEM_UNSHARE,
+        ]
+        .into_iter()
+        .all(|function| self.supports(function))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOT_SUPPORTED: i64 = -1;
+
+    /// Returns the result of a mocked KVM which only implements the features query.
+    fn mock_hvc(bitmap: u64) -> impl FnOnce(u32, [u64; 17]) -> Result<u64, i64> {
+        move |function, args| {
+            assert_eq!(args, [0; 17]);
+            match function {
+                ARM_SMCCC_VENDOR_HYP_KVM_FEATURES_FUNC_ID => Ok(bitmap),
+                _ => Err(NOT_SUPPORTED),
+            }
+        }
+    }
+
+    #[test]
+    fn query_reports_supported_services() {
+        // Bit 0 is the features query itself, bits 2 to 8 are MEMINFO to MMIO_GUARD_UNMAP.
+        let features = KvmFeatures::query(mock_hvc(0b1_1111_1101)).unwrap();
+
+        assert!(features.supports(ARM_SMCCC_VENDOR_HYP_KVM_FEATURES_FUNC_ID));
+        assert!(features.mmio_guard());
+        assert!(features.mem_sharing());
+    }
+
+    #[test]
+    fn query_reports_missing_services() {
+        let features = KvmFeatures::query(mock_hvc(0b1_1110_0001)).unwrap();
+
+        assert!(features.mmio_guard());
+        assert!(!features.mem_sharing());
+        assert!(!features.supports(ARM_SMCCC_KVM_FUNC_MEM_SHARE));
+    }
+
+    #[test]
+    fn query_ignores_undefined_bits() {
+        let features = KvmFeatures::query(mock_hvc(0xffff_ffff_0000_0000)).unwrap();
+
+        assert_eq!(features, KvmFeatures::default());
+    }
+
+    #[test]
+    fn query_propagates_errors() {
+        let hvc = |_, _| Err(NOT_SUPPORTED);
+
+        assert_eq!(KvmFeatures::query(hvc), Err(NOT_SUPPORTED));
+    }
+
+    #[test]
+    fn function_numbers_beyond_the_bitmap_are_unsupported() {
+        let features = KvmFeatures::query(mock_hvc(u32::MAX.into())).unwrap();
+
+        assert!(features.supports(0xc600001f));
+        assert!(!features.supports(0xc6000020));
+    }
+}
@@ -0,0 +1,109 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Pages mapped to the hypervisor MMIO guard for a limited time.
+
+use core::fmt;
+use log::warn;
+
+/// Hypervisor from which MMIO guard pages can be unmapped.
+pub trait MmioGuardUnmap {
+    /// Error returned when unmapping fails.
+    type Error: fmt::Display;
+
+    /// Unmaps the page containing the given memory address from the hypervisor MMIO guard.
+    fn unmap_page(&self, addr: usize) -> Result<(), Self::Error>;
+}
+
+/// Page mapped to the hypervisor MMIO guard, which is unmapped when dropped.
+#[must_use]
+pub struct MmioGuardMapping<'a, H: MmioGuardUnmap + ?Sized> {
+    hypervisor: &'a H,
+    addr: usize,
+}
+
+impl<'a, H: MmioGuardUnmap + ?Sized> MmioGuardMapping<'a, H> {
+    /// Takes ownership of the page containing `addr`, which must already be mapped.
+    pub(crate) fn new(hypervisor: &'a H, addr: usize) -> Self {
+        Self { hypervisor, addr }
+    }
+
+    /// Keeps the page mapped beyond the lifetime of this object.
+    pub fn leak(self) {
+        core::mem::forget(self)
+    }
+}
+
+impl<H: MmioGuardUnmap + ?Sized> Drop for MmioGuardMapping<'_, H> {
+    fn drop(&mut self) {
+        if let Err(e) = self.hypervisor.unmap_page(self.addr) {
+            warn!("Failed to unmap MMIO guard page of {:#x}: {e}", self.addr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    /// Mock hypervisor recording the pages unmapped from its MMIO guard.
+    #[derive(Default)]
+    struct MockHypervisor {
+        unmapped: RefCell<Vec<usize>>,
+        fail: bool,
+    }
+
+    impl MmioGuardUnmap for MockHypervisor {
+        type Error = &'static str;
+
+        fn unmap_page(&self, addr: usize) -> Result<(), Self::Error> {
+            self.unmapped.borrow_mut().push(addr);
+            if self.fail {
+                Err("unmap failed")
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    #[test]
+    fn drop_unmaps_the_page() {
+        let hypervisor = MockHypervisor::default();
+
+        let mapping = MmioGuardMapping::new(&hypervisor, 0x9000);
+        assert!(hypervisor.unmapped.borrow().is_empty());
+        drop(mapping);
+
+        assert_eq!(*hypervisor.unmapped.borrow(), [0x9000]);
+    }
+
+    #[test]
+    fn leak_keeps_the_page_mapped() {
+        let hypervisor = MockHypervisor::default();
+
+        MmioGuardMapping::new(&hypervisor, 0x9000).leak();
+
+        assert!(hypervisor.unmapped.borrow().is_empty());
+    }
+
+    #[test]
+    fn drop_ignores_unmap_failures() {
+        let hypervisor = MockHypervisor { fail: true, ..Default::default() };
+
+        drop(MmioGuardMapping::new(&hypervisor, 0x9000));
+
+        assert_eq!(*hypervisor.unmapped.borrow(), [0x9000]);
+    }
+}
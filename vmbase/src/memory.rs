@@ -16,18 +16,23 @@
 
 mod dbm;
 mod error;
+mod lazy_mmio;
+mod mappings;
+mod mmio_regions;
 mod page_table;
 mod shared;
 mod util;
 
 pub use error::MemoryTrackerError;
+pub use mappings::Permissions;
 pub use page_table::PageTable;
 pub use shared::{
-    handle_permission_fault, handle_translation_fault, MemoryRange, MemoryTracker, MEMORY,
+    handle_permission_fault, handle_translation_fault, is_mmio_address, MemoryRange, MemoryTracker,
+    MEMORY,
 };
 pub use util::{
     flush, flushed_zeroize, min_dcache_line_size, page_4kb_of, PAGE_SIZE, SIZE_128KB, SIZE_16KB,
-    SIZE_2MB, SIZE_4KB, SIZE_4MB, SIZE_64KB,
+    SIZE_256KB, SIZE_2MB, SIZE_4KB, SIZE_4MB, SIZE_64KB,
 };
 
 pub(crate) use shared::{alloc_shared, dealloc_shared};
@@ -0,0 +1,115 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Handling of translation faults on MMIO pages mapped lazily.
+
+use core::ops::Range;
+
+/// Operations on the page table and the MMIO guard needed to map a lazy MMIO page.
+pub(crate) trait LazyMmio {
+    /// Error returned when sharing or mapping a page fails.
+    type Error;
+
+    /// Returns whether the page containing `addr` is already mapped as valid MMIO.
+    fn is_mapped(&self, addr: usize) -> bool;
+
+    /// Shares the granule containing `addr` through the MMIO guard and returns its range.
+    fn share(&mut self, addr: usize) -> Result<Range<usize>, Self::Error>;
+
+    /// Makes the lazy MMIO page table entries covering `range` valid.
+    fn map(&mut self, range: &Range<usize>) -> Result<(), Self::Error>;
+}
+
+/// Handles a translation fault at `addr` by sharing and mapping the lazy MMIO page containing it.
+///
+/// The fault is spurious if the page was mapped after it was taken, e.g. while handling a fault
+/// on the same page from another core. The page is then left as it is: sharing it again would
+/// fail, as the MMIO guard doesn't allow sharing a granule twice.
+pub(crate) fn handle_lazy_mmio_fault<T: LazyMmio + ?Sized>(
+    mmio: &mut T,
+    addr: usize,
+) -> Result<(), T::Error> {
+    if mmio.is_mapped(addr) {
+        return Ok(());
+    }
+    let shared_range = mmio.share(addr)?;
+    mmio.map(&shared_range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GRANULE: usize = 0x1000;
+    const PAGE: Range<usize> = 0x9000_0000..0x9000_1000;
+
+    /// Mock recording the granules shared and the ranges mapped.
+    #[derive(Default)]
+    struct MockMmio {
+        mapped: Vec<Range<usize>>,
+        shared: Vec<usize>,
+        fail_share: bool,
+    }
+
+    impl LazyMmio for MockMmio {
+        type Error = &'static str;
+
+        fn is_mapped(&self, addr: usize) -> bool {
+            self.mapped.iter().any(|r| r.contains(&addr))
+        }
+
+        fn share(&mut self, addr: usize) -> Result<Range<usize>, Self::Error> {
+            if self.fail_share {
+                return Err("share failed");
+            }
+            let base = addr & !(GRANULE - 1);
+            self.shared.push(base);
+            Ok(base..base + GRANULE)
+        }
+
+        fn map(&mut self, range: &Range<usize>) -> Result<(), Self::Error> {
+            self.mapped.push(range.clone());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn fault_on_lazy_page_shares_and_maps_it() {
+        let mut mmio = MockMmio::default();
+
+        assert_eq!(handle_lazy_mmio_fault(&mut mmio, 0x9000_0010), Ok(()));
+
+        assert_eq!(mmio.shared, [PAGE.start]);
+        assert_eq!(mmio.mapped, [PAGE]);
+    }
+
+    #[test]
+    fn spurious_fault_on_mapped_page_neither_shares_nor_remaps() {
+        let mut mmio = MockMmio { mapped: vec![PAGE], ..Default::default() };
+
+        assert_eq!(handle_lazy_mmio_fault(&mut mmio, 0x9000_0ff8), Ok(()));
+
+        assert!(mmio.shared.is_empty());
+        assert_eq!(mmio.mapped, [PAGE]);
+    }
+
+    #[test]
+    fn failure_to_share_leaves_page_unmapped() {
+        let mut mmio = MockMmio { fail_share: true, ..Default::default() };
+
+        assert_eq!(handle_lazy_mmio_fault(&mut mmio, 0x9000_0000), Err("share failed"));
+
+        assert!(mmio.mapped.is_empty());
+    }
+}
@@ -0,0 +1,82 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Description of the ranges mapped by the memory tracker, for debugging.
+
+use core::ops::Range;
+
+/// Access permissions of a range mapped by the memory tracker.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permissions {
+    /// Read-only main memory.
+    ReadOnly,
+    /// Writable main memory.
+    ReadWrite,
+    /// Executable main memory.
+    Executable,
+    /// MMIO range mapped as device memory.
+    Device,
+}
+
+/// Returns the main memory `regions`, followed by the `mmio_regions` as device memory and by the
+/// writable `payload_range`, if any.
+pub(crate) fn mappings<'a>(
+    regions: impl Iterator<Item = (Range<usize>, Permissions)> + 'a,
+    mmio_regions: impl Iterator<Item = &'a Range<usize>> + 'a,
+    payload_range: Option<&'a Range<usize>>,
+) -> impl Iterator<Item = (Range<usize>, Permissions)> + 'a {
+    let mmio_regions = mmio_regions.map(|r| (r.clone(), Permissions::Device));
+    let payload = payload_range.into_iter().map(|r| (r.clone(), Permissions::ReadWrite));
+    regions.chain(mmio_regions).chain(payload)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yields_both_mapped_regions() {
+        let regions =
+            [(0x1000..0x2000, Permissions::Executable), (0x4000..0x8000, Permissions::ReadWrite)];
+
+        let mapped: Vec<_> = mappings(regions.iter().cloned(), [].iter(), None).collect();
+
+        assert_eq!(mapped, regions);
+    }
+
+    #[test]
+    fn yields_mmio_regions_then_payload_after_main_memory() {
+        let regions = [(0x1000..0x2000, Permissions::ReadOnly)];
+        let mmio_regions = [0x9000_0000..0x9000_1000, 0x9100_0000..0x9100_2000];
+        let payload_range = 0x8000_0000..0x8010_0000;
+
+        let mapped: Vec<_> =
+            mappings(regions.iter().cloned(), mmio_regions.iter(), Some(&payload_range)).collect();
+
+        assert_eq!(
+            mapped,
+            [
+                (0x1000..0x2000, Permissions::ReadOnly),
+                (0x9000_0000..0x9000_1000, Permissions::Device),
+                (0x9100_0000..0x9100_2000, Permissions::Device),
+                (0x8000_0000..0x8010_0000, Permissions::ReadWrite),
+            ]
+        );
+    }
+
+    #[test]
+    fn yields_nothing_when_nothing_is_mapped() {
+        assert_eq!(mappings([].into_iter(), [].iter(), None).count(), 0);
+    }
+}
@@ -0,0 +1,127 @@
+// Copyright 2024, The Android Open Source Project
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Bookkeeping of the MMIO ranges mapped by the memory tracker.
+
+use core::ops::Range;
+use tinyvec::ArrayVec;
+
+/// The MMIO ranges mapped for devices.
+///
+/// Only translation faults within these ranges may be handled by MMIO guard mapping the faulting
+/// page: anything else, including main memory, must never be shared with the host that way.
+#[derive(Clone, Debug, Default)]
+pub struct MmioRegions(ArrayVec<[Range<usize>; MmioRegions::CAPACITY]>);
+
+impl MmioRegions {
+    const CAPACITY: usize = 5;
+
+    /// Returns whether no more ranges can be added.
+    pub fn is_full(&self) -> bool {
+        self.0.len() == self.0.capacity()
+    }
+
+    /// Returns whether `range` overlaps with any of the ranges.
+    pub fn overlaps(&self, range: &Range<usize>) -> bool {
+        self.0.iter().any(|r| range.start < r.end && r.start < range.end)
+    }
+
+    /// Adds `range`, or returns it back if there is no space left.
+    pub fn push(&mut self, range: Range<usize>) -> Result<(), Range<usize>> {
+        match self.0.try_push(range) {
+            Some(range) => Err(range),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns whether `addr` belongs to one of the ranges.
+    pub fn contains(&self, addr: usize) -> bool {
+        self.0.iter().any(|r| r.contains(&addr))
+    }
+
+    /// Returns an iterator over the ranges, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = &Range<usize>> {
+        self.0.iter()
+    }
+}
+
+/// Returns the base addresses of the `granule`-sized blocks covering `range`.
+///
+/// `granule` must be a power of two.
+pub fn granules(range: &Range<usize>, granule: usize) -> impl Iterator<Item = usize> {
+    let start = range.start & !(granule - 1);
+    (start..range.end).step_by(granule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UART: Range<usize> = 0x3f8..0x400;
+    const PCI_BAR: Range<usize> = 0x9000_0000..0x9000_4000;
+
+    #[test]
+    fn fault_inside_mmio_regions_is_accepted() {
+        let mut regions = MmioRegions::default();
+        regions.push(UART).unwrap();
+        regions.push(PCI_BAR).unwrap();
+
+        assert!(regions.contains(0x3f8));
+        assert!(regions.contains(0x9000_0000));
+        assert!(regions.contains(0x9000_3fff));
+    }
+
+    #[test]
+    fn fault_outside_mmio_regions_is_rejected() {
+        let mut regions = MmioRegions::default();
+        assert!(!regions.contains(0x9000_0000));
+
+        regions.push(PCI_BAR).unwrap();
+        // Main memory, the end of the BAR and the gap after it aren't MMIO regions.
+        assert!(!regions.contains(0x8000_0000));
+        assert!(!regions.contains(0x9000_4000));
+        assert!(!regions.contains(0x9001_0000));
+    }
+
+    #[test]
+    fn overlapping_and_excess_ranges_are_detected() {
+        let mut regions = MmioRegions::default();
+        regions.push(PCI_BAR).unwrap();
+
+        assert!(regions.overlaps(&(0x9000_3000..0x9000_5000)));
+        assert!(!regions.overlaps(&(0x9000_4000..0x9000_5000)));
+
+        for i in 1..MmioRegions::CAPACITY {
+            assert!(!regions.is_full());
+            regions.push(i * 0x1000..(i + 1) * 0x1000).unwrap();
+        }
+        assert!(regions.is_full());
+        assert_eq!(regions.push(UART), Err(UART));
+        assert_eq!(regions.iter().next(), Some(&PCI_BAR));
+    }
+
+    #[test]
+    fn granules_cover_the_whole_range() {
+        let granules: Vec<_> = granules(&(0x9000_0800..0x9000_2001), 0x1000).collect();
+
+        assert_eq!(granules, [0x9000_0000, 0x9000_1000, 0x9000_2000]);
+    }
+
+    #[test]
+    fn granules_of_aligned_range() {
+        assert_eq!(granules(&PCI_BAR, 0x1000).count(), 4);
+        assert!(granules(&PCI_BAR, 0x4000).eq([0x9000_0000]));
+        assert_eq!(granules(&(0x9000_0000..0x9000_0000), 0x1000).count(), 0);
+    }
+}
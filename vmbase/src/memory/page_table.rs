@@ -36,6 +36,19 @@ const DATA: Attributes = MEMORY.union(Attributes::EXECUTE_NEVER);
 const RODATA: Attributes = DATA.union(Attributes::READ_ONLY);
 const DATA_DBM: Attributes = RODATA.union(Attributes::DBM);
 
+/// Returns whether memory mapped with `attrs` is both writable and executable.
+const fn is_writable_and_executable(attrs: Attributes) -> bool {
+    !attrs.contains(Attributes::READ_ONLY) && !attrs.contains(Attributes::EXECUTE_NEVER)
+}
+
+// Enforce W^X: none of the mappings may allow both writing to and executing from memory.
+const _: () = assert!(!is_writable_and_executable(DEVICE_LAZY));
+const _: () = assert!(!is_writable_and_executable(DEVICE));
+const _: () = assert!(!is_writable_and_executable(CODE));
+const _: () = assert!(!is_writable_and_executable(DATA));
+const _: () = assert!(!is_writable_and_executable(RODATA));
+const _: () = assert!(!is_writable_and_executable(DATA_DBM));
+
 type Result<T> = result::Result<T, MapError>;
 
 /// High-level API for managing MMU mappings.
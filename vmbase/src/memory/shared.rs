@@ -16,6 +16,9 @@
 
 use super::dbm::{flush_dirty_range, mark_dirty_block, set_dbm_enabled};
 use super::error::MemoryTrackerError;
+use super::lazy_mmio::{handle_lazy_mmio_fault, LazyMmio};
+use super::mappings::{mappings, Permissions};
+use super::mmio_regions::{granules, MmioRegions};
 use super::page_table::{PageTable, MMIO_LAZY_MAP_FLAG};
 use super::util::{page_4kb_of, virt_to_phys};
 use crate::console;
@@ -64,6 +67,17 @@ enum MemoryType {
     #[default]
     ReadOnly,
     ReadWrite,
+    Executable,
+}
+
+impl From<MemoryType> for Permissions {
+    fn from(mem_type: MemoryType) -> Self {
+        match mem_type {
+            MemoryType::ReadOnly => Self::ReadOnly,
+            MemoryType::ReadWrite => Self::ReadWrite,
+            MemoryType::Executable => Self::Executable,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -77,7 +91,7 @@ pub struct MemoryTracker {
     total: MemoryRange,
     page_table: PageTable,
     regions: ArrayVec<[MemoryRegion; MemoryTracker::CAPACITY]>,
-    mmio_regions: ArrayVec<[MemoryRange; MemoryTracker::MMIO_CAPACITY]>,
+    mmio_regions: MmioRegions,
     mmio_range: MemoryRange,
     payload_range: Option<MemoryRange>,
     mmio_sharer: MmioSharer,
@@ -85,7 +99,6 @@ pub struct MemoryTracker {
 
 impl MemoryTracker {
     const CAPACITY: usize = 5;
-    const MMIO_CAPACITY: usize = 5;
 
     /// Creates a new instance from an active page table, covering the maximum RAM size.
     pub fn new(
@@ -114,13 +127,26 @@ impl MemoryTracker {
             total,
             page_table,
             regions: ArrayVec::new(),
-            mmio_regions: ArrayVec::new(),
+            mmio_regions: MmioRegions::default(),
             mmio_range,
             payload_range: payload_range.map(|r| r.start.0..r.end.0),
             mmio_sharer: MmioSharer::new().unwrap(),
         }
     }
 
+    /// Returns whether `addr` belongs to one of the MMIO ranges mapped by the tracker.
+    pub fn is_mmio(&self, addr: usize) -> bool {
+        self.mmio_regions.contains(addr)
+    }
+
+    /// Returns the ranges currently mapped by the tracker along with their permissions.
+    ///
+    /// This covers the regions, the MMIO ranges and the payload, in that order.
+    pub fn mappings(&self) -> impl Iterator<Item = (MemoryRange, Permissions)> + '_ {
+        let regions = self.regions.iter().map(|r| (r.range.clone(), r.mem_type.into()));
+        mappings(regions, self.mmio_regions.iter(), self.payload_range.as_ref())
+    }
+
     /// Resize the total RAM size.
     ///
     /// This function fails if it contains regions that are not included within the new size.
@@ -180,6 +206,29 @@ impl MemoryTracker {
         self.add(region)
     }
 
+    /// Maps the address range as read-only and executable memory, for code to be run in place.
+    pub fn map_code(&mut self, range: &MemoryRange) -> Result<MemoryRange> {
+        let region = MemoryRegion { range: range.clone(), mem_type: MemoryType::Executable };
+        self.check_allocatable(&region)?;
+        self.page_table.map_code(&get_va_range(range)).map_err(|e| {
+            error!("Error during code range allocation: {e}");
+            MemoryTrackerError::FailedToMap
+        })?;
+        self.add(region)
+    }
+
+    /// Maps the address range as non-executable memory, which is writable if `writable` is set.
+    ///
+    /// As executable regions are never writable (see `map_code()`), no region of the tracker can
+    /// be both writable and executable.
+    pub fn map_data(&mut self, range: &MemoryRange, writable: bool) -> Result<MemoryRange> {
+        if writable {
+            self.alloc_range_mut(range)
+        } else {
+            self.alloc_range(range)
+        }
+    }
+
     /// Allocate the address range for a const slice; returns None if failed.
     pub fn alloc(&mut self, base: usize, size: NonZeroUsize) -> Result<MemoryRange> {
         self.alloc_range(&(base..(base + size.get())))
@@ -196,10 +245,10 @@ impl MemoryTracker {
         if !range.is_within(&self.mmio_range) {
             return Err(MemoryTrackerError::OutOfRange);
         }
-        if self.mmio_regions.iter().any(|r| range.overlaps(r)) {
+        if self.mmio_regions.overlaps(&range) {
             return Err(MemoryTrackerError::Overlaps);
         }
-        if self.mmio_regions.len() == self.mmio_regions.capacity() {
+        if self.mmio_regions.is_full() {
             return Err(MemoryTrackerError::Full);
         }
 
@@ -215,13 +264,32 @@ impl MemoryTracker {
             })?;
         }
 
-        if self.mmio_regions.try_push(range).is_some() {
+        if self.mmio_regions.push(range).is_err() {
             return Err(MemoryTrackerError::Full);
         }
 
         Ok(())
     }
 
+    /// Maps the given range of addresses like `map_mmio_range()` but also shares every granule it
+    /// covers with the MMIO guard immediately, instead of on the first access to each of them.
+    ///
+    /// This saves a translation fault per granule for small ranges known to be accessed in full.
+    /// As each shared granule is tracked on the heap, it should not be used for large ranges.
+    pub fn map_mmio_range_eagerly(&mut self, range: MemoryRange) -> Result<()> {
+        self.map_mmio_range(range.clone())?;
+        if get_mmio_guard().is_none() {
+            // The range was mapped as valid device memory, there is nothing to share.
+            return Ok(());
+        }
+
+        for base in granules(&range, self.mmio_sharer.granule) {
+            self.handle_mmio_fault(VirtualAddress(base))?;
+        }
+
+        Ok(())
+    }
+
     /// Checks that the memory region meets the following criteria:
     /// - It is within the range of the `MemoryTracker`.
     /// - It does not overlap with any previously allocated regions.
@@ -318,10 +386,7 @@ impl MemoryTracker {
     /// Handles translation fault for blocks flagged for lazy MMIO mapping by enabling the page
     /// table entry and MMIO guard mapping the block. Breaks apart a block entry if required.
     fn handle_mmio_fault(&mut self, addr: VirtualAddress) -> Result<()> {
-        let shared_range = self.mmio_sharer.share(addr)?;
-        self.map_lazy_mmio_as_valid(&shared_range)?;
-
-        Ok(())
+        handle_lazy_mmio_fault(self, addr.0)
     }
 
     /// Modify the PTEs corresponding to a given range from (invalid) "lazy MMIO" to valid MMIO.
@@ -371,6 +436,34 @@ impl MemoryTracker {
     }
 }
 
+impl LazyMmio for MemoryTracker {
+    type Error = MemoryTrackerError;
+
+    fn is_mapped(&self, addr: usize) -> bool {
+        let page = page_4kb_of(addr);
+        let page_range = (VirtualAddress(page)..VirtualAddress(page + PAGE_SIZE)).into();
+        self.page_table
+            .walk_range(&page_range, &|_: &VaRange, desc: &Descriptor, _: usize| {
+                let flags = desc.flags().ok_or(())?;
+                if flags.contains(MMIO_LAZY_MAP_FLAG | Attributes::VALID) {
+                    Ok(())
+                } else {
+                    Err(())
+                }
+            })
+            .is_ok()
+    }
+
+    fn share(&mut self, addr: usize) -> Result<MemoryRange> {
+        let shared_range = self.mmio_sharer.share(VirtualAddress(addr))?;
+        Ok(shared_range.start().0..shared_range.end().0)
+    }
+
+    fn map(&mut self, range: &MemoryRange) -> Result<()> {
+        self.map_lazy_mmio_as_valid(&get_va_range(range))
+    }
+}
+
 impl Drop for MemoryTracker {
     fn drop(&mut self) {
         set_dbm_enabled(false);
@@ -555,6 +648,15 @@ pub fn handle_translation_fault(far: VirtualAddress) -> result::Result<(), Handl
     Ok(memory.handle_mmio_fault(far)?)
 }
 
+/// Returns whether the given fault address register (FAR) is in an MMIO range of the memory
+/// tracker.
+#[inline]
+pub fn is_mmio_address(far: VirtualAddress) -> result::Result<bool, HandleExceptionError> {
+    let guard = MEMORY.try_lock().ok_or(HandleExceptionError::PageTableUnavailable)?;
+    let memory = guard.as_ref().ok_or(HandleExceptionError::PageTableNotInitialized)?;
+    Ok(memory.is_mmio(far.0))
+}
+
 /// Handles a permission fault with the given fault address register (FAR).
 #[inline]
 pub fn handle_permission_fault(far: VirtualAddress) -> result::Result<(), HandleExceptionError> {
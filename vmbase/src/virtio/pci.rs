@@ -14,7 +14,7 @@
 
 //! Functions to scan the PCI bus for VirtIO devices.
 
-use crate::memory::{MemoryTracker, MemoryTrackerError};
+use crate::memory::{MemoryTracker, MemoryTrackerError, SIZE_64KB};
 use alloc::boxed::Box;
 use core::fmt;
 use core::marker::PhantomData;
@@ -32,6 +32,9 @@ use virtio_drivers::{
 
 pub(super) static PCI_INFO: OnceBox<PciInfo> = OnceBox::new();
 
+/// Largest BAR range to be MMIO guard mapped in full when initializing the PCI.
+const MAX_EAGER_BAR_SIZE: usize = SIZE_64KB;
+
 /// PCI errors.
 #[derive(Debug, Clone)]
 pub enum PciError {
@@ -69,7 +72,14 @@ pub fn initialize(pci_info: PciInfo, memory: &mut MemoryTracker) -> Result<PciRo
 
     memory.map_mmio_range(pci_info.cam_range.clone()).map_err(PciError::CamMapFailed)?;
     let bar_range = pci_info.bar_range.start as usize..pci_info.bar_range.end as usize;
-    memory.map_mmio_range(bar_range).map_err(PciError::BarMapFailed)?;
+    // Small BAR windows are shared up front instead of on the first access to each granule but
+    // large ones are left lazy, as every shared granule is tracked on the heap.
+    if bar_range.len() <= MAX_EAGER_BAR_SIZE {
+        memory.map_mmio_range_eagerly(bar_range)
+    } else {
+        memory.map_mmio_range(bar_range)
+    }
+    .map_err(PciError::BarMapFailed)?;
 
     // Safety: This is the only place where we call make_pci_root, and `PCI_INFO.set` above will
     // panic if it is called a second time.